@@ -1,44 +1,37 @@
 // Copyright © 2024 Navarrotech
 
-use std::path::PathBuf;
+use std::path::Path;
 
-use crate::cli::common::get_comment_type;
+use crate::cli::common::comment_lines;
+use crate::cli::common::get_comment_syntax;
 use crate::cli::common::get_copyright_header;
-use crate::cli::common::get_file_type;
+use crate::error::AnubisError;
 use crate::schema::AnubisSchema;
+use crate::vfs::{get_file_name, write_generated_file, FileClass};
 
-pub fn write_automatron(schema: &AnubisSchema, content: &String, file_path: &PathBuf) {
+pub fn write_automatron(
+    schema: &AnubisSchema,
+    content: &String,
+    file_path: &Path,
+) -> Result<(), AnubisError> {
     let mut super_content = content.clone();
 
     // Get file name
-    let file_name = file_path
-        .file_name()
-        .expect("Invalid file path")
-        .to_string_lossy()
-        .to_string();
+    let file_name = get_file_name(file_path)?;
 
-    let file_type = get_file_type(&file_name);
-
-    // Write all parent directories in the path if they don't exist
-    std::fs::create_dir_all(file_path.parent().unwrap()).expect(&format!(
-        "Unable to create parent directories for {file_path}",
-        file_path = file_path.display()
-    ));
-
-    let comment_type = get_comment_type(&file_name);
     let copyright = get_copyright_header(schema, &file_name);
-    let preamble = format!(
-"{comment} ////////////////////////////////////////////
-{comment} // !! AUTO GENERATED FILE, DO NOT EDIT !! //
-{comment} ////////////////////////////////////////////
-
-{comment} This is a generated automatron file by Anubis. 
-{comment} Automatrons are files that are 100% auto-generated regularly by Anubis.
-{comment} It is not safe to edit this file directly, as your changes are extremly likely to be overwritten.",
-        comment = comment_type
+    let preamble = comment_lines(
+        &file_name,
+        "////////////////////////////////////////////
+// !! AUTO GENERATED FILE, DO NOT EDIT !! //
+////////////////////////////////////////////
+
+This is a generated automatron file by Anubis. 
+Automatrons are files that are 100% auto-generated regularly by Anubis.
+It is not safe to edit this file directly, as your changes are extremly likely to be overwritten.",
     );
 
-    if file_type != "md" && file_type != "html" && file_type != "json" && file_type != "gitignore" {
+    if get_comment_syntax(&file_name).is_some() {
         super_content = format!(
             "{copyright}\n{preamble}\n{content}",
             preamble = preamble,
@@ -48,29 +41,29 @@ pub fn write_automatron(schema: &AnubisSchema, content: &String, file_path: &Pat
     }
 
     // Write the content to the file
-    std::fs::write(file_path, super_content).expect(&format!(
-        "Unable to write {file_name} file",
-        file_name = file_name
-    ));
+    write_generated_file(FileClass::Automatron, file_path, &super_content)
 }
 
 #[cfg(test)]
 mod automatrons {
     use super::*;
+    use std::path::PathBuf;
     use tempfile::tempdir;
 
     fn generate_temp_file(content: &String, file_path: &PathBuf) -> PathBuf {
         let temp_directory = tempdir().unwrap().into_path();
 
-        let mut test_schema = AnubisSchema::default();
-        test_schema.project_name = "Anubis Test".to_string();
-        test_schema.copyright_header = String::from("Copyright © {YYYY} Navarrotech");
-        test_schema.copyright_header_formatted = String::from("Copyright © 2024 Navarrotech");
-        test_schema.install_directory = temp_directory.clone();
+        let test_schema = AnubisSchema {
+            project_name: "Anubis Test".to_string(),
+            copyright_header: String::from("Copyright © {YYYY} Navarrotech"),
+            copyright_header_formatted: String::from("Copyright © 2024 Navarrotech"),
+            install_directory: temp_directory.clone(),
+            ..AnubisSchema::default()
+        };
 
         let path_upgraded = temp_directory.clone().join(file_path);
 
-        write_automatron(&test_schema, &content, &path_upgraded);
+        write_automatron(&test_schema, content, &path_upgraded).unwrap();
 
         path_upgraded
     }
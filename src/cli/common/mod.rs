@@ -2,49 +2,164 @@
 
 use crate::schema::AnubisSchema;
 
-pub fn get_copyright_header(schema: &AnubisSchema, file_name: &String) -> String {
+pub fn get_copyright_header(schema: &AnubisSchema, file_name: &str) -> String {
     if schema.copyright_header_formatted.is_empty() {
         return String::from("");
     }
 
-    let comment_type = get_comment_type(file_name);
-
-    if !comment_type.is_empty() {
-        return format!(
-            "{comment} {copyright}\n",
-            comment = comment_type,
-            copyright = schema.copyright_header_formatted
-        );
+    match get_comment_syntax(file_name) {
+        Some(_) => format!(
+            "{copyright}\n",
+            copyright = comment_lines(file_name, &schema.copyright_header_formatted)
+        ),
+        None => String::from(""),
     }
-
-    return String::from("");
 }
 
-pub fn get_file_type(file_name: &String) -> &str {
+pub fn get_file_type(file_name: &str) -> &str {
     // Determine the file type (JSON, YAML, JS, RS, etc.)
-    let file_type: &str = match file_name.split('.').last() {
+    let file_type: &str = match file_name.split('.').next_back() {
         Some(file_type) => file_type,
         None => "txt",
     };
 
-    return file_type;
+    file_type
 }
 
-pub fn get_comment_type(file_name: &String) -> String {
-    // Determine the file type (JSON, YAML, JS, RS, etc.)
-    let file_type = get_file_type(file_name);
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommentSyntax {
+    // i.e. `# comment`
+    Line(&'static str),
+    // i.e. `<!-- comment -->`
+    Block(&'static str, &'static str),
+}
+
+// How to write a comment in each file type Anubis generates
+// JSON can't hold comments, its provenance is tracked in .anubis/manifest.json instead
+pub fn get_comment_syntax(file_name: &str) -> Option<CommentSyntax> {
+    // Files that are known by their whole name
+    match file_name {
+        "Dockerfile" | "Makefile" | "robots.txt" => return Some(CommentSyntax::Line("#")),
+        _ => (),
+    }
 
-    match file_type {
-        // Yaml
-        "yml" | "yaml" => return String::from("#"),
+    match get_file_type(file_name) {
+        // Yaml, Toml, ignore files, shell & config files
+        "yml" | "yaml" | "toml" | "gitignore" | "dockerignore" | "conf" | "env" | "sh" => {
+            Some(CommentSyntax::Line("#"))
+        }
         // Rust
-        "rs" => return String::from("//"),
+        "rs" => Some(CommentSyntax::Line("//")),
         // Javascript & Typescript
-        "js" | "ts" | "tsx" => return String::from("//"),
+        "js" | "cjs" | "mjs" | "jsx" | "ts" | "tsx" => Some(CommentSyntax::Line("//")),
         // Protobuf
-        "proto" => return String::from("//"),
-        _ => (),
+        "proto" => Some(CommentSyntax::Line("//")),
+        // Sass, both the indented and the scss syntax
+        "sass" | "scss" => Some(CommentSyntax::Line("//")),
+        // SQL
+        "sql" => Some(CommentSyntax::Line("--")),
+        // CSS
+        "css" => Some(CommentSyntax::Block("/*", "*/")),
+        // HTML, Markdown & XML
+        "html" | "htm" | "md" | "xml" | "svg" => Some(CommentSyntax::Block("<!--", "-->")),
+        _ => None,
     }
+}
+
+// Turns every line of `text` into a comment, blank lines are kept blank
+pub fn comment_lines(file_name: &str, text: &str) -> String {
+    let Some(syntax) = get_comment_syntax(file_name) else {
+        return String::from("");
+    };
+
+    text.lines()
+        .map(|line| match (line.is_empty(), syntax) {
+            (true, _) => String::new(),
+            (false, CommentSyntax::Line(start)) => format!("{start} {line}"),
+            (false, CommentSyntax::Block(start, end)) => format!("{start} {line} {end}"),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
 
-    return String::from("");
+// Converts snake_case model names into PascalCase type names (i.e. "user_preferences" -> "UserPreferences")
+pub fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut characters = part.chars();
+            match characters.next() {
+                Some(first) => first.to_uppercase().chain(characters).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+// Postgres identifiers are always quoted, names like "user" are reserved words
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod common_tests {
+    use super::*;
+
+    #[test]
+    fn every_generated_file_type_has_a_comment_syntax() {
+        for file_name in [
+            "Anubis.yaml",
+            ".gitlab-ci.yml",
+            "Cargo.toml",
+            "Dockerfile",
+            ".dockerignore",
+            ".gitignore",
+            "nginx.conf",
+            "robots.txt",
+            "main.rs",
+            ".eslintrc.cjs",
+            "main.tsx",
+            "root.proto",
+            "index.sass",
+            "bulma.scss",
+            "0001_init.sql",
+            "index.css",
+            "index.html",
+            "README.md",
+        ] {
+            assert!(
+                get_comment_syntax(file_name).is_some(),
+                "{file_name} should have a comment syntax"
+            );
+        }
+
+        assert_eq!(get_comment_syntax("package.json"), None);
+    }
+
+    #[test]
+    fn comments_use_the_file_syntax() {
+        assert_eq!(comment_lines("a.rs", "one\n\ntwo"), "// one\n\n// two");
+        assert_eq!(comment_lines("a.sql", "one"), "-- one");
+        assert_eq!(comment_lines("a.css", "one"), "/* one */");
+        assert_eq!(comment_lines("index.html", "one"), "<!-- one -->");
+        assert_eq!(comment_lines("a.json", "one"), "");
+    }
+
+    #[test]
+    fn copyright_headers_are_commented() {
+        let schema = AnubisSchema {
+            copyright_header_formatted: String::from("Copyright © 2024 Bank"),
+            ..AnubisSchema::default()
+        };
+
+        assert_eq!(
+            get_copyright_header(&schema, "index.html"),
+            "<!-- Copyright © 2024 Bank -->\n"
+        );
+        assert_eq!(
+            get_copyright_header(&schema, "Dockerfile"),
+            "# Copyright © 2024 Bank\n"
+        );
+        assert_eq!(get_copyright_header(&schema, "package.json"), "");
+    }
 }
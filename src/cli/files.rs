@@ -0,0 +1,95 @@
+// Copyright © 2024 Navarrotech
+
+// Lib
+use clap::Args;
+
+// Core structs
+use crate::error::AnubisError;
+use crate::logger::{info, warning};
+use crate::manifest::{get_edited_automatrons, Manifest};
+use crate::schema::AnubisSchema;
+
+#[derive(Args)]
+pub struct FilesArgs {
+    #[clap(short = 'd', long, default_value = "")]
+    pub directory: String,
+}
+
+// Lists every file Anubis owns, and whether it was edited since Anubis last wrote it
+pub fn list_files(schema: &AnubisSchema) -> Result<(), AnubisError> {
+    let manifest = Manifest::read(&schema.install_directory)?;
+    if manifest.files.is_empty() {
+        info!("Anubis doesn't own any files yet, run `cargo anubis generate` first");
+        return Ok(());
+    }
+
+    for line in create_file_list(schema, &manifest) {
+        println!("{line}");
+    }
+
+    let edited_automatrons = get_edited_automatrons(&schema.install_directory)?;
+    if !edited_automatrons.is_empty() {
+        warning!(
+            "{} automatron(s) were edited by hand, generate will refuse to overwrite them without `--force` or `--backup`",
+            edited_automatrons.len()
+        );
+    }
+
+    Ok(())
+}
+
+pub fn create_file_list(schema: &AnubisSchema, manifest: &Manifest) -> Vec<String> {
+    manifest
+        .files
+        .iter()
+        .map(|(path, entry)| {
+            format!(
+                "{class:<10} {status:<10} {path}",
+                class = entry.class,
+                status = manifest.get_status(&schema.install_directory, path),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod files_tests {
+    use super::*;
+    use crate::manifest::update_manifest;
+    use crate::vfs::{start_recording, stop_recording, write_generated_file, FileClass};
+    use tempfile::tempdir;
+
+    #[test]
+    fn lists_files_with_their_status() {
+        let schema = AnubisSchema {
+            install_directory: tempdir().unwrap().into_path(),
+            ..AnubisSchema::default()
+        };
+
+        start_recording(false);
+        write_generated_file(
+            FileClass::Automatron,
+            &schema.install_directory.join("api/src/main.rs"),
+            "fn main() {}",
+        )
+        .unwrap();
+        write_generated_file(
+            FileClass::Relic,
+            &schema.install_directory.join("api/Cargo.toml"),
+            "[package]",
+        )
+        .unwrap();
+        update_manifest(&schema.install_directory, &stop_recording(), "generate").unwrap();
+
+        std::fs::write(schema.install_directory.join("api/Cargo.toml"), "[edited]").unwrap();
+
+        let manifest = Manifest::read(&schema.install_directory).unwrap();
+        assert_eq!(
+            create_file_list(&schema, &manifest),
+            vec![
+                "relic      modified   api/Cargo.toml",
+                "automatron unmodified api/src/main.rs",
+            ]
+        );
+    }
+}
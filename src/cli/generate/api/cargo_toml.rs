@@ -0,0 +1,81 @@
+// Copyright © 2024 Navarrotech
+
+use crate::schema::AnubisSchema;
+
+pub fn create_cargo_toml(schema: &AnubisSchema) -> String {
+    // Cargo refuses a manifest with an empty version
+    let version = match schema.version.trim() {
+        "" => "0.1.0",
+        version => version,
+    };
+
+    format!(
+        r#"
+[package]
+name = "api"
+version = "{version}"
+description = "{description}"
+edition = "2021"
+publish = false
+
+[dependencies]
+argon2 = {{ version = "0.5", features = ["std"] }}
+chrono = {{ version = "0.4", features = ["serde"] }}
+rocket = {{ version = "0.5.1", features = ["json"] }}
+rust_decimal = {{ version = "1", features = ["serde-with-str"] }}
+serde = {{ version = "1.0", features = ["derive"] }}
+serde_json = "1"
+sqlx = {{ version = "0.7", features = ["runtime-tokio", "postgres", "chrono", "migrate", "rust_decimal", "uuid", "json"] }}
+uuid = {{ version = "1", features = ["v4", "serde"] }}
+"#,
+        description = schema.description.replace('"', "\\\""),
+    )
+}
+
+pub fn create_rocket_toml() -> String {
+    String::from(
+        r#"
+# https://rocket.rs/guide/v0.5/configuration/
+[default]
+address = "0.0.0.0"
+port = 8000
+
+[default.limits]
+json = "1 MiB"
+
+[release]
+log_level = "normal"
+"#,
+    )
+}
+
+#[cfg(test)]
+mod check_cargo_toml {
+    use super::*;
+    use crate::schema::AnubisSchema;
+
+    #[test]
+    fn escapes_quotes_in_the_description() {
+        let test_schema = AnubisSchema {
+            description: String::from("The \"best\" project"),
+            ..AnubisSchema::default()
+        };
+
+        let content = create_cargo_toml(&test_schema);
+
+        assert!(content.contains("description = \"The \\\"best\\\" project\""));
+    }
+
+    #[test]
+    fn falls_back_to_a_version_when_none_is_set() {
+        let test_schema = AnubisSchema {
+            version: String::new(),
+            ..AnubisSchema::default()
+        };
+
+        let content = create_cargo_toml(&test_schema);
+
+        assert!(content.contains("version = \"0.1.0\""));
+        assert!(!content.contains("version = \"\""));
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright © 2024 Navarrotech
+
+use cargo_toml::create_cargo_toml;
+use cargo_toml::create_rocket_toml;
+use routes::create_model_routes;
+use routes::create_routes_mod;
+use routes::get_route_models;
+use server::create_config_rs;
+use server::create_custom_rs;
+use server::create_errors_rs;
+use server::create_hashing_rs;
+use server::create_main_rs;
+use server::create_migrations_rs;
+use server::create_money_rs;
+use server::create_patch_rs;
+use server::create_utility_mod;
+
+use crate::automatrons::write::write_automatron;
+use crate::error::AnubisError;
+use crate::relics::write::write_relic;
+use crate::schema::AnubisSchema;
+
+// Generates the Rocket backend under api/
+// Wiring is written as automatrons, the places you're expected to extend are relics
+pub fn generate_api(schema: &AnubisSchema) -> Result<(), AnubisError> {
+    let api_directory = schema.install_directory.join("api");
+
+    // Cargo.toml
+    write_relic(
+        schema,
+        &create_cargo_toml(schema),
+        &api_directory.join("Cargo.toml"),
+    )?;
+
+    // Rocket.toml
+    write_relic(
+        schema,
+        &create_rocket_toml(),
+        &api_directory.join("Rocket.toml"),
+    )?;
+
+    // Custom routes, fairings and state
+    write_relic(
+        schema,
+        &create_custom_rs(),
+        &api_directory.join("src/custom.rs"),
+    )?;
+
+    write_automatron(
+        schema,
+        &create_main_rs(),
+        &api_directory.join("src/main.rs"),
+    )?;
+
+    write_automatron(
+        schema,
+        &create_config_rs(schema),
+        &api_directory.join("src/config.rs"),
+    )?;
+
+    write_automatron(
+        schema,
+        &create_utility_mod(),
+        &api_directory.join("src/utility/mod.rs"),
+    )?;
+
+    write_automatron(
+        schema,
+        &create_errors_rs(),
+        &api_directory.join("src/utility/errors.rs"),
+    )?;
+
+    write_automatron(
+        schema,
+        &create_migrations_rs(),
+        &api_directory.join("src/utility/migrations.rs"),
+    )?;
+
+    write_automatron(
+        schema,
+        &create_hashing_rs(),
+        &api_directory.join("src/utility/hashing.rs"),
+    )?;
+
+    write_automatron(
+        schema,
+        &create_money_rs(),
+        &api_directory.join("src/utility/money.rs"),
+    )?;
+
+    write_automatron(
+        schema,
+        &create_patch_rs(),
+        &api_directory.join("src/utility/patch.rs"),
+    )?;
+
+    write_automatron(
+        schema,
+        &create_routes_mod(schema),
+        &api_directory.join("src/routes/mod.rs"),
+    )?;
+
+    for model in get_route_models(schema) {
+        write_automatron(
+            schema,
+            &create_model_routes(schema, model),
+            &api_directory.join(format!("src/routes/{}.rs", model.name)),
+        )?;
+    }
+
+    Ok(())
+}
+
+// Postgres friendly database name for the project (i.e. "My Project" -> "my_project")
+pub fn get_database_name(schema: &AnubisSchema) -> String {
+    schema
+        .project_name
+        .to_lowercase()
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() {
+                character
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+pub mod cargo_toml;
+pub mod routes;
+pub mod server;
@@ -0,0 +1,937 @@
+// Copyright © 2024 Navarrotech
+
+use chrono::Datelike;
+
+use crate::cli::common::{quote_identifier, to_pascal_case};
+use crate::models::{JoinTable, ModelFields, ModelKind, Models, UseOption};
+use crate::relationships::{ReferentialAction, RelationshipGraph};
+use crate::schema::AnubisSchema;
+
+// Field names that need to be written as raw identifiers in Rust
+const RUST_KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use",
+    "where", "while", "yield", "try",
+];
+
+pub fn create_routes_mod(schema: &AnubisSchema) -> String {
+    if get_route_models(schema).next().is_none() {
+        return String::from(
+            r#"
+pub fn routes() -> Vec<rocket::Route> {
+    vec![]
+}
+"#,
+        );
+    }
+
+    let modules = get_route_models(schema)
+        .map(|model| format!("pub mod {};", model.name))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let routes = get_route_models(schema)
+        .map(|model| format!("    routes.extend({}::routes());", model.name))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    format!(
+        r#"
+{modules}
+
+pub fn routes() -> Vec<rocket::Route> {{
+    let mut routes = vec![];
+{routes}
+    routes
+}}
+"#
+    )
+}
+
+// Join models are only reachable through the attach and detach routes of the model linking them
+// With `json_api: false` there are no JSON routes at all, models are only synced over protobufs
+pub fn get_route_models(schema: &AnubisSchema) -> impl Iterator<Item = &Models> {
+    schema
+        .models
+        .iter()
+        .filter(|model| schema.settings.json_api && !schema.is_join_model(&model.name))
+}
+
+// One module per model, with JSON CRUD handlers mounted under /api/<model>
+pub fn create_model_routes(schema: &AnubisSchema, model: &Models) -> String {
+    let name = &model.name;
+    let struct_name = to_pascal_case(name);
+    let table = quote_identifier(name);
+
+    // Private fields are never selected, so they can't leak into responses
+    let public_fields: Vec<&ModelFields> = model
+        .fields
+        .iter()
+        .filter(|field| field.is_column() && !field.is_private())
+        .collect();
+    let input_fields: Vec<&ModelFields> = model
+        .fields
+        .iter()
+        .filter(|field| field.is_column() && !field.is_generated())
+        .collect();
+    let update_fields: Vec<&ModelFields> = input_fields
+        .iter()
+        .filter(|field| !field.primary_key)
+        .copied()
+        .collect();
+
+    let columns = public_fields
+        .iter()
+        .map(|field| quote_identifier(&field.name))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let mut response_struct = String::new();
+    for field in public_fields.iter() {
+        response_struct.push_str(&format!(
+            "    pub {}: {},\n",
+            rust_identifier(&field.name),
+            if field.is_nullable() {
+                format!("Option<{}>", field.kind.rust_type())
+            } else {
+                field.kind.rust_type().to_string()
+            }
+        ));
+    }
+
+    let mut create_struct = String::new();
+    for field in input_fields.iter() {
+        create_struct.push_str(&format!(
+            "    pub {}: {},\n",
+            rust_identifier(&field.name),
+            if field.required {
+                field.kind.rust_type().to_string()
+            } else {
+                format!("Option<{}>", field.kind.rust_type())
+            }
+        ));
+    }
+
+    // Nullable fields tell an omitted value (None) apart from an explicit null (Some(None))
+    let mut update_struct = String::new();
+    for field in update_fields.iter() {
+        if field.is_nullable() {
+            update_struct.push_str(
+                "    #[serde(default, deserialize_with = \"crate::utility::patch::deserialize_present\")]\n",
+            );
+            update_struct.push_str(&format!(
+                "    pub {}: Option<Option<{}>>,\n",
+                rust_identifier(&field.name),
+                field.kind.rust_type()
+            ));
+        } else {
+            update_struct.push_str(&format!(
+                "    pub {}: Option<{}>,\n",
+                rust_identifier(&field.name),
+                field.kind.rust_type()
+            ));
+        }
+    }
+
+    // Every column is written on create, generated values included
+    let insert_fields: Vec<&ModelFields> = model
+        .fields
+        .iter()
+        .filter(|field| field.is_column())
+        .collect();
+    let insert_sql = if insert_fields.is_empty() {
+        format!("INSERT INTO {table} DEFAULT VALUES RETURNING {columns}")
+    } else {
+        format!(
+            "INSERT INTO {table} ({insert_columns}) VALUES ({placeholders}) RETURNING {columns}",
+            insert_columns = insert_fields
+                .iter()
+                .map(|field| quote_identifier(&field.name))
+                .collect::<Vec<String>>()
+                .join(", "),
+            placeholders = (1..=insert_fields.len())
+                .map(|index| format!("${index}"))
+                .collect::<Vec<String>>()
+                .join(", "),
+        )
+    };
+    let insert_binds = insert_fields
+        .iter()
+        .map(|field| format!("        .bind({})\n", create_bind(field)))
+        .collect::<String>();
+
+    let mut handlers = vec![format!("list_{name}"), format!("create_{name}")];
+
+    let mut content = format!(
+        r#"
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::{{Deserialize, Serialize}};
+use sqlx::PgPool;
+
+use crate::utility::errors::ApiError;
+
+// Pagination limits for list requests
+const DEFAULT_TAKE: i64 = 50;
+const MAX_TAKE: i64 = 500;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct {struct_name} {{
+{response_struct}}}
+
+#[derive(Debug, Deserialize)]
+pub struct Create{struct_name} {{
+{create_struct}}}
+
+#[get("/{name}?<skip>&<take>")]
+pub async fn list_{name}(
+    db: &State<PgPool>,
+    skip: Option<i64>,
+    take: Option<i64>,
+) -> Result<Json<Vec<{struct_name}>>, ApiError> {{
+    let rows = sqlx::query_as::<_, {struct_name}>({list_sql:?})
+        .bind(skip.unwrap_or(0).max(0))
+        .bind(take.unwrap_or(DEFAULT_TAKE).clamp(1, MAX_TAKE))
+        .fetch_all(db.inner())
+        .await?;
+
+    Ok(Json(rows))
+}}
+
+#[post("/{name}", format = "json", data = "<body>")]
+pub async fn create_{name}(
+    db: &State<PgPool>,
+    body: Json<Create{struct_name}>,
+) -> Result<(Status, Json<{struct_name}>), ApiError> {{
+    let body = body.into_inner();
+
+    let row = sqlx::query_as::<_, {struct_name}>({insert_sql:?})
+{insert_binds}        .fetch_one(db.inner())
+        .await?;
+
+    Ok((Status::Created, Json(row)))
+}}
+"#,
+        list_sql = match model.primary_key() {
+            Some(primary_key) => format!(
+                "SELECT {columns} FROM {table} ORDER BY {order} OFFSET $1 LIMIT $2",
+                order = quote_identifier(&primary_key.name)
+            ),
+            None => format!("SELECT {columns} FROM {table} OFFSET $1 LIMIT $2"),
+        },
+    );
+
+    // Models without a primary key can only be listed and created
+    if let Some(primary_key) = model.primary_key() {
+        let key_column = quote_identifier(&primary_key.name);
+        let key_type = primary_key.kind.rust_type();
+
+        content.push_str(&format!(
+            r#"
+#[get("/{name}/<id>")]
+pub async fn get_{name}(db: &State<PgPool>, id: {key_type}) -> Result<Json<{struct_name}>, ApiError> {{
+    let row = sqlx::query_as::<_, {struct_name}>({get_sql:?})
+        .bind(id)
+        .fetch_optional(db.inner())
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(Json(row))
+}}
+{delete_comment}
+#[delete("/{name}/<id>")]
+pub async fn delete_{name}(db: &State<PgPool>, id: {key_type}) -> Result<Status, ApiError> {{
+    let result = sqlx::query({delete_sql:?})
+        .bind(id)
+        .execute(db.inner())
+        .await{delete_error}?;
+
+    if result.rows_affected() == 0 {{
+        return Err(ApiError::NotFound);
+    }}
+
+    Ok(Status::NoContent)
+}}
+"#,
+            get_sql = format!("SELECT {columns} FROM {table} WHERE {key_column} = $1"),
+            delete_sql = format!("DELETE FROM {table} WHERE {key_column} = $1"),
+            delete_comment = create_delete_comment(schema, model),
+            delete_error = create_delete_error(schema, model),
+        ));
+        handlers.push(format!("get_{name}"));
+        handlers.push(format!("delete_{name}"));
+
+        // Omitted fields are left untouched, so a PATCH only needs to send what changed
+        // Nullable fields are also bound with whether they were sent, so `null` clears them
+        let mut assignments = vec![];
+        let mut update_binds = String::new();
+        let mut index = 2;
+        for field in update_fields.iter() {
+            let column = quote_identifier(&field.name);
+            if field.is_nullable() {
+                assignments.push(format!(
+                    "{column} = CASE WHEN ${index} THEN ${value} ELSE {column} END",
+                    value = index + 1
+                ));
+                update_binds.push_str(&format!(
+                    "        .bind(body.{}.is_some())\n",
+                    rust_identifier(&field.name)
+                ));
+                index += 2;
+            } else {
+                assignments.push(format!("{column} = COALESCE(${index}, {column})"));
+                index += 1;
+            }
+            update_binds.push_str(&format!("        .bind({})\n", update_bind(field)));
+        }
+        for field in model.fields.iter() {
+            if field.use_method == Some(UseOption::UpdatedAt) {
+                assignments.push(format!(
+                    "{column} = ${index}",
+                    column = quote_identifier(&field.name),
+                ));
+                update_binds.push_str("        .bind(chrono::Utc::now())\n");
+                index += 1;
+            }
+        }
+
+        if !assignments.is_empty() {
+            content.push_str(&format!(
+                r#"
+#[derive(Debug, Deserialize)]
+pub struct Update{struct_name} {{
+{update_struct}}}
+
+#[patch("/{name}/<id>", format = "json", data = "<body>")]
+pub async fn update_{name}(
+    db: &State<PgPool>,
+    id: {key_type},
+    body: Json<Update{struct_name}>,
+) -> Result<Json<{struct_name}>, ApiError> {{
+    let body = body.into_inner();
+
+    let row = sqlx::query_as::<_, {struct_name}>({update_sql:?})
+        .bind(id)
+{update_binds}        .fetch_optional(db.inner())
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(Json(row))
+}}
+"#,
+                update_sql = format!(
+                    "UPDATE {table} SET {assignments} WHERE {key_column} = $1 RETURNING {columns}",
+                    assignments = assignments.join(", ")
+                ),
+            ));
+            handlers.push(format!("update_{name}"));
+        }
+    }
+
+    for join_table in schema.get_join_tables() {
+        if join_table.model.name == model.name {
+            content.push_str(&create_link_routes(&join_table, &mut handlers));
+        }
+    }
+
+    content.push_str(&format!(
+        r#"
+pub fn routes() -> Vec<rocket::Route> {{
+    routes![{handlers}]
+}}
+"#,
+        handlers = handlers.join(", ")
+    ));
+
+    content
+}
+
+// What else a delete touches, following the `on_delete` of every foreign key pointing at the model
+fn create_delete_comment(schema: &AnubisSchema, model: &Models) -> String {
+    let graph = RelationshipGraph::build(schema);
+    let mut deletes = vec![];
+    let mut clears = vec![];
+
+    for relationship in graph.get_referencing(&model.name) {
+        let Some(field) = relationship.field else {
+            continue;
+        };
+        let column = format!("{}.{}", relationship.model.name, field.name);
+        match relationship.on_delete {
+            ReferentialAction::Cascade => deletes.push(column),
+            ReferentialAction::SetNull => clears.push(column),
+            ReferentialAction::Restrict => (),
+        }
+    }
+
+    for join_table in schema.get_join_tables() {
+        if join_table.model.name == model.name || join_table.linked.name == model.name {
+            deletes.push(join_table.name.clone());
+        }
+    }
+    // Both sides of a link can name the same join table
+    deletes.sort();
+    deletes.dedup();
+    clears.sort();
+
+    let mut comment = String::new();
+    if !deletes.is_empty() {
+        comment.push_str(&format!(
+            "\n// Also deletes the rows pointing at it from: {}",
+            deletes.join(", ")
+        ));
+    }
+    if !clears.is_empty() {
+        comment.push_str(&format!(
+            "\n// Sets these back to null where they point at it: {}",
+            clears.join(", ")
+        ));
+    }
+
+    comment
+}
+
+// Restricted foreign keys refuse the delete while rows still point at the model
+fn create_delete_error(schema: &AnubisSchema, model: &Models) -> String {
+    let graph = RelationshipGraph::build(schema);
+    let restricted: Vec<String> = graph
+        .get_referencing(&model.name)
+        .iter()
+        .filter(|relationship| relationship.on_delete == ReferentialAction::Restrict)
+        .filter_map(|relationship| {
+            relationship
+                .field
+                .map(|field| format!("{}.{}", relationship.model.name, field.name))
+        })
+        .collect();
+
+    if restricted.is_empty() {
+        return String::new();
+    }
+
+    let message = format!(
+        "Can't delete this {}, it's still linked from {}",
+        model.name,
+        restricted.join(", ")
+    );
+
+    format!(
+        r#"
+        .map_err(|error| match &error {{
+            sqlx::Error::Database(database_error) if database_error.is_foreign_key_violation() => {{
+                ApiError::Conflict(String::from({message:?}))
+            }}
+            _ => ApiError::from(error),
+        }})"#
+    )
+}
+
+// Many-to-many links are listed, attached and detached under /api/<model>/<id>/<field>
+// Attaching a pair that's already linked updates its extra columns instead of failing
+fn create_link_routes(join_table: &JoinTable, handlers: &mut Vec<String>) -> String {
+    let name = &join_table.model.name;
+    let field = &join_table.field.name;
+    let struct_name = format!("Attach{}{}", to_pascal_case(name), to_pascal_case(field));
+    let table = quote_identifier(&join_table.name);
+    let model_column = quote_identifier(&join_table.model_column());
+    let linked_column = quote_identifier(&join_table.linked_column());
+    let key_type = join_table.model_key.kind.rust_type();
+    let linked_type = join_table.linked_key.kind.rust_type();
+
+    let extra_fields = join_table.extra_fields();
+    let input_fields: Vec<&ModelFields> = extra_fields
+        .iter()
+        .filter(|field| !field.is_generated())
+        .copied()
+        .collect();
+
+    let mut columns = vec![model_column.clone(), linked_column.clone()];
+    let mut binds = String::new();
+    for field in extra_fields.iter() {
+        columns.push(quote_identifier(&field.name));
+        binds.push_str(&format!("        .bind({})\n", create_bind(field)));
+    }
+
+    // Generated values like `use: created_at` keep what they were set to when first attached
+    let updated_fields: Vec<&ModelFields> = extra_fields
+        .iter()
+        .filter(|field| !field.is_generated() || field.use_method == Some(UseOption::UpdatedAt))
+        .copied()
+        .collect();
+    let conflict = match updated_fields.is_empty() {
+        true => String::from("DO NOTHING"),
+        false => format!(
+            "DO UPDATE SET {}",
+            updated_fields
+                .iter()
+                .map(|field| {
+                    let column = quote_identifier(&field.name);
+                    format!("{column} = EXCLUDED.{column}")
+                })
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+    };
+    let attach_sql = format!(
+        "INSERT INTO {table} ({}) VALUES ({}) ON CONFLICT ({model_column}, {linked_column}) {conflict}",
+        columns.join(", "),
+        (1..=columns.len())
+            .map(|index| format!("${index}"))
+            .collect::<Vec<String>>()
+            .join(", "),
+    );
+
+    let mut attach_struct = String::new();
+    for field in input_fields.iter() {
+        attach_struct.push_str(&format!(
+            "    pub {}: {},\n",
+            rust_identifier(&field.name),
+            if field.required {
+                field.kind.rust_type().to_string()
+            } else {
+                format!("Option<{}>", field.kind.rust_type())
+            }
+        ));
+    }
+
+    // Links without extra columns to fill in don't take a body
+    let (attach_route, attach_body, attach_struct) = match input_fields.is_empty() {
+        true => (
+            format!("#[post(\"/{name}/<id>/{field}/<linked_id>\")]"),
+            String::new(),
+            String::new(),
+        ),
+        false => (
+            format!(
+                "#[post(\"/{name}/<id>/{field}/<linked_id>\", format = \"json\", data = \"<body>\")]"
+            ),
+            format!("    body: Json<{struct_name}>,\n"),
+            format!("\n#[derive(Debug, Deserialize)]\npub struct {struct_name} {{\n{attach_struct}}}\n"),
+        ),
+    };
+    let unpack_body = match input_fields.is_empty() {
+        true => "",
+        false => "    let body = body.into_inner();\n\n",
+    };
+
+    handlers.push(format!("list_{name}_{field}"));
+    handlers.push(format!("attach_{name}_{field}"));
+    handlers.push(format!("detach_{name}_{field}"));
+
+    format!(
+        r#"
+#[get("/{name}/<id>/{field}")]
+pub async fn list_{name}_{field}(
+    db: &State<PgPool>,
+    id: {key_type},
+) -> Result<Json<Vec<{linked_type}>>, ApiError> {{
+    let ids = sqlx::query_scalar::<_, {linked_type}>({list_sql:?})
+        .bind(id)
+        .fetch_all(db.inner())
+        .await?;
+
+    Ok(Json(ids))
+}}
+{attach_struct}
+{attach_route}
+pub async fn attach_{name}_{field}(
+    db: &State<PgPool>,
+    id: {key_type},
+    linked_id: {linked_type},
+{attach_body}) -> Result<Status, ApiError> {{
+{unpack_body}    sqlx::query({attach_sql:?})
+        .bind(id)
+        .bind(linked_id)
+{binds}        .execute(db.inner())
+        .await?;
+
+    Ok(Status::NoContent)
+}}
+
+#[delete("/{name}/<id>/{field}/<linked_id>")]
+pub async fn detach_{name}_{field}(
+    db: &State<PgPool>,
+    id: {key_type},
+    linked_id: {linked_type},
+) -> Result<Status, ApiError> {{
+    let result = sqlx::query({detach_sql:?})
+        .bind(id)
+        .bind(linked_id)
+        .execute(db.inner())
+        .await?;
+
+    if result.rows_affected() == 0 {{
+        return Err(ApiError::NotFound);
+    }}
+
+    Ok(Status::NoContent)
+}}
+"#,
+        list_sql = format!(
+            "SELECT {linked_column} FROM {table} WHERE {model_column} = $1 ORDER BY {linked_column}"
+        ),
+        detach_sql =
+            format!("DELETE FROM {table} WHERE {model_column} = $1 AND {linked_column} = $2"),
+    )
+}
+
+fn rust_identifier(name: &str) -> String {
+    if RUST_KEYWORDS.contains(&name) {
+        format!("r#{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+// The value bound for a column when a row is created
+fn create_bind(field: &ModelFields) -> String {
+    let value = format!("body.{}", rust_identifier(&field.name));
+
+    match field.use_method {
+        Some(UseOption::Uuid) if field.kind == ModelKind::Uuid => {
+            return String::from("uuid::Uuid::new_v4()")
+        }
+        Some(UseOption::Uuid) => return String::from("uuid::Uuid::new_v4().to_string()"),
+        Some(UseOption::CreatedAt) | Some(UseOption::UpdatedAt) => {
+            return String::from("chrono::Utc::now()")
+        }
+        _ => (),
+    }
+
+    let hashed = field.hash && field.kind == ModelKind::String;
+
+    if field.required {
+        return match hashed {
+            true => format!("crate::utility::hashing::hash(&{value})?"),
+            false => value,
+        };
+    }
+
+    let value = match hashed {
+        true => format!("{value}.as_deref().map(crate::utility::hashing::hash).transpose()?"),
+        false => value,
+    };
+
+    match get_default_literal(field) {
+        Some(default) => format!("{value}.unwrap_or_else(|| {default})"),
+        None => value,
+    }
+}
+
+// The value bound for a column when a row is updated, None keeps the current value
+fn update_bind(field: &ModelFields) -> String {
+    let value = match field.is_nullable() {
+        true => format!("body.{}.flatten()", rust_identifier(&field.name)),
+        false => format!("body.{}", rust_identifier(&field.name)),
+    };
+
+    if field.hash && field.kind == ModelKind::String {
+        return format!("{value}.as_deref().map(crate::utility::hashing::hash).transpose()?");
+    }
+
+    value
+}
+
+// Defaults are applied by the api, so they're checked against the field kind here
+fn get_default_literal(field: &ModelFields) -> Option<String> {
+    let default = field.default.as_ref()?;
+    if !field.kind.is_valid_default(default) {
+        return None;
+    }
+
+    match field.kind {
+        ModelKind::String => Some(format!("String::from({default:?})")),
+        ModelKind::Number => default.parse::<i32>().ok().map(|value| value.to_string()),
+        ModelKind::Float => default
+            .parse::<f64>()
+            .ok()
+            .map(|value| format!("{value:?}_f64")),
+        ModelKind::Boolean => default.parse::<bool>().ok().map(|value| value.to_string()),
+        // Written out exactly, so the generated code has nothing left to parse
+        ModelKind::Decimal { .. } => {
+            let default = default.trim();
+            let scale = default
+                .split_once('.')
+                .map_or(0, |(_, fraction)| fraction.len());
+            let mantissa = default.replace('.', "").parse::<i128>().ok()?;
+            Some(format!(
+                "rust_decimal::Decimal::from_i128_with_scale({mantissa}, {scale})"
+            ))
+        }
+        ModelKind::Date => {
+            let date = chrono::NaiveDate::parse_from_str(default, "%Y-%m-%d").ok()?;
+            Some(format!(
+                "chrono::NaiveDate::from_ymd_opt({}, {}, {}).unwrap_or_default()",
+                date.year(),
+                date.month(),
+                date.day()
+            ))
+        }
+        ModelKind::Uuid => Some(format!(
+            "uuid::Uuid::from_u128(0x{})",
+            default.replace('-', "").to_lowercase()
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod check_model_routes {
+    use super::*;
+    use crate::models::FormatChoice;
+
+    fn mock_user_model() -> Models {
+        Models {
+            name: String::from("user"),
+            fields: vec![
+                ModelFields {
+                    name: String::from("id"),
+                    use_method: Some(UseOption::Uuid),
+                    primary_key: true,
+                    required: true,
+                    ..Default::default()
+                },
+                ModelFields {
+                    name: String::from("password"),
+                    format: Some(FormatChoice::Password),
+                    hash: true,
+                    replicate: false,
+                    required: true,
+                    ..Default::default()
+                },
+                ModelFields {
+                    name: String::from("type"),
+                    default: Some(String::from("member")),
+                    ..Default::default()
+                },
+                ModelFields {
+                    name: String::from("updated_at"),
+                    kind: ModelKind::DateTime,
+                    use_method: Some(UseOption::UpdatedAt),
+                    required: true,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn private_fields_are_never_selected() {
+        let content = create_model_routes(&AnubisSchema::default(), &mock_user_model());
+
+        assert!(content.contains(
+            r#""SELECT \"id\", \"type\", \"updated_at\" FROM \"user\" WHERE \"id\" = $1""#
+        ));
+        assert!(content.contains(
+            "pub struct User {\n    pub id: String,\n    pub r#type: String,\n    pub updated_at: chrono::DateTime<chrono::Utc>,\n}"
+        ));
+        assert!(content.contains(".bind(crate::utility::hashing::hash(&body.password)?)"));
+    }
+
+    #[test]
+    fn create_fills_in_generated_values_and_defaults() {
+        let content = create_model_routes(&AnubisSchema::default(), &mock_user_model());
+
+        assert!(content.contains(".bind(uuid::Uuid::new_v4().to_string())"));
+        assert!(content.contains(".bind(body.r#type.unwrap_or_else(|| String::from(\"member\")))"));
+        assert!(content.contains("pub r#type: String,"));
+    }
+
+    #[test]
+    fn update_touches_updated_at() {
+        let content = create_model_routes(&AnubisSchema::default(), &mock_user_model());
+
+        assert!(content.contains(
+            r#"\"type\" = COALESCE($3, \"type\"), \"updated_at\" = $4 WHERE \"id\" = $1"#
+        ));
+        assert!(
+            content.contains("routes![list_user, create_user, get_user, delete_user, update_user]")
+        );
+    }
+
+    #[test]
+    fn update_can_clear_nullable_fields() {
+        let mut model = mock_user_model();
+        model.fields.insert(
+            2,
+            ModelFields {
+                name: String::from("nickname"),
+                required: false,
+                ..Default::default()
+            },
+        );
+
+        let content = create_model_routes(&AnubisSchema::default(), &model);
+
+        assert!(content.contains(
+            "    #[serde(default, deserialize_with = \"crate::utility::patch::deserialize_present\")]\n    pub nickname: Option<Option<String>>,"
+        ));
+        assert!(content.contains(
+            r#"\"nickname\" = CASE WHEN $3 THEN $4 ELSE \"nickname\" END, \"type\" = COALESCE($5, \"type\"), \"updated_at\" = $6"#
+        ));
+        assert!(content.contains(
+            "        .bind(body.nickname.is_some())\n        .bind(body.nickname.flatten())\n"
+        ));
+    }
+
+    #[test]
+    fn models_without_a_primary_key_can_only_be_listed_and_created() {
+        let model = Models {
+            name: String::from("event"),
+            ..Default::default()
+        };
+
+        let content = create_model_routes(&AnubisSchema::default(), &model);
+
+        assert!(content.contains("routes![list_event, create_event]"));
+        assert!(!content.contains("#[delete("));
+    }
+
+    #[test]
+    fn many_to_many_fields_get_attach_and_detach_routes() {
+        let schema = crate::cli::parse::parse_schema_str(
+            "
+project:
+  name: Test
+models:
+  transactions:
+    fields:
+      - name: id
+        primary_key: true
+      - name: tags
+        links: tag
+        through: transaction_tags
+  tag:
+    fields:
+      - name: id
+        kind: number
+        primary_key: true
+  transaction_tags:
+    fields:
+      - name: note
+        required: false
+",
+            std::path::PathBuf::from("/tmp"),
+        )
+        .unwrap();
+
+        let content = create_model_routes(&schema, &schema.models[0]);
+
+        assert!(content.contains("pub struct Transactions {\n    pub id: String,\n}"));
+        assert!(content.contains(
+            "#[post(\"/transactions/<id>/tags/<linked_id>\", format = \"json\", data = \"<body>\")]"
+        ));
+        assert!(content.contains(
+            r#""INSERT INTO \"transaction_tags\" (\"transactions_id\", \"tag_id\", \"note\") VALUES ($1, $2, $3) ON CONFLICT (\"transactions_id\", \"tag_id\") DO UPDATE SET \"note\" = EXCLUDED.\"note\"""#
+        ));
+        assert!(content
+            .contains("pub struct AttachTransactionsTags {\n    pub note: Option<String>,\n}"));
+        assert!(content.contains("linked_id: i32,"));
+        assert!(content.contains("routes![list_transactions, create_transactions, get_transactions, delete_transactions, list_transactions_tags, attach_transactions_tags, detach_transactions_tags]"));
+
+        // The join model is only reachable through the routes above
+        assert_eq!(
+            get_route_models(&schema)
+                .map(|model| model.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["transactions", "tag"]
+        );
+    }
+
+    #[test]
+    fn delete_comment_lists_each_join_table_once() {
+        let schema = crate::cli::parse::parse_schema_str(
+            "
+project:
+  name: Test
+models:
+  user:
+    fields:
+      - name: id
+        primary_key: true
+      - name: tags
+        links: tag
+        through: user_tags
+      - name: roles
+        links: role
+        through: user_roles
+  tag:
+    fields:
+      - name: id
+        primary_key: true
+      - name: users
+        links: user
+        through: user_tags
+  role:
+    fields:
+      - name: id
+        primary_key: true
+",
+            std::path::PathBuf::from("/tmp"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            create_delete_comment(&schema, &schema.models[0]),
+            "\n// Also deletes the rows pointing at it from: user_roles, user_tags"
+        );
+    }
+
+    #[test]
+    fn delete_handlers_follow_on_delete() {
+        let schema = crate::cli::parse::parse_schema_str(
+            "
+project:
+  name: Test
+models:
+  user:
+    fields:
+      - name: id
+        primary_key: true
+  posts:
+    has_owner: user.id
+    fields:
+      - name: id
+        primary_key: true
+      - name: author
+        use: owner
+      - name: editor
+        links: user
+        required: false
+        on_delete: set_null
+      - name: reviewer
+        links: user
+",
+            std::path::PathBuf::from("/tmp"),
+        )
+        .unwrap();
+
+        let content = create_model_routes(&schema, &schema.models[0]);
+
+        assert!(content.contains(
+            "// Also deletes the rows pointing at it from: posts.author\n// Sets these back to null where they point at it: posts.editor\n#[delete(\"/user/<id>\")]"
+        ));
+        assert!(content.contains(
+            "ApiError::Conflict(String::from(\"Can't delete this user, it's still linked from posts.reviewer\"))"
+        ));
+    }
+
+    #[test]
+    fn json_api_can_be_turned_off() {
+        let mut schema = AnubisSchema {
+            models: vec![mock_user_model()],
+            ..AnubisSchema::default()
+        };
+        assert!(create_routes_mod(&schema).contains("pub mod user;"));
+
+        schema.settings.json_api = false;
+        assert_eq!(get_route_models(&schema).count(), 0);
+        assert_eq!(
+            create_routes_mod(&schema),
+            "\npub fn routes() -> Vec<rocket::Route> {\n    vec![]\n}\n"
+        );
+    }
+}
@@ -2,21 +2,308 @@
 
 // Lib
 use clap::Args;
+use std::io::IsTerminal;
 
 // Core structs
+use crate::error::AnubisError;
+use crate::logger::{self, info, step, warning, Level};
+use crate::manifest::{
+    backup_files, get_edited_automatrons, remove_orphaned_automatrons, update_manifest,
+};
 use crate::schema::AnubisSchema;
+use crate::vfs::diff::create_unified_diff;
+use crate::vfs::{get_recorded_changes, start_recording, stop_recording, FileAction, FileChange};
 
 // Setup sub-functions
+use crate::cli::generate::api::generate_api;
+use crate::cli::generate::frontend::generate_frontend;
+use crate::cli::generate::migrations::generate_migrations;
 use crate::cli::generate::protobufs::generate_protobufs;
+use crate::cli::synthetics::dockerfiles::generate_dockerfiles;
 
-#[derive(Args)]
+#[derive(Args, Default)]
 pub struct GenerateArgs {
     #[clap(short = 'd', long, default_value = "")]
     pub directory: String,
+
+    /// Allow dropped tables, dropped columns, narrowed types and new required columns
+    #[clap(long)]
+    pub allow_destructive: bool,
+
+    /// Lists what would be written without touching any files
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Prints a unified diff of every file that changes
+    #[clap(long)]
+    pub diff: bool,
+
+    /// A dry run that exits non-zero when anything would change, for CI
+    #[clap(long)]
+    pub check: bool,
+
+    /// Overwrite automatrons even if they were edited by hand
+    #[clap(long)]
+    pub force: bool,
+
+    /// Copy hand edited automatrons to .anubis/backups/ before overwriting them
+    #[clap(long)]
+    pub backup: bool,
+}
+
+pub fn generate(schema: &AnubisSchema, args: &GenerateArgs) -> Result<(), AnubisError> {
+    let dry_run = args.dry_run || args.check;
+    protect_edited_automatrons(schema, args, dry_run)?;
+
+    // Every write is recorded, so the manifest knows which files this run produced
+    start_recording(dry_run);
+
+    info!("Generating project...");
+    // Migrations go first, so a refused migration doesn't leave the project half generated
+    step("Generated migrations", || {
+        generate_migrations(schema, args.allow_destructive)
+    })?;
+    step("Generated the api", || generate_api(schema))?;
+    step("Generated protobufs", || generate_protobufs(schema))?;
+    step("Generated the frontend", || generate_frontend(schema))?;
+    step("Generated dockerfiles", || generate_dockerfiles(schema))?;
+
+    step("Updated the manifest", || {
+        remove_orphaned_automatrons(&schema.install_directory, &get_recorded_changes())?;
+        update_manifest(
+            &schema.install_directory,
+            &get_recorded_changes(),
+            "generate",
+        )
+    })?;
+
+    let changes = stop_recording();
+    if dry_run || args.diff {
+        print_changes(schema, &changes, args.diff);
+    }
+
+    let changed = changes
+        .iter()
+        .filter(|change| {
+            matches!(
+                change.action,
+                FileAction::Create | FileAction::Update | FileAction::Delete
+            )
+        })
+        .count();
+
+    if !dry_run {
+        info!("Project generated successfully!");
+    } else if changed == 0 {
+        info!("Generated files are up to date");
+    } else {
+        info!("Dry run, {changed} file(s) would change and nothing was written");
+    }
+
+    if args.check && changed > 0 {
+        return Err(AnubisError::OutOfDate { files: changed });
+    }
+
+    Ok(())
+}
+
+// Automatrons are overwritten on every generate, so hand edits are refused unless forced or backed up
+fn protect_edited_automatrons(
+    schema: &AnubisSchema,
+    args: &GenerateArgs,
+    dry_run: bool,
+) -> Result<(), AnubisError> {
+    let edited = get_edited_automatrons(&schema.install_directory)?;
+    if edited.is_empty() {
+        return Ok(());
+    }
+
+    if dry_run {
+        warning!(
+            "These automatrons were edited by hand and would be overwritten:\n  {}",
+            edited.join("\n  ")
+        );
+    } else if args.backup {
+        let timestamp = chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string();
+        let backup_directory = backup_files(&schema.install_directory, &edited, &timestamp)?;
+        info!(
+            "Backed up {} edited automatron(s) to {}",
+            edited.len(),
+            backup_directory.display()
+        );
+    } else if args.force {
+        info!("Overwriting {} edited automatron(s)", edited.len());
+    } else {
+        let mut message = String::from(
+            "These automatrons were edited by hand, and generating would overwrite them:",
+        );
+        for path in edited.iter() {
+            message.push_str(&format!("\n  {path}"));
+        }
+        message
+            .push_str("\nMove your changes into a relic or a synthetic file, then generate again.");
+
+        return Err(AnubisError::Refused {
+            message,
+            hint: String::from(
+                "Re-run with `--backup` to copy them aside first, or `--force` to overwrite them",
+            ),
+        });
+    }
+
+    Ok(())
 }
 
-pub fn generate(schema: &AnubisSchema, args: &GenerateArgs) {
-    println!("Generating project...");
-    generate_protobufs(schema);
-    println!("Project generated successfully!");
+// Each change is one log record, so `-q` hides the list and `--log-format json` gets
+// the action, class, path and diff as fields instead of a colored table
+fn print_changes(schema: &AnubisSchema, changes: &[FileChange], show_diff: bool) {
+    let json = logger::is_json();
+    let color = !json && std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+
+    for change in changes {
+        let local_path = change
+            .path
+            .strip_prefix(&schema.install_directory)
+            .unwrap_or(&change.path)
+            .display()
+            .to_string();
+
+        let (message, fields) = create_change_record(&local_path, change, show_diff, json, color);
+        logger::log_with(Level::Info, &message, fields);
+    }
+}
+
+fn create_change_record(
+    local_path: &str,
+    change: &FileChange,
+    show_diff: bool,
+    json: bool,
+    color: bool,
+) -> (String, json::JsonValue) {
+    // The padding only lines up the text table
+    let mut message = match json {
+        true => format!("{} {local_path}", change.action),
+        false => format!(
+            "{action} {class:<10} {local_path}",
+            action = paint_action(change.action, color),
+            class = change.class,
+        ),
+    };
+    let mut fields = json::object! {
+        action: change.action.to_string(),
+        class: change.class.to_string(),
+        path: local_path,
+    };
+
+    if show_diff
+        && matches!(
+            change.action,
+            FileAction::Create | FileAction::Update | FileAction::Delete
+        )
+    {
+        let diff = create_unified_diff(local_path, &change.before, &change.after, color);
+        match json {
+            true => fields["diff"] = diff.into(),
+            false => message.push_str(&format!("\n{diff}")),
+        }
+    }
+
+    (message, fields)
+}
+
+fn paint_action(action: FileAction, color: bool) -> String {
+    let label = format!("{action:<9}");
+    if !color {
+        return label;
+    }
+
+    let code = match action {
+        FileAction::Create => "\x1b[32m",
+        FileAction::Update => "\x1b[33m",
+        FileAction::Delete => "\x1b[31m",
+        FileAction::Unchanged | FileAction::Skip => "\x1b[2m",
+    };
+    format!("{code}{label}\x1b[0m")
+}
+
+#[cfg(test)]
+mod generate_tests {
+    use super::*;
+    use crate::models::{ModelFields, Models};
+    use tempfile::tempdir;
+
+    fn mock_schema() -> AnubisSchema {
+        AnubisSchema {
+            install_directory: tempdir().unwrap().into_path(),
+            models: vec![Models {
+                name: String::from("user"),
+                fields: vec![ModelFields {
+                    name: String::from("id"),
+                    primary_key: true,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..AnubisSchema::default()
+        }
+    }
+
+    #[test]
+    fn dry_run_writes_nothing() {
+        let schema = mock_schema();
+
+        generate(
+            &schema,
+            &GenerateArgs {
+                dry_run: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let entries = std::fs::read_dir(&schema.install_directory).unwrap();
+        assert_eq!(entries.count(), 0);
+    }
+
+    #[test]
+    fn generating_twice_changes_nothing() {
+        let schema = mock_schema();
+        generate(&schema, &GenerateArgs::default()).unwrap();
+
+        start_recording(true);
+        generate_migrations(&schema, false).unwrap();
+        generate_api(&schema).unwrap();
+        generate_protobufs(&schema).unwrap();
+        generate_dockerfiles(&schema).unwrap();
+        let changes = stop_recording();
+
+        assert!(!changes.is_empty());
+        assert!(changes
+            .iter()
+            .all(|change| matches!(change.action, FileAction::Unchanged | FileAction::Skip)));
+    }
+
+    #[test]
+    fn changes_are_structured_in_json_logs() {
+        let change = FileChange {
+            path: std::path::PathBuf::from("/project/api/src/main.rs"),
+            class: crate::vfs::FileClass::Automatron,
+            action: FileAction::Update,
+            before: String::from("old\n"),
+            after: String::from("new\n"),
+        };
+
+        let (message, fields) = create_change_record("api/src/main.rs", &change, true, true, false);
+        assert_eq!(message, "update api/src/main.rs");
+        assert_eq!(fields["action"], "update");
+        assert_eq!(fields["class"], "automatron");
+        assert_eq!(fields["path"], "api/src/main.rs");
+        assert!(fields["diff"].as_str().unwrap().contains("+new"));
+
+        let (message, fields) =
+            create_change_record("api/src/main.rs", &change, true, false, false);
+        assert!(message.starts_with("update    automatron api/src/main.rs\n"));
+        assert!(message.contains("-old"));
+        assert!(fields["diff"].is_null());
+    }
 }
@@ -0,0 +1,641 @@
+// Copyright © 2024 Navarrotech
+
+use crate::cli::common::to_pascal_case;
+use crate::models::{FormatChoice, JoinTable, ModelFields, ModelKind, Models};
+use crate::schema::AnubisSchema;
+use crate::settings::Styling;
+
+// Shared by every generated form, so each form only describes its own fields
+pub fn create_forms_ts() -> String {
+    String::from(
+        r#"
+import axios from 'axios'
+
+import { API_URL } from '@/env'
+
+// What each input of a form holds, checkboxes hold booleans and everything else holds text
+export type FormValues = Record<string, string | boolean>
+
+// How an input is turned back into the value the api expects
+export type InputType = 'text' | 'number' | 'boolean' | 'datetime' | 'json'
+
+// The generated JSON routes are mounted under /api
+export const api = axios.create({
+  baseURL: `${API_URL}/api`
+})
+
+// Turns a value from the api into what its input holds
+export function toInput(value: unknown, type: InputType = 'text'): string | boolean {
+  if (type === 'boolean') {
+    return value === true
+  }
+  if (value === null || value === undefined) {
+    return ''
+  }
+  if (type === 'datetime') {
+    // datetime-local inputs work in local time, without a timezone
+    const date = new Date(String(value))
+    const offset = date.getTimezoneOffset() * 60_000
+    return new Date(date.getTime() - offset).toISOString().slice(0, 16)
+  }
+  if (type === 'json') {
+    return JSON.stringify(value)
+  }
+  return String(value)
+}
+
+// Empty inputs are sent as null, so optional fields can be cleared
+export function fromInput(value: string | boolean | undefined, type: InputType = 'text'): unknown {
+  if (type === 'boolean') {
+    return value === true
+  }
+  if (value === undefined || value === '') {
+    return null
+  }
+
+  const text = String(value)
+  switch (type) {
+    case 'number':
+      return Number(text)
+    case 'datetime':
+      return new Date(text).toISOString()
+    case 'json':
+      return JSON.parse(text)
+    default:
+      return text
+  }
+}
+
+// Attaches the newly selected ids of a many-to-many field and detaches the unselected ones
+export async function syncLinks(path: string, saved: string[], selected: string[], body?: object) {
+  const attach = selected.filter(id => !saved.includes(id))
+  const detach = saved.filter(id => !selected.includes(id))
+
+  await Promise.all([
+    ...attach.map(id => api.post(`${path}/${encodeURIComponent(id)}`, body)),
+    ...detach.map(id => api.delete(`${path}/${encodeURIComponent(id)}`))
+  ])
+}
+"#,
+    )
+}
+
+// A create and edit form for one model, `links` + `through` fields are picked with a multi-select
+pub fn create_model_form(schema: &AnubisSchema, model: &Models) -> String {
+    let name = &model.name;
+    let type_name = to_pascal_case(name);
+    let primary_key = model.primary_key();
+    let join_tables: Vec<JoinTable> = schema
+        .get_join_tables()
+        .into_iter()
+        .filter(|join_table| join_table.model.name == model.name)
+        .collect();
+
+    // Values the server fills in itself are never part of the form
+    let input_fields: Vec<&ModelFields> = model
+        .fields
+        .iter()
+        .filter(|field| field.is_column() && !field.is_generated())
+        .collect();
+
+    let mut imports = vec![format!("import type {{ {type_name} }} from './types'")];
+    for join_table in join_tables.iter() {
+        let linked_type = to_pascal_case(&join_table.linked.name);
+        let import = match join_table.linked.name == model.name {
+            true => continue,
+            false => format!(
+                "import type {{ {linked_type} }} from '@/modules/{}/types'",
+                join_table.linked.name
+            ),
+        };
+        if !imports.contains(&import) {
+            imports.push(import);
+        }
+    }
+
+    let initial_values = input_fields
+        .iter()
+        .map(|field| {
+            // Private fields never come back from the api, so they always start out empty
+            let value = match field.is_private() {
+                true => String::from("undefined"),
+                false => format!("row?.{}", field.name),
+            };
+            format!(
+                "    {}: toInput({value}, '{}')",
+                field.name,
+                get_input_type(field)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",\n");
+
+    let mut state = String::new();
+    let mut effects = String::new();
+    let mut sync_links = String::new();
+    let mut saved_links = String::new();
+    let mut inputs = String::new();
+
+    for field in input_fields.iter() {
+        inputs.push_str(&create_input(schema, model, field));
+    }
+
+    if let Some(primary_key) = primary_key {
+        let mut load_links = String::new();
+        for join_table in join_tables.iter() {
+            let field = &join_table.field.name;
+            let pascal_field = to_pascal_case(field);
+            let linked_type = to_pascal_case(&join_table.linked.name);
+            let linked_key = &join_table.linked_key.name;
+
+            state.push_str(&format!(
+                r#"  const [selected{pascal_field}, setSelected{pascal_field}] = useState<string[]>([])
+  const [saved{pascal_field}, setSaved{pascal_field}] = useState<string[]>([])
+  const [{field}Options, set{pascal_field}Options] = useState<{linked_type}[]>([])
+"#
+            ));
+            effects.push_str(&format!(
+                r#"
+  useEffect(() => {{
+    api
+      .get<{linked_type}[]>('/{linked}', {{ params: {{ take: 500 }} }})
+      .then(({{ data }}) => set{pascal_field}Options(data))
+  }}, [])
+"#,
+                linked = join_table.linked.name,
+            ));
+            load_links.push_str(&format!(
+                r#"    api
+      .get<unknown[]>(`/{name}/${{encodeURIComponent(String(row.{key}))}}/{field}`)
+      .then(({{ data }}) => {{
+        setSelected{pascal_field}(data.map(String))
+        setSaved{pascal_field}(data.map(String))
+      }})
+"#,
+                key = primary_key.name,
+            ));
+            // Extra columns on the link are left to their defaults, the attach route then takes a body
+            let attach_body = match join_table.extra_fields().is_empty() {
+                true => "",
+                false => ", {}",
+            };
+            sync_links.push_str(&format!(
+                "    await syncLinks(`${{path}}/{field}`, saved{pascal_field}, selected{pascal_field}{attach_body})\n"
+            ));
+            saved_links.push_str(&format!(
+                "    setSaved{pascal_field}(selected{pascal_field})\n"
+            ));
+            inputs.push_str(&create_multi_select(
+                schema,
+                model,
+                join_table,
+                &get_label_field(join_table).name,
+                linked_key,
+            ));
+        }
+
+        if !load_links.is_empty() {
+            effects.push_str(&format!(
+                r#"
+  useEffect(() => {{
+    if (!row) {{
+      return
+    }}
+
+{load_links}  }}, [row])
+"#
+            ));
+        }
+    }
+
+    let create_body = create_request_body(&input_fields, false);
+    let save = match primary_key {
+        Some(primary_key) => format!(
+            r#"    const {{ data: saved }} = row
+      ? await api.patch<{type_name}>(`/{name}/${{encodeURIComponent(String(row.{key}))}}`, {{
+{update_body}
+        }})
+      : await api.post<{type_name}>('/{name}', {{
+{create_body}
+        }})
+"#,
+            key = primary_key.name,
+            update_body = create_request_body(
+                &input_fields
+                    .iter()
+                    .filter(|field| !field.primary_key)
+                    .copied()
+                    .collect::<Vec<&ModelFields>>(),
+                true
+            ),
+        ),
+        None => format!(
+            r#"    const {{ data: saved }} = await api.post<{type_name}>('/{name}', {{
+{create_body}
+    }})
+"#
+        ),
+    };
+    let sync_links = match (primary_key, sync_links.is_empty()) {
+        (Some(primary_key), false) => format!(
+            "\n    const path = `/{name}/${{encodeURIComponent(String(saved.{key}))}}`\n{sync_links}{saved_links}",
+            key = primary_key.name
+        ),
+        _ => String::new(),
+    };
+
+    let react_imports = match effects.is_empty() {
+        true => "useState, type FormEvent",
+        false => "useEffect, useState, type FormEvent",
+    };
+    let form_imports = match sync_links.is_empty() {
+        true => "api, fromInput, toInput, type FormValues",
+        false => "api, fromInput, syncLinks, toInput, type FormValues",
+    };
+
+    format!(
+        r#"
+import {{ {react_imports} }} from 'react'
+
+import {{ {form_imports} }} from '@/modules/forms'
+{imports}
+
+type Props = {{
+  // The row to edit, a new one is created when it's left out
+  row?: {type_name}
+  onSaved?: (row: {type_name}) => void
+}}
+
+export function {type_name}Form({{ row, onSaved }}: Props) {{
+  const [values, setValues] = useState<FormValues>({{
+{initial_values}
+  }})
+{state}{effects}
+  function setValue(name: string, value: string | boolean) {{
+    setValues(previous => ({{ ...previous, [name]: value }}))
+  }}
+
+  async function onSubmit(event: FormEvent<HTMLFormElement>) {{
+    event.preventDefault()
+
+{save}{sync_links}
+    onSaved?.(saved)
+  }}
+
+  return (
+    <form onSubmit={{onSubmit}}>
+{inputs}      <button type="submit"{button_class}>Save</button>
+    </form>
+  )
+}}
+"#,
+        imports = imports.join("\n"),
+        button_class = class_attribute(schema, "button is-primary"),
+    )
+}
+
+// How a field's input is read back, see `fromInput` in forms.ts
+fn get_input_type(field: &ModelFields) -> &'static str {
+    if field.use_enum.is_some() {
+        return "text";
+    }
+
+    match field.kind {
+        ModelKind::Boolean => "boolean",
+        ModelKind::Number | ModelKind::Float => "number",
+        ModelKind::DateTime => "datetime",
+        ModelKind::Money | ModelKind::Json | ModelKind::Bytes | ModelKind::List(_) => "json",
+        // Decimals are sent as strings so no precision is lost
+        _ => "text",
+    }
+}
+
+// Every input field is sent on create, updates leave out private fields that weren't filled in
+fn create_request_body(fields: &[&ModelFields], update: bool) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            let value = format!(
+                "fromInput(values.{}, '{}')",
+                field.name,
+                get_input_type(field)
+            );
+            match update && field.is_private() {
+                true => format!(
+                    "          ...(values.{name} ? {{ {name}: {value} }} : {{}})",
+                    name = field.name
+                ),
+                false => format!("          {}: {value}", field.name),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",\n")
+}
+
+fn create_input(schema: &AnubisSchema, model: &Models, field: &ModelFields) -> String {
+    let id = format!("{}-{}", model.name, field.name);
+    let label = get_label(&field.name);
+    let name = &field.name;
+
+    let mut attributes = vec![format!("id=\"{id}\"")];
+    let on_change = match field.kind {
+        ModelKind::Boolean => {
+            format!("onChange={{event => setValue('{name}', event.target.checked)}}")
+        }
+        _ => format!("onChange={{event => setValue('{name}', event.target.value)}}"),
+    };
+    let add_value_attributes = |attributes: &mut Vec<String>| {
+        // Private fields aren't sent back, so leaving them empty keeps the saved value on edit
+        match field.required && field.kind != ModelKind::Boolean {
+            true if field.is_private() => {
+                attributes.push(String::from("required={row === undefined}"))
+            }
+            true => attributes.push(String::from("required")),
+            false => (),
+        }
+        // A row's key can't change once it's created
+        if field.primary_key {
+            attributes.push(String::from("disabled={row !== undefined}"));
+        }
+        match field.kind {
+            ModelKind::Boolean => attributes.push(format!("checked={{values.{name} === true}}")),
+            _ => attributes.push(format!("value={{String(values.{name})}}")),
+        }
+        attributes.push(on_change.clone());
+    };
+
+    if field.kind == ModelKind::Boolean && field.use_enum.is_none() {
+        attributes.push(String::from("type=\"checkbox\""));
+        add_value_attributes(&mut attributes);
+
+        return format!(
+            r#"      <div{field_class}>
+        <label{checkbox_class}>
+{input}
+          {{' '}}{label}
+        </label>
+      </div>
+"#,
+            field_class = class_attribute(schema, "field"),
+            checkbox_class = class_attribute(schema, "checkbox"),
+            input = create_element("input", &attributes, None, 10),
+        );
+    }
+
+    let control = if let Some(values) = &field.use_enum {
+        add_value_attributes(&mut attributes);
+
+        let mut options = vec![];
+        if !field.required {
+            options.push(String::from("              <option value=\"\" />"));
+        }
+        for option in values {
+            options.push(format!(
+                "              <option value=\"{option}\">{option}</option>"
+            ));
+        }
+        format!(
+            "          <div{select_class}>\n{select}\n          </div>",
+            select_class = class_attribute(schema, "select"),
+            select = create_element("select", &attributes, Some(&options.join("\n")), 12),
+        )
+    } else if get_input_type(field) == "json" {
+        // Structured values are edited as JSON
+        attributes.extend(class_name(schema, "textarea"));
+        add_value_attributes(&mut attributes);
+        create_element("textarea", &attributes, None, 10)
+    } else {
+        let input_type = match (&field.kind, &field.format) {
+            (ModelKind::Number | ModelKind::Float | ModelKind::Decimal { .. }, _) => "number",
+            (ModelKind::Date, _) => "date",
+            (ModelKind::DateTime, _) => "datetime-local",
+            (_, Some(FormatChoice::Email)) => "email",
+            (_, Some(FormatChoice::Phone)) => "tel",
+            (_, Some(FormatChoice::Password) | Some(FormatChoice::Secret)) => "password",
+            _ => "text",
+        };
+        attributes.extend(class_name(schema, "input"));
+        attributes.push(format!("type=\"{input_type}\""));
+        // Number inputs only take whole numbers unless told otherwise
+        if matches!(field.kind, ModelKind::Float | ModelKind::Decimal { .. }) {
+            attributes.push(String::from("step=\"any\""));
+        }
+        add_value_attributes(&mut attributes);
+        create_element("input", &attributes, None, 10)
+    };
+
+    format!(
+        r#"      <div{field_class}>
+        <label{label_class} htmlFor="{id}">{label}</label>
+        <div{control_class}>
+{control}
+        </div>
+      </div>
+"#,
+        field_class = class_attribute(schema, "field"),
+        label_class = class_attribute(schema, "label"),
+        control_class = class_attribute(schema, "control"),
+    )
+}
+
+// Picks any number of linked rows, they're attached and detached once the row is saved
+fn create_multi_select(
+    schema: &AnubisSchema,
+    model: &Models,
+    join_table: &JoinTable,
+    label_field: &str,
+    linked_key: &str,
+) -> String {
+    let field = &join_table.field.name;
+    let pascal_field = to_pascal_case(field);
+
+    format!(
+        r#"      <div{field_class}>
+        <label{label_class} htmlFor="{id}">{label}</label>
+        <div{control_class}>
+          <div{select_class}>
+            <select
+              id="{id}"
+              multiple
+              value={{selected{pascal_field}}}
+              onChange={{event => setSelected{pascal_field}(
+                Array.from(event.target.selectedOptions, option => option.value)
+              )}}
+            >
+              {{{field}Options.map(option => (
+                <option key={{String(option.{linked_key})}} value={{String(option.{linked_key})}}>
+                  {{String(option.{label_field})}}
+                </option>
+              ))}}
+            </select>
+          </div>
+        </div>
+      </div>
+"#,
+        id = format!("{}-{field}", model.name),
+        label = get_label(field),
+        field_class = class_attribute(schema, "field"),
+        label_class = class_attribute(schema, "label"),
+        control_class = class_attribute(schema, "control"),
+        select_class = class_attribute(schema, "select is-multiple"),
+    )
+}
+
+// A JSX element with one attribute per line, so long inputs stay under the line length
+fn create_element(
+    tag: &str,
+    attributes: &[String],
+    children: Option<&str>,
+    indent: usize,
+) -> String {
+    let padding = " ".repeat(indent);
+    let attributes = attributes
+        .iter()
+        .map(|attribute| format!("{padding}  {attribute}\n"))
+        .collect::<String>();
+
+    match children {
+        Some(children) => {
+            format!("{padding}<{tag}\n{attributes}{padding}>\n{children}\n{padding}</{tag}>")
+        }
+        None => format!("{padding}<{tag}\n{attributes}{padding}/>"),
+    }
+}
+
+// Linked rows are shown by their first public text field, or by their key when they have none
+fn get_label_field<'a>(join_table: &'a JoinTable) -> &'a ModelFields {
+    join_table
+        .linked
+        .fields
+        .iter()
+        .find(|field| {
+            field.kind == ModelKind::String
+                && field.is_column()
+                && !field.is_private()
+                && !field.primary_key
+                && field.links.is_none()
+        })
+        .unwrap_or(join_table.linked_key)
+}
+
+// i.e. "favorite_icecream" -> "Favorite icecream"
+fn get_label(name: &str) -> String {
+    let label = name.replace('_', " ");
+    let mut characters = label.chars();
+    match characters.next() {
+        Some(first) => first.to_uppercase().chain(characters).collect(),
+        None => label,
+    }
+}
+
+// Bulma classes are only written when the project styles with Bulma
+fn class_name(schema: &AnubisSchema, classes: &str) -> Option<String> {
+    match schema.settings.styling {
+        Styling::Bulma => Some(format!("className=\"{classes}\"")),
+        Styling::Plain => None,
+    }
+}
+
+fn class_attribute(schema: &AnubisSchema, classes: &str) -> String {
+    class_name(schema, classes)
+        .map(|class_name| format!(" {class_name}"))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod check_forms {
+    use super::*;
+    use crate::cli::parse::parse_schema_str;
+    use crate::settings::Settings;
+    use std::path::PathBuf;
+
+    fn parse(content: &str) -> AnubisSchema {
+        parse_schema_str(content, PathBuf::from("/tmp")).unwrap()
+    }
+
+    const LINKED_SCHEMA: &str = "
+project:
+  name: Test
+models:
+  transactions:
+    fields:
+      - name: id
+        primary_key: true
+        use: uuid
+      - name: note
+        required: false
+      - name: tags
+        links: tag
+        through: transaction_tags
+  tag:
+    fields:
+      - name: id
+        primary_key: true
+        use: uuid
+      - name: label
+  transaction_tags:
+    fields:
+      - name: added_at
+        kind: datetime
+        required: false
+";
+
+    #[test]
+    fn links_through_a_join_table_are_picked_with_a_multi_select() {
+        let schema = parse(LINKED_SCHEMA);
+
+        let content = create_model_form(&schema, &schema.models[0]);
+
+        assert!(content.contains("import type { Tag } from '@/modules/tag/types'"));
+        assert!(content.contains(
+            "            <select
+              id=\"transactions-tags\"
+              multiple
+              value={selectedTags}"
+        ));
+        assert!(content.contains(".get<Tag[]>('/tag', { params: { take: 500 } })"));
+        assert!(content.contains("<option key={String(option.id)} value={String(option.id)}>"));
+        assert!(content.contains("{String(option.label)}"));
+        assert!(content.contains(
+            ".get<unknown[]>(`/transactions/${encodeURIComponent(String(row.id))}/tags`)"
+        ));
+        // The join model has extra columns, so attaching sends a body
+        assert!(content.contains("await syncLinks(`${path}/tags`, savedTags, selectedTags, {})"));
+    }
+
+    #[test]
+    fn generated_values_are_left_out_of_the_form() {
+        let schema = parse(LINKED_SCHEMA);
+
+        let content = create_model_form(&schema, &schema.models[0]);
+
+        assert!(content.contains("    note: toInput(row?.note, 'text')\n  })"));
+        assert!(!content.contains("transactions-id"));
+        assert!(!content.contains("toInput(row?.id"));
+    }
+
+    #[test]
+    fn forms_without_links_only_save_the_row() {
+        let schema = parse(LINKED_SCHEMA);
+
+        let content = create_model_form(&schema, &schema.models[1]);
+
+        assert!(content.contains("import { useState, type FormEvent } from 'react'"));
+        assert!(!content.contains("syncLinks"));
+        assert!(!content.contains("multiple"));
+    }
+
+    #[test]
+    fn plain_styling_writes_no_bulma_classes() {
+        let mut schema = parse(LINKED_SCHEMA);
+        schema.settings = Settings {
+            styling: Styling::Plain,
+            ..Settings::default()
+        };
+
+        let content = create_model_form(&schema, &schema.models[0]);
+
+        assert!(!content.contains("className"));
+        assert!(content.contains("<button type=\"submit\">Save</button>"));
+    }
+}
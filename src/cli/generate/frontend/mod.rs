@@ -0,0 +1,115 @@
+// Copyright © 2024 Navarrotech
+
+use forms::create_forms_ts;
+use forms::create_model_form;
+use types::create_model_types;
+
+use crate::automatrons::write::write_automatron;
+use crate::cli::common::to_pascal_case;
+use crate::cli::generate::api::routes::get_route_models;
+use crate::error::AnubisError;
+use crate::models::Models;
+use crate::schema::AnubisSchema;
+
+// Generates the per-model modules under frontend/src/modules/
+pub fn generate_frontend(schema: &AnubisSchema) -> Result<(), AnubisError> {
+    for model in get_module_models(schema) {
+        let module_directory = schema
+            .install_directory
+            .join(format!("frontend/src/modules/{}", model.name));
+
+        write_automatron(
+            schema,
+            &create_model_types(model),
+            &module_directory.join("types.ts"),
+        )?;
+    }
+
+    // Forms save through the JSON routes, so there are none with `json_api: false`
+    if get_route_models(schema).next().is_some() {
+        write_automatron(
+            schema,
+            &create_forms_ts(),
+            &schema
+                .install_directory
+                .join("frontend/src/modules/forms.ts"),
+        )?;
+    }
+
+    for model in get_route_models(schema) {
+        write_automatron(
+            schema,
+            &create_model_form(schema, model),
+            &schema.install_directory.join(format!(
+                "frontend/src/modules/{}/{}Form.tsx",
+                model.name,
+                to_pascal_case(&model.name)
+            )),
+        )?;
+    }
+
+    Ok(())
+}
+
+// Join models are stored with the model that links them, so they have no module of their own
+pub fn get_module_models(schema: &AnubisSchema) -> impl Iterator<Item = &Models> {
+    schema
+        .models
+        .iter()
+        .filter(|model| !schema.is_join_model(&model.name))
+}
+
+pub mod forms;
+pub mod types;
+
+#[cfg(test)]
+mod check_frontend {
+    use super::*;
+    use crate::models::ModelFields;
+    use crate::settings::Settings;
+    use tempfile::tempdir;
+
+    fn mock_schema(json_api: bool) -> AnubisSchema {
+        AnubisSchema {
+            install_directory: tempdir().unwrap().into_path(),
+            models: vec![Models {
+                name: String::from("user"),
+                fields: vec![ModelFields {
+                    name: String::from("id"),
+                    primary_key: true,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            settings: Settings {
+                json_api,
+                ..Settings::default()
+            },
+            ..AnubisSchema::default()
+        }
+    }
+
+    #[test]
+    fn writes_types_and_forms_for_each_model() {
+        let schema = mock_schema(true);
+        let modules = schema.install_directory.join("frontend/src/modules");
+
+        generate_frontend(&schema).unwrap();
+
+        assert!(modules.join("forms.ts").exists());
+        assert!(modules.join("user/types.ts").exists());
+        assert!(modules.join("user/UserForm.tsx").exists());
+    }
+
+    #[test]
+    fn forms_need_the_json_api() {
+        let schema = mock_schema(false);
+        let modules = schema.install_directory.join("frontend/src/modules");
+
+        generate_frontend(&schema).unwrap();
+
+        assert!(modules.join("user/types.ts").exists());
+        assert!(!modules.join("forms.ts").exists());
+        assert!(!modules.join("user/UserForm.tsx").exists());
+    }
+}
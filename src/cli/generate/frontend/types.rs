@@ -0,0 +1,100 @@
+// Copyright © 2024 Navarrotech
+
+use crate::cli::common::to_pascal_case;
+use crate::models::{ModelFields, Models};
+
+// The shape of a row as the api sends it, private fields are never part of a response
+pub fn create_model_types(model: &Models) -> String {
+    let properties = model
+        .fields
+        .iter()
+        .filter(|field| field.is_column() && !field.is_private())
+        .map(|field| format!("  {}: {}\n", field.name, get_typescript_type(field)))
+        .collect::<String>();
+
+    format!(
+        r#"
+export interface {interface_name} {{
+{properties}}}
+"#,
+        interface_name = to_pascal_case(&model.name),
+    )
+}
+
+// Enums narrow the type down to their values, nullable columns can also be null
+pub fn get_typescript_type(field: &ModelFields) -> String {
+    let typescript_type = match &field.use_enum {
+        Some(values) => values
+            .iter()
+            .map(|value| format!("'{}'", value.replace('\'', "\\'")))
+            .collect::<Vec<String>>()
+            .join(" | "),
+        None => field.kind.typescript_type(),
+    };
+
+    match field.is_nullable() {
+        true => format!("{typescript_type} | null"),
+        false => typescript_type,
+    }
+}
+
+#[cfg(test)]
+mod check_types {
+    use super::*;
+    use crate::models::ModelKind;
+
+    #[test]
+    fn interfaces_follow_the_api_response() {
+        let model = Models {
+            name: String::from("bank_account"),
+            fields: vec![
+                ModelFields {
+                    name: String::from("id"),
+                    primary_key: true,
+                    ..Default::default()
+                },
+                ModelFields {
+                    name: String::from("balance"),
+                    kind: ModelKind::Money,
+                    required: true,
+                    ..Default::default()
+                },
+                ModelFields {
+                    name: String::from("history"),
+                    kind: ModelKind::List(Box::new(ModelKind::Money)),
+                    ..Default::default()
+                },
+                ModelFields {
+                    name: String::from("status"),
+                    required: true,
+                    use_enum: Some(vec![String::from("open"), String::from("closed")]),
+                    ..Default::default()
+                },
+                ModelFields {
+                    name: String::from("pin"),
+                    replicate: false,
+                    ..Default::default()
+                },
+                ModelFields {
+                    name: String::from("tags"),
+                    links: Some(String::from("tag")),
+                    through: Some(String::from("account_tags")),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            create_model_types(&model),
+            "
+export interface BankAccount {
+  id: string
+  balance: { amount: string, currency: string }
+  history: Array<{ amount: string, currency: string }> | null
+  status: 'open' | 'closed'
+}
+"
+        );
+    }
+}
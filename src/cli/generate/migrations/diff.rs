@@ -0,0 +1,650 @@
+// Copyright © 2024 Navarrotech
+
+use crate::cli::common::quote_identifier;
+use crate::cli::generate::migrations::snapshot::{ColumnSnapshot, ForeignKey, TableSnapshot};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    CreateTable(TableSnapshot),
+    DropTable(String),
+    AddColumn {
+        table: String,
+        column: ColumnSnapshot,
+    },
+    DropColumn {
+        table: String,
+        column: String,
+    },
+    AlterType {
+        table: String,
+        column: String,
+        from: String,
+        to: String,
+    },
+    SetNullable {
+        table: String,
+        column: String,
+        nullable: bool,
+    },
+    SetDefault {
+        table: String,
+        column: String,
+        default: Option<String>,
+    },
+    SetPrimaryKey {
+        table: String,
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    CreateIndex {
+        table: String,
+        column: String,
+        name: String,
+        unique: bool,
+    },
+    DropIndex {
+        name: String,
+    },
+    AddForeignKey {
+        table: String,
+        column: String,
+        references: ForeignKey,
+    },
+    DropForeignKey {
+        table: String,
+        column: String,
+    },
+}
+
+impl Change {
+    // Changes that can lose data, or fail on a table that already has rows,
+    // these are refused unless explicitly allowed
+    pub fn is_destructive(&self) -> bool {
+        match self {
+            Change::DropTable(_) | Change::DropColumn { .. } => true,
+            Change::AddColumn { column, .. } => !column.nullable && column.default.is_none(),
+            Change::SetNullable { nullable, .. } => !nullable,
+            Change::AlterType { from, to, .. } => is_narrowing(from, to),
+            Change::SetPrimaryKey { from, .. } => !from.is_empty(),
+            _ => false,
+        }
+    }
+
+    // Whether the change creates or changes a column to this SQL type, or a list of it
+    pub fn uses_type(&self, sql_type: &str) -> bool {
+        let matches = |column_type: &str| column_type.trim_end_matches("[]") == sql_type;
+
+        match self {
+            Change::CreateTable(table) => {
+                table.columns.iter().any(|column| matches(&column.sql_type))
+            }
+            Change::AddColumn { column, .. } => matches(&column.sql_type),
+            Change::AlterType { to, .. } => matches(to),
+            _ => false,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Change::CreateTable(table) => format!("creates table `{}`", table.name),
+            Change::DropTable(table) => format!("drops table `{table}`"),
+            Change::AddColumn { table, column } => match self.is_destructive() {
+                true => format!(
+                    "adds required column `{table}.{}` without a default, which fails if `{table}` has rows",
+                    column.name
+                ),
+                false => format!("adds column `{table}.{}`", column.name),
+            },
+            Change::DropColumn { table, column } => format!("drops column `{table}.{column}`"),
+            Change::AlterType {
+                table,
+                column,
+                from,
+                to,
+            } => match is_narrowing(from, to) {
+                true => format!("narrows `{table}.{column}` from {from} to {to}"),
+                false => format!("changes `{table}.{column}` from {from} to {to}"),
+            },
+            Change::SetNullable {
+                table,
+                column,
+                nullable,
+            } => match nullable {
+                true => format!("makes `{table}.{column}` optional"),
+                false => format!(
+                    "makes `{table}.{column}` required, which fails if any row is missing a value"
+                ),
+            },
+            Change::SetDefault { table, column, .. } => {
+                format!("changes the default of `{table}.{column}`")
+            }
+            Change::SetPrimaryKey { table, .. } => format!("changes the primary key of `{table}`"),
+            Change::CreateIndex { name, .. } => format!("creates index `{name}`"),
+            Change::DropIndex { name } => format!("drops index `{name}`"),
+            Change::AddForeignKey { table, column, .. } => {
+                format!("adds a foreign key on `{table}.{column}`")
+            }
+            Change::DropForeignKey { table, column } => {
+                format!("drops the foreign key on `{table}.{column}`")
+            }
+        }
+    }
+
+    // Statements run in phases, so constraints are dropped before the columns they use change
+    // and foreign keys are only added once every table they point at exists
+    pub fn phase(&self) -> u8 {
+        match self {
+            Change::DropForeignKey { .. } => 0,
+            Change::DropIndex { .. } => 1,
+            Change::CreateTable(_) => 2,
+            Change::AddColumn { .. } => 3,
+            Change::AlterType { .. } => 4,
+            Change::SetNullable { .. } | Change::SetDefault { .. } => 5,
+            Change::SetPrimaryKey { .. } => 6,
+            Change::CreateIndex { .. } => 7,
+            Change::AddForeignKey { .. } => 8,
+            Change::DropColumn { .. } => 9,
+            Change::DropTable(_) => 10,
+        }
+    }
+
+    pub fn to_sql(&self) -> String {
+        match self {
+            Change::CreateTable(table) => {
+                let mut definitions: Vec<String> = table
+                    .columns
+                    .iter()
+                    .map(|column| format!("    {}", column_definition(column)))
+                    .collect();
+
+                let primary_key = table.primary_key();
+                if !primary_key.is_empty() {
+                    definitions.push(format!(
+                        "    PRIMARY KEY ({})",
+                        quote_identifiers(primary_key.iter().map(|column| &column.name))
+                    ));
+                }
+
+                format!(
+                    "CREATE TABLE {} (\n{}\n);",
+                    quote_identifier(&table.name),
+                    definitions.join(",\n")
+                )
+            }
+            Change::DropTable(table) => format!("DROP TABLE {};", quote_identifier(table)),
+            Change::AddColumn { table, column } => format!(
+                "ALTER TABLE {} ADD COLUMN {};",
+                quote_identifier(table),
+                column_definition(column)
+            ),
+            Change::DropColumn { table, column } => format!(
+                "ALTER TABLE {} DROP COLUMN {};",
+                quote_identifier(table),
+                quote_identifier(column)
+            ),
+            Change::AlterType {
+                table, column, to, ..
+            } => format!(
+                "ALTER TABLE {table} ALTER COLUMN {column} TYPE {to} USING {column}::{to};",
+                table = quote_identifier(table),
+                column = quote_identifier(column),
+            ),
+            Change::SetNullable {
+                table,
+                column,
+                nullable,
+            } => format!(
+                "ALTER TABLE {} ALTER COLUMN {} {} NOT NULL;",
+                quote_identifier(table),
+                quote_identifier(column),
+                if *nullable { "DROP" } else { "SET" }
+            ),
+            Change::SetDefault {
+                table,
+                column,
+                default,
+            } => format!(
+                "ALTER TABLE {} ALTER COLUMN {} {};",
+                quote_identifier(table),
+                quote_identifier(column),
+                match default {
+                    Some(default) => format!("SET DEFAULT {default}"),
+                    None => String::from("DROP DEFAULT"),
+                }
+            ),
+            Change::SetPrimaryKey { table, from, to } => {
+                let mut statements = vec![];
+                if !from.is_empty() {
+                    statements.push(format!(
+                        "ALTER TABLE {} DROP CONSTRAINT IF EXISTS {};",
+                        quote_identifier(table),
+                        quote_identifier(&format!("{table}_pkey"))
+                    ));
+                }
+                if !to.is_empty() {
+                    statements.push(format!(
+                        "ALTER TABLE {} ADD PRIMARY KEY ({});",
+                        quote_identifier(table),
+                        quote_identifiers(to)
+                    ));
+                }
+                statements.join("\n")
+            }
+            Change::CreateIndex {
+                table,
+                column,
+                name,
+                unique,
+            } => format!(
+                "CREATE {}INDEX {} ON {} ({});",
+                if *unique { "UNIQUE " } else { "" },
+                quote_identifier(name),
+                quote_identifier(table),
+                quote_identifier(column)
+            ),
+            Change::DropIndex { name } => {
+                format!("DROP INDEX IF EXISTS {};", quote_identifier(name))
+            }
+            Change::AddForeignKey {
+                table,
+                column,
+                references,
+            } => format!(
+                "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({}) ON DELETE {} ON UPDATE {};",
+                quote_identifier(table),
+                quote_identifier(&format!("{table}_{column}_fkey")),
+                quote_identifier(column),
+                quote_identifier(&references.table),
+                quote_identifier(&references.column),
+                references.on_delete.sql(),
+                references.on_update.sql()
+            ),
+            Change::DropForeignKey { table, column } => format!(
+                "ALTER TABLE {} DROP CONSTRAINT IF EXISTS {};",
+                quote_identifier(table),
+                quote_identifier(&format!("{table}_{column}_fkey"))
+            ),
+        }
+    }
+}
+
+// Works out every change needed to go from the previous snapshot to the current one
+pub fn diff_snapshots(previous: &[TableSnapshot], current: &[TableSnapshot]) -> Vec<Change> {
+    let mut changes = vec![];
+
+    for table in current {
+        match previous.iter().find(|previous| previous.name == table.name) {
+            Some(previous) => diff_table(previous, table, &mut changes),
+            None => {
+                changes.push(Change::CreateTable(table.clone()));
+                for column in table.columns.iter() {
+                    add_constraints(&table.name, column, &mut changes);
+                }
+            }
+        }
+    }
+
+    for table in previous {
+        if !current.iter().any(|current| current.name == table.name) {
+            // Foreign keys are dropped up front so tables can be dropped in any order
+            for column in table.columns.iter() {
+                if column.references.is_some() {
+                    changes.push(Change::DropForeignKey {
+                        table: table.name.clone(),
+                        column: column.name.clone(),
+                    });
+                }
+            }
+            changes.push(Change::DropTable(table.name.clone()));
+        }
+    }
+
+    changes.sort_by_key(|change| change.phase());
+    changes
+}
+
+fn diff_table(previous: &TableSnapshot, current: &TableSnapshot, changes: &mut Vec<Change>) {
+    let table = &current.name;
+
+    for column in current.columns.iter() {
+        let Some(previous_column) = previous.column(&column.name) else {
+            changes.push(Change::AddColumn {
+                table: table.clone(),
+                column: column.clone(),
+            });
+            add_constraints(table, column, changes);
+            continue;
+        };
+
+        if previous_column.sql_type != column.sql_type {
+            changes.push(Change::AlterType {
+                table: table.clone(),
+                column: column.name.clone(),
+                from: previous_column.sql_type.clone(),
+                to: column.sql_type.clone(),
+            });
+        }
+
+        if previous_column.nullable != column.nullable {
+            changes.push(Change::SetNullable {
+                table: table.clone(),
+                column: column.name.clone(),
+                nullable: column.nullable,
+            });
+        }
+
+        if previous_column.default != column.default {
+            changes.push(Change::SetDefault {
+                table: table.clone(),
+                column: column.name.clone(),
+                default: column.default.clone(),
+            });
+        }
+
+        if previous_column.index(table) != column.index(table) {
+            if let Some((name, _)) = previous_column.index(table) {
+                changes.push(Change::DropIndex { name });
+            }
+            if let Some((name, unique)) = column.index(table) {
+                changes.push(Change::CreateIndex {
+                    table: table.clone(),
+                    column: column.name.clone(),
+                    name,
+                    unique,
+                });
+            }
+        }
+
+        if previous_column.references != column.references {
+            if previous_column.references.is_some() {
+                changes.push(Change::DropForeignKey {
+                    table: table.clone(),
+                    column: column.name.clone(),
+                });
+            }
+            if let Some(references) = &column.references {
+                changes.push(Change::AddForeignKey {
+                    table: table.clone(),
+                    column: column.name.clone(),
+                    references: references.clone(),
+                });
+            }
+        }
+    }
+
+    // Dropping a column also drops its indexes and foreign keys
+    for column in previous.columns.iter() {
+        if current.column(&column.name).is_none() {
+            changes.push(Change::DropColumn {
+                table: table.clone(),
+                column: column.name.clone(),
+            });
+        }
+    }
+
+    let previous_key: Vec<String> = previous
+        .primary_key()
+        .iter()
+        .map(|column| column.name.clone())
+        .collect();
+    let current_key: Vec<String> = current
+        .primary_key()
+        .iter()
+        .map(|column| column.name.clone())
+        .collect();
+    if previous_key != current_key {
+        changes.push(Change::SetPrimaryKey {
+            table: table.clone(),
+            from: previous_key,
+            to: current_key,
+        });
+    }
+}
+
+// Indexes and foreign keys for a column that didn't exist before
+fn add_constraints(table: &str, column: &ColumnSnapshot, changes: &mut Vec<Change>) {
+    if let Some((name, unique)) = column.index(table) {
+        changes.push(Change::CreateIndex {
+            table: table.to_string(),
+            column: column.name.clone(),
+            name,
+            unique,
+        });
+    }
+
+    if let Some(references) = &column.references {
+        changes.push(Change::AddForeignKey {
+            table: table.to_string(),
+            column: column.name.clone(),
+            references: references.clone(),
+        });
+    }
+}
+
+fn quote_identifiers<'a>(names: impl IntoIterator<Item = &'a String>) -> String {
+    names
+        .into_iter()
+        .map(|name| quote_identifier(name))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn column_definition(column: &ColumnSnapshot) -> String {
+    let mut definition = format!("{} {}", quote_identifier(&column.name), column.sql_type);
+
+    if !column.nullable {
+        definition.push_str(" NOT NULL");
+    }
+
+    if let Some(default) = &column.default {
+        definition.push_str(&format!(" DEFAULT {default}"));
+    }
+
+    definition
+}
+
+// Whether existing values might not fit into the new type
+fn is_narrowing(from: &str, to: &str) -> bool {
+    if from == to || to == "TEXT" {
+        return false;
+    }
+
+    match (get_varchar_length(from), get_varchar_length(to)) {
+        (Some(from_length), Some(to_length)) => to_length < from_length,
+        _ => !(from == "INTEGER" && to == "DOUBLE PRECISION"),
+    }
+}
+
+fn get_varchar_length(sql_type: &str) -> Option<u32> {
+    sql_type
+        .strip_prefix("VARCHAR(")?
+        .strip_suffix(')')?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use super::*;
+    use crate::relationships::ReferentialAction;
+
+    fn column(name: &str, sql_type: &str) -> ColumnSnapshot {
+        ColumnSnapshot {
+            name: name.to_string(),
+            sql_type: sql_type.to_string(),
+            nullable: false,
+            primary_key: false,
+            unique: false,
+            default: None,
+            references: None,
+        }
+    }
+
+    fn table(name: &str, columns: Vec<ColumnSnapshot>) -> TableSnapshot {
+        TableSnapshot {
+            name: name.to_string(),
+            columns,
+        }
+    }
+
+    #[test]
+    fn new_tables_are_created_before_foreign_keys_are_added() {
+        let mut id = column("id", "TEXT");
+        id.primary_key = true;
+        let mut owner = column("user_id", "TEXT");
+        owner.references = Some(ForeignKey {
+            table: String::from("user"),
+            column: String::from("id"),
+            on_delete: ReferentialAction::Cascade,
+            on_update: ReferentialAction::Cascade,
+        });
+
+        let changes = diff_snapshots(
+            &[],
+            &[
+                table("transactions", vec![id.clone(), owner]),
+                table("user", vec![id]),
+            ],
+        );
+
+        let sql: Vec<String> = changes.iter().map(|change| change.to_sql()).collect();
+        assert_eq!(
+            sql,
+            vec![
+                "CREATE TABLE \"transactions\" (\n    \"id\" TEXT NOT NULL,\n    \"user_id\" TEXT NOT NULL,\n    PRIMARY KEY (\"id\")\n);",
+                "CREATE TABLE \"user\" (\n    \"id\" TEXT NOT NULL,\n    PRIMARY KEY (\"id\")\n);",
+                "CREATE INDEX \"transactions_user_id_idx\" ON \"transactions\" (\"user_id\");",
+                "ALTER TABLE \"transactions\" ADD CONSTRAINT \"transactions_user_id_fkey\" FOREIGN KEY (\"user_id\") REFERENCES \"user\" (\"id\") ON DELETE CASCADE ON UPDATE CASCADE;",
+            ]
+        );
+        assert!(!changes.iter().any(|change| change.is_destructive()));
+    }
+
+    #[test]
+    fn join_tables_have_a_composite_primary_key() {
+        let mut user_id = column("user_id", "TEXT");
+        user_id.primary_key = true;
+        let mut tag_id = column("tag_id", "TEXT");
+        tag_id.primary_key = true;
+
+        let changes = diff_snapshots(&[], &[table("user_tags", vec![user_id, tag_id])]);
+
+        assert_eq!(
+            changes[0].to_sql(),
+            "CREATE TABLE \"user_tags\" (\n    \"user_id\" TEXT NOT NULL,\n    \"tag_id\" TEXT NOT NULL,\n    PRIMARY KEY (\"user_id\", \"tag_id\")\n);"
+        );
+    }
+
+    #[test]
+    fn unchanged_snapshots_have_no_changes() {
+        let tables = vec![table("user", vec![column("name", "VARCHAR(64)")])];
+
+        assert!(diff_snapshots(&tables, &tables).is_empty());
+    }
+
+    #[test]
+    fn dropped_columns_and_narrowed_types_are_destructive() {
+        let previous = vec![table(
+            "user",
+            vec![column("name", "VARCHAR(64)"), column("age", "INTEGER")],
+        )];
+        let current = vec![table("user", vec![column("name", "VARCHAR(32)")])];
+
+        let changes = diff_snapshots(&previous, &current);
+
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (change.describe(), change.is_destructive()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    String::from("narrows `user.name` from VARCHAR(64) to VARCHAR(32)"),
+                    true
+                ),
+                (String::from("drops column `user.age`"), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn widened_types_are_not_destructive() {
+        assert!(!is_narrowing("VARCHAR(32)", "VARCHAR(64)"));
+        assert!(!is_narrowing("VARCHAR(32)", "TEXT"));
+        assert!(!is_narrowing("INTEGER", "DOUBLE PRECISION"));
+        assert!(is_narrowing("TEXT", "VARCHAR(64)"));
+        assert!(is_narrowing("DOUBLE PRECISION", "INTEGER"));
+    }
+
+    #[test]
+    fn added_columns_and_unique_changes() {
+        let previous = vec![table("user", vec![column("email", "TEXT")])];
+        let mut email = column("email", "TEXT");
+        email.unique = true;
+        let mut language = column("language", "VARCHAR(2)");
+        language.default = Some(String::from("'en'"));
+
+        let changes = diff_snapshots(&previous, &[table("user", vec![email, language])]);
+
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| change.to_sql())
+                .collect::<Vec<_>>(),
+            vec![
+                "ALTER TABLE \"user\" ADD COLUMN \"language\" VARCHAR(2) NOT NULL DEFAULT 'en';",
+                "CREATE UNIQUE INDEX \"user_email_key\" ON \"user\" (\"email\");",
+            ]
+        );
+    }
+
+    #[test]
+    fn required_columns_without_a_default_are_destructive() {
+        let mut optional_bio = column("bio", "TEXT");
+        optional_bio.nullable = true;
+        let previous = vec![table("user", vec![column("email", "TEXT"), optional_bio])];
+        let current = vec![table(
+            "user",
+            vec![
+                column("email", "TEXT"),
+                column("bio", "TEXT"),
+                column("age", "INTEGER"),
+            ],
+        )];
+
+        let changes = diff_snapshots(&previous, &current);
+
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (change.describe(), change.is_destructive()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    String::from("adds required column `user.age` without a default, which fails if `user` has rows"),
+                    true
+                ),
+                (
+                    String::from("makes `user.bio` required, which fails if any row is missing a value"),
+                    true
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn optional_or_defaulted_columns_are_not_destructive() {
+        let previous = vec![table("user", vec![column("email", "TEXT")])];
+        let mut nickname = column("nickname", "TEXT");
+        nickname.nullable = true;
+        let mut language = column("language", "VARCHAR(2)");
+        language.default = Some(String::from("'en'"));
+        let mut email = column("email", "TEXT");
+        email.nullable = true;
+
+        let changes = diff_snapshots(&previous, &[table("user", vec![email, nickname, language])]);
+
+        assert_eq!(changes.len(), 3);
+        assert!(!changes.iter().any(|change| change.is_destructive()));
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright © 2024 Navarrotech
+
+use std::path::Path;
+
+use diff::diff_snapshots;
+use diff::Change;
+use snapshot::read_snapshot;
+use snapshot::snapshot_schema;
+use snapshot::write_snapshot;
+use snapshot::SNAPSHOT_FILE;
+
+use crate::error::AnubisError;
+use crate::logger::{info, warning};
+use crate::models::MONEY_SQL_TYPE;
+use crate::relics::write::write_relic;
+use crate::schema::AnubisSchema;
+use crate::vfs::read_dir;
+
+// Writes the next migration under api/migrations/ for whatever changed since the last generate
+pub fn generate_migrations(
+    schema: &AnubisSchema,
+    allow_destructive: bool,
+) -> Result<(), AnubisError> {
+    let previous = read_snapshot(&schema.install_directory)?;
+    let current = snapshot_schema(schema);
+
+    let changes = diff_snapshots(previous.as_deref().unwrap_or_default(), &current);
+    if changes.is_empty() {
+        info!("Database is up to date, no migration needed");
+        return Ok(());
+    }
+
+    let destructive: Vec<&Change> = changes
+        .iter()
+        .filter(|change| change.is_destructive())
+        .collect();
+
+    if !destructive.is_empty() {
+        let described: Vec<String> = destructive.iter().map(|change| change.describe()).collect();
+        warning!(
+            "This migration contains changes that can lose data or fail on existing rows:\n  {}",
+            described.join("\n  ")
+        );
+
+        if !allow_destructive {
+            return Err(AnubisError::Refused {
+                message: String::from(
+                    "This migration can lose data or fail on existing rows, so nothing was generated",
+                ),
+                hint: String::from(
+                    "Give new required fields a default, or re-run with `--allow-destructive` to write this migration anyway",
+                ),
+            });
+        }
+    }
+
+    // Without the snapshot every table looks new, so another init migration would fail on apply
+    let migrations_directory = schema.install_directory.join("api/migrations");
+    let version = get_next_version(&migrations_directory);
+    if previous.is_none() && version > 1 {
+        return Err(AnubisError::Refused {
+            message: format!(
+                "api/migrations already has migrations, but {SNAPSHOT_FILE} is missing, so nothing was generated"
+            ),
+            hint: format!(
+                "Restore {SNAPSHOT_FILE} from version control, it's what new migrations are diffed against"
+            ),
+        });
+    }
+
+    let file_name = format!(
+        "{version:04}_{description}.sql",
+        description = if previous.is_none() { "init" } else { "update" }
+    );
+
+    write_relic(
+        schema,
+        &create_migration(&changes),
+        &migrations_directory.join(&file_name),
+    )?;
+    write_snapshot(&schema.install_directory, &current)?;
+
+    info!("Created migration api/migrations/{file_name}");
+
+    Ok(())
+}
+
+pub fn create_migration(changes: &[Change]) -> String {
+    let mut statements = vec![];
+
+    // Created if it doesn't exist yet, so every migration adding a money column can include it
+    if changes
+        .iter()
+        .any(|change| change.uses_type(MONEY_SQL_TYPE))
+    {
+        statements.push(format!(
+            r#"DO $$ BEGIN
+    CREATE TYPE "{MONEY_SQL_TYPE}" AS ("amount" NUMERIC(19, 4), "currency" CHAR(3));
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;"#
+        ));
+    }
+
+    for change in changes {
+        match change.is_destructive() {
+            true => statements.push(format!(
+                "-- Destructive: {}\n{}",
+                change.describe(),
+                change.to_sql()
+            )),
+            false => statements.push(change.to_sql()),
+        }
+    }
+
+    format!("\n{}\n", statements.join("\n\n"))
+}
+
+// Migrations are numbered so sqlx applies them in the order they were generated
+fn get_next_version(migrations_directory: &Path) -> u32 {
+    read_dir(migrations_directory)
+        .iter()
+        .filter_map(|file_name| file_name.split('_').next()?.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1
+}
+
+pub mod diff;
+pub mod snapshot;
+
+#[cfg(test)]
+mod migrations_tests {
+    use super::*;
+    use crate::models::{ModelFields, ModelKind, Models};
+    use crate::vfs::{start_recording, stop_recording};
+    use tempfile::tempdir;
+
+    fn mock_schema() -> AnubisSchema {
+        AnubisSchema {
+            install_directory: tempdir().unwrap().into_path(),
+            models: vec![Models {
+                name: String::from("user"),
+                fields: vec![ModelFields {
+                    name: String::from("id"),
+                    primary_key: true,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..AnubisSchema::default()
+        }
+    }
+
+    #[test]
+    fn migrations_are_numbered_and_only_written_when_something_changed() {
+        let mut schema = mock_schema();
+        let migrations_directory = schema.install_directory.join("api/migrations");
+
+        generate_migrations(&schema, false).unwrap();
+        generate_migrations(&schema, false).unwrap();
+
+        schema.models[0].fields.push(ModelFields {
+            name: String::from("name"),
+            required: false,
+            ..Default::default()
+        });
+        generate_migrations(&schema, false).unwrap();
+
+        let mut files: Vec<String> = std::fs::read_dir(&migrations_directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+
+        assert_eq!(files, vec!["0001_init.sql", "0002_update.sql"]);
+
+        let update = std::fs::read_to_string(migrations_directory.join("0002_update.sql")).unwrap();
+        assert!(update.contains("ALTER TABLE \"user\" ADD COLUMN \"name\" TEXT;"));
+    }
+
+    #[test]
+    fn refuses_a_second_init_when_the_snapshot_is_missing() {
+        let schema = mock_schema();
+        generate_migrations(&schema, false).unwrap();
+        std::fs::remove_file(schema.install_directory.join(SNAPSHOT_FILE)).unwrap();
+
+        let error = generate_migrations(&schema, false).unwrap_err();
+        assert!(matches!(error, AnubisError::Refused { .. }));
+
+        let files = read_dir(&schema.install_directory.join("api/migrations"));
+        assert_eq!(files, vec!["0001_init.sql"]);
+    }
+
+    #[test]
+    fn dry_runs_number_migrations_after_pending_ones() {
+        let mut schema = mock_schema();
+        start_recording(true);
+        generate_migrations(&schema, false).unwrap();
+
+        schema.models[0].fields.push(ModelFields {
+            name: String::from("name"),
+            required: false,
+            ..Default::default()
+        });
+        generate_migrations(&schema, false).unwrap();
+
+        let migrations_directory = schema.install_directory.join("api/migrations");
+        assert_eq!(
+            read_dir(&migrations_directory),
+            vec!["0001_init.sql", "0002_update.sql"]
+        );
+
+        stop_recording();
+        assert!(!migrations_directory.exists());
+    }
+
+    #[test]
+    fn money_columns_create_their_type_first() {
+        let mut schema = mock_schema();
+        schema.models[0].fields.push(ModelFields {
+            name: String::from("balance"),
+            kind: ModelKind::List(Box::new(ModelKind::Money)),
+            ..Default::default()
+        });
+
+        let changes = diff_snapshots(&[], &snapshot_schema(&schema));
+        let migration = create_migration(&changes);
+
+        assert!(migration.starts_with("\nDO $$ BEGIN\n    CREATE TYPE \"anubis_money\""));
+        assert!(migration.contains("    \"balance\" anubis_money[],\n"));
+    }
+}
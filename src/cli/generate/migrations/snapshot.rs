@@ -0,0 +1,497 @@
+// Copyright © 2024 Navarrotech
+
+// A snapshot is the database shape Anubis last generated migrations for
+// It's compared against the current schema to work out what the next migration needs to do
+
+use std::path::Path;
+
+use crate::error::AnubisError;
+use crate::models::{JoinTable, ModelFields, ModelKind, Models, UseOption};
+use crate::relationships::{ReferentialAction, RelationshipGraph};
+use crate::schema::AnubisSchema;
+use crate::vfs::{read_file, write_file};
+
+pub const SNAPSHOT_FILE: &str = ".anubis/snapshot.json";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableSnapshot {
+    pub name: String,
+    pub columns: Vec<ColumnSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSnapshot {
+    pub name: String,
+    pub sql_type: String,
+    pub nullable: bool,
+    pub primary_key: bool,
+    pub unique: bool,
+    pub default: Option<String>,
+    pub references: Option<ForeignKey>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignKey {
+    pub table: String,
+    pub column: String,
+    pub on_delete: ReferentialAction,
+    pub on_update: ReferentialAction,
+}
+
+impl TableSnapshot {
+    pub fn column(&self, name: &str) -> Option<&ColumnSnapshot> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    // Join tables have a composite primary key, every other table has at most one column
+    pub fn primary_key(&self) -> Vec<&ColumnSnapshot> {
+        self.columns
+            .iter()
+            .filter(|column| column.primary_key)
+            .collect()
+    }
+}
+
+impl ColumnSnapshot {
+    // Unique columns get a unique index, foreign keys get a plain one so joins stay fast
+    // Primary keys are already indexed by Postgres
+    pub fn index(&self, table: &str) -> Option<(String, bool)> {
+        if self.primary_key {
+            None
+        } else if self.unique {
+            Some((format!("{table}_{}_key", self.name), true))
+        } else if self.references.is_some() {
+            Some((format!("{table}_{}_idx", self.name), false))
+        } else {
+            None
+        }
+    }
+}
+
+pub fn snapshot_schema(schema: &AnubisSchema) -> Vec<TableSnapshot> {
+    let graph = RelationshipGraph::build(schema);
+
+    let mut tables: Vec<TableSnapshot> = schema
+        .models
+        .iter()
+        .filter(|model| !schema.is_join_model(&model.name))
+        .map(|model| TableSnapshot {
+            name: model.name.clone(),
+            columns: model
+                .fields
+                .iter()
+                .filter(|field| field.is_column())
+                .map(|field| snapshot_column(&graph, model, field))
+                .collect(),
+        })
+        .collect();
+
+    tables.extend(
+        schema
+            .get_join_tables()
+            .iter()
+            .map(|join_table| snapshot_join_table(&graph, join_table)),
+    );
+
+    tables
+}
+
+// Foreign keys come from the relationship graph, and always match the type of the column they point at
+fn snapshot_column(
+    graph: &RelationshipGraph,
+    model: &Models,
+    field: &ModelFields,
+) -> ColumnSnapshot {
+    let relationship = graph.get_field_relationship(&model.name, &field.name);
+
+    ColumnSnapshot {
+        name: field.name.clone(),
+        sql_type: match relationship {
+            Some(relationship) => get_sql_type(relationship.target_field),
+            None => get_sql_type(field),
+        },
+        nullable: field.is_nullable(),
+        primary_key: field.primary_key,
+        unique: field.unique,
+        default: get_sql_default(field),
+        references: relationship.map(|relationship| ForeignKey {
+            table: relationship.target.name.clone(),
+            column: relationship.target_field.name.clone(),
+            on_delete: relationship.on_delete,
+            on_update: relationship.on_update,
+        }),
+    }
+}
+
+// Join tables are keyed by both linked ids, so the same pair can't be linked twice
+// Deleting either side removes the link
+fn snapshot_join_table(graph: &RelationshipGraph, join_table: &JoinTable) -> TableSnapshot {
+    let key_column = |name: String, model: &Models, key: &ModelFields| ColumnSnapshot {
+        name,
+        sql_type: get_sql_type(key),
+        nullable: false,
+        primary_key: true,
+        unique: false,
+        default: None,
+        references: Some(ForeignKey {
+            table: model.name.clone(),
+            column: key.name.clone(),
+            on_delete: ReferentialAction::Cascade,
+            on_update: ReferentialAction::Cascade,
+        }),
+    };
+
+    let mut columns = vec![
+        key_column(
+            join_table.model_column(),
+            join_table.model,
+            join_table.model_key,
+        ),
+        key_column(
+            join_table.linked_column(),
+            join_table.linked,
+            join_table.linked_key,
+        ),
+    ];
+
+    if let Some(join_model) = join_table.join_model {
+        columns.extend(
+            join_table
+                .extra_fields()
+                .into_iter()
+                .map(|field| snapshot_column(graph, join_model, field)),
+        );
+    }
+
+    TableSnapshot {
+        name: join_table.name.clone(),
+        columns,
+    }
+}
+
+pub fn get_sql_type(field: &ModelFields) -> String {
+    match field.kind {
+        // Hashed values are longer than what the user typed in, so they're never length limited
+        ModelKind::String => match field.maximum {
+            Some(maximum) if !field.hash => format!("VARCHAR({maximum})"),
+            _ => String::from("TEXT"),
+        },
+        _ => field.kind.sql_type(),
+    }
+}
+
+fn get_sql_default(field: &ModelFields) -> Option<String> {
+    match field.use_method {
+        Some(UseOption::Uuid) if field.kind == ModelKind::String => {
+            return Some(String::from("gen_random_uuid()::text"))
+        }
+        Some(UseOption::Uuid) if field.kind == ModelKind::Uuid => {
+            return Some(String::from("gen_random_uuid()"))
+        }
+        Some(UseOption::CreatedAt) | Some(UseOption::UpdatedAt) => {
+            return Some(String::from("now()"))
+        }
+        _ => (),
+    }
+
+    let default = field.default.as_ref()?;
+    if !field.kind.is_valid_default(default) {
+        return None;
+    }
+
+    match field.kind {
+        ModelKind::String | ModelKind::Date | ModelKind::Uuid => {
+            Some(format!("'{}'", default.replace('\'', "''")))
+        }
+        ModelKind::Number | ModelKind::Float | ModelKind::Decimal { .. } => {
+            Some(default.trim().to_string())
+        }
+        ModelKind::Boolean => Some(default.to_uppercase()),
+        _ => None,
+    }
+}
+
+// None before the first migration was generated
+pub fn read_snapshot(install_directory: &Path) -> Result<Option<Vec<TableSnapshot>>, AnubisError> {
+    let snapshot_path = install_directory.join(SNAPSHOT_FILE);
+    let Some(content) = read_file(&snapshot_path) else {
+        return Ok(None);
+    };
+
+    let parsed =
+        json::parse(&content).map_err(|error| AnubisError::invalid_data(&snapshot_path, error))?;
+
+    Ok(Some(
+        parsed["tables"]
+            .members()
+            .map(|table| TableSnapshot {
+                name: table["name"].to_string(),
+                columns: table["columns"]
+                    .members()
+                    .map(|column| ColumnSnapshot {
+                        name: column["name"].to_string(),
+                        sql_type: column["type"].to_string(),
+                        nullable: column["nullable"].as_bool().unwrap_or(true),
+                        primary_key: column["primary_key"].as_bool().unwrap_or(false),
+                        unique: column["unique"].as_bool().unwrap_or(false),
+                        default: column["default"].as_str().map(|value| value.to_string()),
+                        references: match column["references"].is_object() {
+                            true => Some(ForeignKey {
+                                table: column["references"]["table"].to_string(),
+                                column: column["references"]["column"].to_string(),
+                                on_delete: read_action(&column["references"]["on_delete"]),
+                                on_update: read_action(&column["references"]["on_update"]),
+                            }),
+                            false => None,
+                        },
+                    })
+                    .collect(),
+            })
+            .collect(),
+    ))
+}
+
+// Snapshots from before foreign keys had rules were created without any, which Postgres restricts
+fn read_action(value: &json::JsonValue) -> ReferentialAction {
+    value
+        .as_str()
+        .and_then(ReferentialAction::from_name)
+        .unwrap_or(ReferentialAction::Restrict)
+}
+
+pub fn write_snapshot(
+    install_directory: &Path,
+    tables: &[TableSnapshot],
+) -> Result<(), AnubisError> {
+    let snapshot_path = install_directory.join(SNAPSHOT_FILE);
+
+    let mut snapshot = json::object! {
+        tables: json::JsonValue::new_array(),
+    };
+
+    for table in tables {
+        let mut columns = json::JsonValue::new_array();
+        for column in table.columns.iter() {
+            columns
+                .push(json::object! {
+                    name: column.name.clone(),
+                    "type": column.sql_type.clone(),
+                    nullable: column.nullable,
+                    primary_key: column.primary_key,
+                    unique: column.unique,
+                    default: column.default.clone(),
+                    references: column.references.as_ref().map(|foreign_key| json::object! {
+                        table: foreign_key.table.clone(),
+                        column: foreign_key.column.clone(),
+                        on_delete: foreign_key.on_delete.name(),
+                        on_update: foreign_key.on_update.name(),
+                    }),
+                })
+                .unwrap();
+        }
+
+        snapshot["tables"]
+            .push(json::object! {
+                name: table.name.clone(),
+                columns: columns,
+            })
+            .unwrap();
+    }
+
+    write_file(&snapshot_path, &json::stringify_pretty(snapshot, 2))
+}
+
+#[cfg(test)]
+mod snapshot_tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn snapshots_round_trip_through_json() {
+        let temp_directory = tempdir().unwrap().into_path();
+        let tables = vec![TableSnapshot {
+            name: String::from("transactions"),
+            columns: vec![
+                ColumnSnapshot {
+                    name: String::from("id"),
+                    sql_type: String::from("TEXT"),
+                    nullable: false,
+                    primary_key: true,
+                    unique: false,
+                    default: Some(String::from("gen_random_uuid()::text")),
+                    references: None,
+                },
+                ColumnSnapshot {
+                    name: String::from("user_id"),
+                    sql_type: String::from("TEXT"),
+                    nullable: true,
+                    primary_key: false,
+                    unique: false,
+                    default: None,
+                    references: Some(ForeignKey {
+                        table: String::from("user"),
+                        column: String::from("id"),
+                        on_delete: ReferentialAction::Cascade,
+                        on_update: ReferentialAction::Restrict,
+                    }),
+                },
+            ],
+        }];
+
+        write_snapshot(&temp_directory, &tables).unwrap();
+
+        assert_eq!(read_snapshot(&temp_directory).unwrap(), Some(tables));
+    }
+
+    #[test]
+    fn foreign_keys_take_the_type_of_the_referenced_column() {
+        let schema = AnubisSchema {
+            models: vec![
+                Models {
+                    name: String::from("user"),
+                    fields: vec![
+                        ModelFields {
+                            name: String::from("id"),
+                            primary_key: true,
+                            maximum: Some(36),
+                            ..Default::default()
+                        },
+                        // `max` on a link limits the relationship, not the column length
+                        ModelFields {
+                            name: String::from("preferences"),
+                            links: Some(String::from("preferences")),
+                            maximum: Some(1),
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                },
+                Models {
+                    name: String::from("preferences"),
+                    fields: vec![ModelFields {
+                        name: String::from("id"),
+                        kind: ModelKind::Number,
+                        primary_key: true,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+            ],
+            ..AnubisSchema::default()
+        };
+
+        let tables = snapshot_schema(&schema);
+
+        assert_eq!(tables[0].columns[0].sql_type, "VARCHAR(36)");
+        assert_eq!(tables[0].columns[1].sql_type, "INTEGER");
+        assert_eq!(
+            tables[0].columns[1].references,
+            Some(ForeignKey {
+                table: String::from("preferences"),
+                column: String::from("id"),
+                on_delete: ReferentialAction::Restrict,
+                on_update: ReferentialAction::Cascade,
+            })
+        );
+    }
+
+    #[test]
+    fn many_to_many_links_get_a_join_table() {
+        let schema = crate::cli::parse::parse_schema_str(
+            "
+project:
+  name: Test
+models:
+  user:
+    fields:
+      - name: id
+        primary_key: true
+      - name: friends
+        links: user
+        through: friendships
+  friendships:
+    fields:
+      - name: since
+        kind: datetime
+        use: created_at
+",
+            std::path::PathBuf::from("/tmp"),
+        )
+        .unwrap();
+
+        let tables = snapshot_schema(&schema);
+
+        assert_eq!(
+            tables
+                .iter()
+                .map(|table| table.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["user", "friendships"]
+        );
+        assert_eq!(tables[0].columns.len(), 1);
+
+        let friendships = &tables[1];
+        assert_eq!(
+            friendships
+                .primary_key()
+                .iter()
+                .map(|column| column.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["user_id", "friends_id"]
+        );
+        assert_eq!(
+            friendships.columns[1].references,
+            Some(ForeignKey {
+                table: String::from("user"),
+                column: String::from("id"),
+                on_delete: ReferentialAction::Cascade,
+                on_update: ReferentialAction::Cascade,
+            })
+        );
+        assert_eq!(
+            friendships.column("since").unwrap().default,
+            Some(String::from("now()"))
+        );
+    }
+
+    #[test]
+    fn kinds_map_to_sql_types_and_defaults() {
+        let fields = [
+            (
+                "decimal(12, 2)",
+                Some("10.50"),
+                "NUMERIC(12, 2)",
+                Some("10.50"),
+            ),
+            ("date", Some("2024-01-31"), "DATE", Some("'2024-01-31'")),
+            ("uuid", None, "UUID", None),
+            ("json", None, "JSONB", None),
+            ("bytes", None, "BYTEA", None),
+            ("list<string>", None, "TEXT[]", None),
+            ("money", None, "anubis_money", None),
+            // Invalid defaults are left out, validate reports them
+            ("number", Some("ten"), "INTEGER", None),
+        ];
+
+        for (kind, default, sql_type, sql_default) in fields {
+            let field = ModelFields {
+                kind: kind.parse().unwrap(),
+                default: default.map(String::from),
+                ..Default::default()
+            };
+
+            assert_eq!(get_sql_type(&field), sql_type, "{kind}");
+            assert_eq!(get_sql_default(&field).as_deref(), sql_default, "{kind}");
+        }
+
+        let generated = ModelFields {
+            kind: ModelKind::Uuid,
+            use_method: Some(UseOption::Uuid),
+            ..Default::default()
+        };
+        assert_eq!(
+            get_sql_default(&generated).as_deref(),
+            Some("gen_random_uuid()")
+        );
+    }
+}
@@ -1,5 +1,8 @@
 // Copyright © 2024 Navarrotech
 
+pub mod api;
 pub mod command;
+pub mod frontend;
+pub mod migrations;
 
 pub mod protobufs;
@@ -0,0 +1,223 @@
+// Copyright © 2024 Navarrotech
+
+// Protobuf clients only know fields by their tag, so once a field has a tag it must keep it
+// Tags are stored in .anubis/proto.lock, much like Cargo.lock pins dependency versions
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::error::AnubisError;
+use crate::vfs::{read_file, write_file};
+
+const LOCK_FILE: &str = ".anubis/proto.lock";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ProtobufLock {
+    // Fully qualified message name -> field name -> tag
+    pub messages: BTreeMap<String, BTreeMap<String, u32>>,
+    // Tags and names of removed fields, these are reserved forever
+    pub retired: BTreeMap<String, BTreeMap<String, u32>>,
+}
+
+impl ProtobufLock {
+    pub fn read(install_directory: &Path) -> Result<ProtobufLock, AnubisError> {
+        let lock_path = install_directory.join(LOCK_FILE);
+        let Some(content) = read_file(&lock_path) else {
+            return Ok(ProtobufLock::default());
+        };
+
+        let parsed =
+            json::parse(&content).map_err(|error| AnubisError::invalid_data(&lock_path, error))?;
+
+        Ok(ProtobufLock {
+            messages: read_tags(&parsed["messages"]),
+            retired: read_tags(&parsed["retired"]),
+        })
+    }
+
+    pub fn write(&self, install_directory: &Path) -> Result<(), AnubisError> {
+        let lock_path = install_directory.join(LOCK_FILE);
+
+        let content = json::stringify_pretty(
+            json::object! {
+                messages: write_tags(&self.messages),
+                retired: write_tags(&self.retired),
+            },
+            2,
+        );
+
+        write_file(&lock_path, &content)
+    }
+
+    // Returns the field's locked tag, or assigns the next free one
+    // Tags below first_tag are left for hand-written fields
+    pub fn get_tag(&mut self, message: &str, field: &str, first_tag: u32) -> u32 {
+        if let Some(tag) = self.messages.get(message).and_then(|tags| tags.get(field)) {
+            return *tag;
+        }
+
+        // Retired tags are never handed out again
+        let tag = self
+            .messages
+            .get(message)
+            .into_iter()
+            .chain(self.retired.get(message))
+            .flat_map(|tags| tags.values())
+            .max()
+            .map(|highest| highest + 1)
+            .unwrap_or(first_tag)
+            .max(first_tag);
+
+        self.messages
+            .entry(message.to_string())
+            .or_default()
+            .insert(field.to_string(), tag);
+        tag
+    }
+
+    // Moves every locked field that's no longer in the message to the retired list
+    pub fn retire_missing(&mut self, message: &str, fields: &[&str]) {
+        let Some(tags) = self.messages.get_mut(message) else {
+            return;
+        };
+
+        let removed: Vec<String> = tags
+            .keys()
+            .filter(|field| !fields.contains(&field.as_str()))
+            .cloned()
+            .collect();
+
+        for field in removed {
+            let tag = tags.remove(&field).unwrap();
+            self.retired
+                .entry(message.to_string())
+                .or_default()
+                .insert(field, tag);
+        }
+    }
+
+    pub fn get_retired_tag(&self, message: &str, field: &str) -> Option<u32> {
+        self.retired.get(message)?.get(field).copied()
+    }
+
+    // `reserved` statements that stop removed tags and names from being reused in the .proto file
+    pub fn create_reserved(&self, message: &str) -> String {
+        let Some(retired) = self
+            .retired
+            .get(message)
+            .filter(|retired| !retired.is_empty())
+        else {
+            return String::new();
+        };
+
+        let mut tags: Vec<u32> = retired.values().copied().collect();
+        tags.sort();
+
+        let mut ranges: Vec<(u32, u32)> = vec![];
+        for tag in tags {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == tag => *end = tag,
+                _ => ranges.push((tag, tag)),
+            }
+        }
+
+        let ranges = ranges
+            .iter()
+            .map(|(start, end)| match start == end {
+                true => start.to_string(),
+                false => format!("{start} to {end}"),
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let names = retired
+            .keys()
+            .map(|name| format!("\"{name}\""))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        format!("    reserved {ranges};\n    reserved {names};\n")
+    }
+}
+
+fn read_tags(value: &json::JsonValue) -> BTreeMap<String, BTreeMap<String, u32>> {
+    let mut messages: BTreeMap<String, BTreeMap<String, u32>> = BTreeMap::new();
+
+    for (message, fields) in value.entries() {
+        let tags = messages.entry(message.to_string()).or_default();
+        for (field, tag) in fields.entries() {
+            if let Some(tag) = tag.as_u32() {
+                tags.insert(field.to_string(), tag);
+            }
+        }
+    }
+
+    messages
+}
+
+fn write_tags(messages: &BTreeMap<String, BTreeMap<String, u32>>) -> json::JsonValue {
+    let mut value = json::JsonValue::new_object();
+
+    for (message, fields) in messages.iter() {
+        let mut tags = json::JsonValue::new_object();
+        for (field, tag) in fields.iter() {
+            tags[field.as_str()] = (*tag).into();
+        }
+        value[message.as_str()] = tags;
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod lock_tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn tags_survive_reordering_and_new_fields() {
+        let temp_directory = tempdir().unwrap().into_path();
+
+        let mut lock = ProtobufLock::read(&temp_directory).unwrap();
+        assert_eq!(lock.get_tag("structs.User", "id", 1), 1);
+        assert_eq!(lock.get_tag("structs.User", "name", 1), 2);
+        lock.write(&temp_directory).unwrap();
+
+        // The same fields in a different order, with a new one in front
+        let mut lock = ProtobufLock::read(&temp_directory).unwrap();
+        assert_eq!(lock.get_tag("structs.User", "email", 1), 3);
+        assert_eq!(lock.get_tag("structs.User", "name", 1), 2);
+        assert_eq!(lock.get_tag("structs.User", "id", 1), 1);
+    }
+
+    #[test]
+    fn tags_start_after_reserved_ones() {
+        let mut lock = ProtobufLock::default();
+
+        assert_eq!(lock.get_tag("SyncResponse", "user", 3), 3);
+        assert_eq!(lock.get_tag("SyncResponse", "transactions", 3), 4);
+    }
+
+    #[test]
+    fn removed_fields_are_retired_and_reserved() {
+        let temp_directory = tempdir().unwrap().into_path();
+
+        let mut lock = ProtobufLock::default();
+        for field in ["id", "name", "nickname", "age", "email"] {
+            lock.get_tag("structs.User", field, 1);
+        }
+        lock.retire_missing("structs.User", &["id", "email"]);
+        lock.write(&temp_directory).unwrap();
+
+        let mut lock = ProtobufLock::read(&temp_directory).unwrap();
+        assert_eq!(lock.get_retired_tag("structs.User", "nickname"), Some(3));
+        assert_eq!(
+            lock.create_reserved("structs.User"),
+            "    reserved 2 to 4;\n    reserved \"age\", \"name\", \"nickname\";\n"
+        );
+
+        // New fields never take a retired tag, even the highest one
+        lock.retire_missing("structs.User", &["id"]);
+        assert_eq!(lock.get_tag("structs.User", "phone", 1), 6);
+    }
+}
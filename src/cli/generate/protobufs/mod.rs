@@ -0,0 +1,876 @@
+// Copyright © 2024 Navarrotech
+
+use std::collections::HashSet;
+
+use lock::ProtobufLock;
+
+use crate::automatrons::write::write_automatron;
+use crate::cli::common::to_pascal_case;
+use crate::error::AnubisError;
+use crate::models::{ModelFields, Models, RelationshipMode};
+use crate::relationships::RelationshipGraph;
+use crate::relics::write::write_relic;
+use crate::schema::AnubisSchema;
+
+pub fn generate_protobufs(schema: &AnubisSchema) -> Result<(), AnubisError> {
+    let mut lock = ProtobufLock::read(&schema.install_directory)?;
+    let links = get_message_links(schema);
+    retire_removed_fields(schema, &mut lock);
+
+    generate_common_protobuf(schema)?;
+    generate_auth_protobuf(schema)?;
+    write_relic(
+        schema,
+        &create_custom_protobuf(),
+        &schema.install_directory.join("proto/custom.proto"),
+    )?;
+
+    write_automatron(
+        schema,
+        &create_root_protobuf(schema, &mut lock),
+        &schema.install_directory.join("./proto/root.proto"),
+    )?;
+
+    for model in get_message_models(schema) {
+        write_automatron(
+            schema,
+            &create_model_protobuf(schema, model, &links, &mut lock),
+            &schema
+                .install_directory
+                .join(format!("./proto/structs/{}.proto", model.name)),
+        )?;
+    }
+
+    lock.write(&schema.install_directory)
+}
+
+fn generate_auth_protobuf(schema: &AnubisSchema) -> Result<(), AnubisError> {
+    let auth_protobuf = String::from(
+        r#"
+syntax = "proto3";
+
+package auth;
+
+// A standardized user struct for authentication
+message User {
+    string id = 1;
+    optional string email = 2;
+    optional string phone = 3;
+    string first_name = 4;
+    string last_name = 5;
+    string password = 6;
+    optional string avatar = 7;
+    optional string bio = 8;
+    UserPreferences preferences = 9;
+    string created_at = 10;
+    string updated_at = 11;
+}
+
+enum Theme {
+    LIGHT = 0;
+    DARK = 1;
+    SYSTEM = 2;
+}
+
+// Preferences for the user
+message UserPreferences {
+    string language = 1;
+    Theme theme = 2;
+    string timezone = 3;
+}
+
+message AuthResponse {
+    bool authorized = 1;
+    User user = 2;
+}
+
+message UnauthorizedRequest {
+    bool authorized = 1;
+}
+
+message AuthorizeByPhoneRequest {
+    string phone = 1;
+    optional string OTP = 2;
+}
+"#,
+    );
+
+    write_automatron(
+        schema,
+        &auth_protobuf,
+        &schema.install_directory.join("./proto/auth.proto"),
+    )
+}
+
+fn generate_common_protobuf(schema: &AnubisSchema) -> Result<(), AnubisError> {
+    let common_protobuf = String::from(
+        r#"
+syntax = "proto3";
+
+package common;
+
+// Used for reporting server-side errors to the client
+// A code is used instead of a stringified message for internationalization
+message ServerError {
+    int32 code = 1;
+}
+
+// An exact amount of money, the amount is a decimal string so no precision is lost
+// The currency is an ISO 4217 code, i.e. USD
+message Money {
+    string amount = 1;
+    string currency = 2;
+}
+
+// A blank message, sometimes used for signaling
+message Blank {
+    int32 i = 1;
+}
+
+// Get request enums
+enum SortOrder {
+    ASC = 0;
+    DESC = 1;
+}
+
+enum FilterOperator {
+    EQUALS = 0;
+    NOT_EQUALS = 1;
+    GREATER_THAN = 2;
+    LESS_THAN = 3;
+    GREATER_THAN_OR_EQUAL = 4;
+    LESS_THAN_OR_EQUAL = 5;
+    CONTAINS = 6;
+    NOT_CONTAINS = 7;
+    STARTS_WITH = 8;
+    ENDS_WITH = 9;
+}
+
+// For GET requests to specify list vs single item
+// Also handles pagination, search, etc
+message ListRequest {
+    string id = 1;
+    optional int32 skip = 2;
+    optional int32 take = 3;
+    optional string search = 4;
+    optional string sort_by = 5;
+    optional SortOrder sort_order = 6;
+    repeated Filter filters = 7;
+}
+
+// For GET requests to filter results
+message Filter {
+    string field = 1;
+    string value = 2;
+    FilterOperator operator = 3;
+}
+
+// Used for reporting client-side errors to the backend
+message ClientErrorReport {
+    string message = 1;
+    string stack_trace = 2;
+}
+
+// Form validation errors to ensure the client is sending the correct data
+// 1. Path is the field path (i.e. 'user.email')
+// 2. Key is the field name (i.e. 'email')
+// 3. Code is the error code for internationalization
+message FormInvalid {
+    string path = 1;
+    string key = 2;
+    int32 code = 3;
+    optional int32 max_length = 4;
+    optional int32 min_length = 5;
+    optional int32 max_value = 6;
+    optional int32 min_value = 7;
+    repeated string required_missing_fields = 8;
+    optional bool invalid_type = 9;
+    optional bool invalid_email = 10;
+    optional bool invalid_phone = 11;
+    
+}
+
+message FormsInvalid {
+    repeated FormInvalid invalid = 1;
+}
+
+// This is used for the client to specify a specific item from the server
+// This is mostly used for DELETE requests or GET requests for a single item
+message SpecifyRequest {
+    string id = 1;
+}
+"#,
+    );
+
+    write_automatron(
+        schema,
+        &common_protobuf,
+        &schema.install_directory.join("./proto/common.proto"),
+    )
+}
+
+// Custom messages live in a relic, so they survive every `generate`
+pub fn create_custom_protobuf() -> String {
+    String::from(
+        r#"
+syntax = "proto3";
+
+package custom;
+
+// Add and import your own custom protobuf structs here
+
+"#,
+    )
+}
+
+// Fields and models removed from Anubis.yaml keep their tags reserved, so old clients don't misread new data
+pub fn retire_removed_fields(schema: &AnubisSchema, lock: &mut ProtobufLock) {
+    let graph = RelationshipGraph::build(schema);
+
+    for model in get_message_models(schema) {
+        let fields: Vec<&str> = model
+            .fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect();
+        lock.retire_missing(&format!("structs.{}", to_pascal_case(&model.name)), &fields);
+
+        let owned = get_sync_owned(schema, &graph, model);
+        if !owned.is_empty() {
+            let mut entries = vec![model.name.as_str()];
+            entries.extend(owned.iter().map(|owned| owned.name.as_str()));
+            lock.retire_missing(&format!("root.{}", get_sync_name(model)), &entries);
+        }
+    }
+
+    let top_level: Vec<&str> = get_message_models(schema)
+        .filter(|model| get_sync_owner(schema, &graph, model).is_none())
+        .map(|model| model.name.as_str())
+        .collect();
+    lock.retire_missing("root.SyncResponse", &top_level);
+
+    let models: Vec<&str> = get_message_models(schema)
+        .map(|model| model.name.as_str())
+        .collect();
+    lock.retire_missing("root.ChangeEvent", &models);
+}
+
+pub fn create_root_protobuf(schema: &AnubisSchema, lock: &mut ProtobufLock) -> String {
+    let graph = RelationshipGraph::build(schema);
+    let models_imports = get_message_models(schema)
+        .map(|model| format!("import \"structs/{}.proto\";", model.name))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    // Owned models are synced inside their owner, everything else at the top level
+    let mut models_sync = String::new();
+    let mut sync_messages = String::new();
+    for model in get_message_models(schema) {
+        if get_sync_owner(schema, &graph, model).is_none() {
+            models_sync.push_str(&create_sync_entry(
+                schema,
+                &graph,
+                model,
+                "root.SyncResponse",
+                3,
+                lock,
+                &mut sync_messages,
+            ));
+        }
+    }
+
+    let mut models_changes = String::new();
+    for model in get_message_models(schema) {
+        let message = format!("structs.{}", to_pascal_case(&model.name));
+
+        models_changes.push_str(&format!(
+            "    {message} {name} = {tag};\n",
+            name = model.name,
+            tag = lock.get_tag("root.ChangeEvent", &model.name, 2)
+        ));
+    }
+
+    let sync_reserved = lock.create_reserved("root.SyncResponse");
+    let changes_reserved = lock.create_reserved("root.ChangeEvent");
+
+    format!(
+        r#"
+syntax = "proto3";
+
+package root;
+
+import "auth.proto";
+{models_imports}
+
+// When the application initializes, it will send this message from the server
+// This is designed to bring the client state up to speed with the server
+message SyncResponse {{
+    auth.User current_user = 1;
+    auth.UserPreferences current_preferences = 2;
+{sync_reserved}{models_sync}}}
+{sync_messages}
+// When a database item is updated that is relevant to the client, 
+// the server will send this message to the client to keep their state in sync
+message ChangeEvent {{
+    string type = 1;
+{changes_reserved}{models_changes}}}
+"#
+    )
+}
+
+// A model's entry in a sync message, models that own others are sent as `<Model>Sync` messages
+fn create_sync_entry(
+    schema: &AnubisSchema,
+    graph: &RelationshipGraph,
+    model: &Models,
+    message: &str,
+    first_tag: u32,
+    lock: &mut ProtobufLock,
+    sync_messages: &mut String,
+) -> String {
+    let owned = get_sync_owned(schema, graph, model);
+    let message_type = match owned.is_empty() {
+        true => format!("structs.{}", to_pascal_case(&model.name)),
+        false => {
+            create_sync_message(schema, graph, model, &owned, lock, sync_messages);
+            get_sync_name(model)
+        }
+    };
+
+    format!(
+        "    {label}{message_type} {name} = {tag};\n",
+        label = if model.mode == RelationshipMode::OneToMany {
+            "repeated "
+        } else {
+            ""
+        },
+        name = model.name,
+        tag = lock.get_tag(message, &model.name, first_tag)
+    )
+}
+
+fn create_sync_message(
+    schema: &AnubisSchema,
+    graph: &RelationshipGraph,
+    model: &Models,
+    owned: &[&Models],
+    lock: &mut ProtobufLock,
+    sync_messages: &mut String,
+) {
+    let name = get_sync_name(model);
+    let key = format!("root.{name}");
+
+    let mut fields = format!(
+        "    structs.{} {} = {};\n",
+        to_pascal_case(&model.name),
+        model.name,
+        lock.get_tag(&key, &model.name, 1)
+    );
+    for owned in owned.iter() {
+        fields.push_str(&create_sync_entry(
+            schema,
+            graph,
+            owned,
+            &key,
+            1,
+            lock,
+            sync_messages,
+        ));
+    }
+
+    let reserved = lock.create_reserved(&key);
+    sync_messages.push_str(&format!(
+        "\n// A {model_name} together with everything it owns\nmessage {name} {{\n{reserved}{fields}}}\n",
+        model_name = model.name
+    ));
+}
+
+fn get_sync_name(model: &Models) -> String {
+    format!("{}Sync", to_pascal_case(&model.name))
+}
+
+// The model this one is synced inside of, if any
+pub fn get_sync_owner<'a>(
+    schema: &AnubisSchema,
+    graph: &RelationshipGraph<'a>,
+    model: &Models,
+) -> Option<&'a Models> {
+    graph
+        .get_owner(&model.name)
+        .filter(|owner| !schema.is_join_model(&owner.name))
+}
+
+fn get_sync_owned<'a>(
+    schema: &AnubisSchema,
+    graph: &RelationshipGraph<'a>,
+    model: &Models,
+) -> Vec<&'a Models> {
+    graph
+        .get_owned(&model.name)
+        .into_iter()
+        .filter(|owned| !schema.is_join_model(&owned.name))
+        .collect()
+}
+
+// The lock key of the message a model is synced in, i.e. root.SyncResponse or root.UserSync
+pub fn get_sync_message(
+    schema: &AnubisSchema,
+    graph: &RelationshipGraph,
+    model: &Models,
+) -> String {
+    match get_sync_owner(schema, graph, model) {
+        Some(owner) => format!("root.{}", get_sync_name(owner)),
+        None => String::from("root.SyncResponse"),
+    }
+}
+
+pub fn create_model_protobuf(
+    schema: &AnubisSchema,
+    model: &Models,
+    links: &HashSet<(String, String)>,
+    lock: &mut ProtobufLock,
+) -> String {
+    let name = to_pascal_case(&model.name);
+    let lock_key = format!("structs.{name}");
+
+    let mut enums = String::new();
+    let mut inner_struct = String::new();
+    let mut create_struct = String::new();
+    let mut update_struct = String::new();
+    let mut imports: Vec<String> = vec![];
+
+    for field in model.fields.iter() {
+        let tag = lock.get_tag(&lock_key, &field.name, 1);
+
+        if let Some(values) = &field.use_enum {
+            enums.push_str(&create_enum(model, field, values));
+        }
+
+        // Links are sent as the linked message, but created and updated by id
+        let message_link = match &field.links {
+            Some(linked) if links.contains(&(model.name.clone(), field.name.clone())) => {
+                if linked != &model.name {
+                    imports.push(format!("import \"structs/{linked}.proto\";"));
+                }
+                Some(to_pascal_case(linked))
+            }
+            _ => None,
+        };
+        let input_type = get_input_type(schema, model, field);
+
+        if let (Some(import), None) = (field.kind.proto_import(), &field.links) {
+            imports.push(format!("import \"{import}\";"));
+        }
+
+        // Many-to-many links are sent as the linked ids, and changed with the attach and detach routes
+        if !field.is_column() {
+            if !field.is_private() {
+                inner_struct.push_str(&format!(
+                    "    repeated {input_type} {} = {tag};\n",
+                    field.name
+                ));
+            }
+            continue;
+        }
+
+        // We don't send passwords to the frontend!
+        if !field.is_private() {
+            inner_struct.push_str(&match message_link {
+                Some(message) => format!("    {message} {} = {tag};\n", field.name),
+                None => create_field(&input_type, &field.name, tag, field.is_nullable()),
+            });
+        }
+
+        if !field.is_generated() {
+            create_struct.push_str(&create_field(
+                &input_type,
+                &field.name,
+                tag,
+                !field.required,
+            ));
+        }
+
+        if field.primary_key {
+            update_struct.push_str(&create_field(&input_type, &field.name, tag, false));
+        } else if !field.is_generated() {
+            update_struct.push_str(&create_field(&input_type, &field.name, tag, true));
+        }
+    }
+
+    imports.sort();
+    imports.dedup();
+
+    // Create and update messages share tags with the model message, so they reserve the same ones
+    let reserved = lock.create_reserved(&lock_key);
+    let imports = match imports.is_empty() {
+        true => String::new(),
+        false => format!("\n{}\n", imports.join("\n")),
+    };
+
+    format!(
+        r#"
+syntax = "proto3";
+
+package structs;
+{imports}{enums}
+message {name} {{
+{reserved}{inner_struct}}}
+
+message Create{name} {{
+{reserved}{create_struct}}}
+
+message Update{name} {{
+{reserved}{update_struct}}}
+"#
+    )
+}
+
+// Join models are stored inside their join table, so they don't get a message of their own
+fn get_message_models(schema: &AnubisSchema) -> impl Iterator<Item = &Models> {
+    schema
+        .models
+        .iter()
+        .filter(|model| !schema.is_join_model(&model.name))
+}
+
+fn create_field(proto_type: &str, name: &str, tag: u32, optional: bool) -> String {
+    // Message types always track presence and lists can't be optional,
+    // only scalars and enums take `optional`. Message types are the package qualified ones
+    let is_message = proto_type.contains('.');
+    let is_list = proto_type.starts_with("repeated ");
+    let label = if optional && !is_message && !is_list {
+        "optional "
+    } else {
+        ""
+    };
+
+    format!("    {label}{proto_type} {name} = {tag};\n")
+}
+
+// Enum values share a scope across the whole package, so every value is prefixed with the enum name
+fn create_enum(model: &Models, field: &ModelFields, values: &[String]) -> String {
+    let prefix = to_constant_case(&format!("{}_{}", model.name, field.name));
+
+    let mut variants = format!("    {prefix}_UNSPECIFIED = 0;\n");
+    for (index, value) in values.iter().enumerate() {
+        variants.push_str(&format!(
+            "    {prefix}_{} = {};\n",
+            to_constant_case(value),
+            index + 1
+        ));
+    }
+
+    format!(
+        "\nenum {name} {{\n{variants}}}\n",
+        name = get_enum_name(model, field)
+    )
+}
+
+fn get_enum_name(model: &Models, field: &ModelFields) -> String {
+    to_pascal_case(&format!("{}_{}", model.name, field.name))
+}
+
+// The type used for a field in create and update messages
+fn get_input_type(schema: &AnubisSchema, model: &Models, field: &ModelFields) -> String {
+    if let Some(linked) = &field.links {
+        return schema
+            .models
+            .iter()
+            .find(|model| &model.name == linked)
+            .and_then(|linked| linked.primary_key())
+            .map(|primary_key| primary_key.kind.proto_type())
+            .unwrap_or_else(|| String::from("string"));
+    }
+
+    if field.use_enum.is_some() {
+        return get_enum_name(model, field);
+    }
+
+    field.kind.proto_type()
+}
+
+fn to_constant_case(value: &str) -> String {
+    value
+        .chars()
+        .map(|character| match character.is_ascii_alphanumeric() {
+            true => character.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect()
+}
+
+// Protobuf imports can't be circular, so a link is only sent as a message
+// if the linked model doesn't already link back to this one
+fn get_message_links(schema: &AnubisSchema) -> HashSet<(String, String)> {
+    let mut accepted: Vec<(String, String)> = vec![];
+    let mut links = HashSet::new();
+
+    for model in schema.models.iter() {
+        for field in model.fields.iter() {
+            let Some(linked) = &field.links else {
+                continue;
+            };
+
+            if !field.is_column() || !schema.models.iter().any(|model| &model.name == linked) {
+                continue;
+            }
+
+            if linked == &model.name || !is_reachable(&accepted, linked, &model.name) {
+                if linked != &model.name {
+                    accepted.push((model.name.clone(), linked.clone()));
+                }
+                links.insert((model.name.clone(), field.name.clone()));
+            }
+        }
+    }
+
+    links
+}
+
+fn is_reachable(edges: &[(String, String)], from: &str, to: &str) -> bool {
+    let mut visited: Vec<&str> = vec![];
+    let mut queue = vec![from];
+
+    while let Some(current) = queue.pop() {
+        if current == to {
+            return true;
+        }
+        if visited.contains(&current) {
+            continue;
+        }
+        visited.push(current);
+
+        for (source, target) in edges {
+            if source == current {
+                queue.push(target);
+            }
+        }
+    }
+
+    false
+}
+
+pub mod lock;
+
+#[cfg(test)]
+mod protobufs_tests {
+    use super::*;
+    use crate::models::{ModelKind, UseOption};
+
+    fn mock_schema() -> AnubisSchema {
+        AnubisSchema {
+            models: vec![
+                Models {
+                    name: String::from("user"),
+                    fields: vec![
+                        ModelFields {
+                            name: String::from("id"),
+                            use_method: Some(UseOption::Uuid),
+                            primary_key: true,
+                            required: true,
+                            ..Default::default()
+                        },
+                        ModelFields {
+                            name: String::from("age"),
+                            kind: ModelKind::Number,
+                            ..Default::default()
+                        },
+                        ModelFields {
+                            name: String::from("language"),
+                            default: Some(String::from("en")),
+                            use_enum: Some(vec![String::from("en"), String::from("es")]),
+                            ..Default::default()
+                        },
+                        ModelFields {
+                            name: String::from("preferences"),
+                            links: Some(String::from("preferences")),
+                            required: true,
+                            ..Default::default()
+                        },
+                        ModelFields {
+                            name: String::from("created_at"),
+                            kind: ModelKind::DateTime,
+                            use_method: Some(UseOption::CreatedAt),
+                            required: true,
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                },
+                Models {
+                    name: String::from("preferences"),
+                    fields: vec![
+                        ModelFields {
+                            name: String::from("id"),
+                            primary_key: true,
+                            required: true,
+                            ..Default::default()
+                        },
+                        ModelFields {
+                            name: String::from("user"),
+                            links: Some(String::from("user")),
+                            required: true,
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                },
+            ],
+            ..AnubisSchema::default()
+        }
+    }
+
+    #[test]
+    fn model_protobuf_is_valid_proto3() {
+        let schema = mock_schema();
+        let links = get_message_links(&schema);
+        let mut lock = ProtobufLock::default();
+
+        let content = create_model_protobuf(&schema, &schema.models[0], &links, &mut lock);
+
+        assert!(content.contains("import \"google/protobuf/timestamp.proto\";"));
+        assert!(content.contains("import \"structs/preferences.proto\";"));
+        assert!(content.contains(
+            "enum UserLanguage {\n    USER_LANGUAGE_UNSPECIFIED = 0;\n    USER_LANGUAGE_EN = 1;\n    USER_LANGUAGE_ES = 2;\n}"
+        ));
+        assert!(content.contains(
+            "message User {\n    string id = 1;\n    optional int32 age = 2;\n    UserLanguage language = 3;\n    Preferences preferences = 4;\n    google.protobuf.Timestamp created_at = 5;\n}"
+        ));
+        assert!(content.contains(
+            "message CreateUser {\n    optional int32 age = 2;\n    optional UserLanguage language = 3;\n    string preferences = 4;\n}"
+        ));
+        assert!(content.contains(
+            "message UpdateUser {\n    string id = 1;\n    optional int32 age = 2;\n    optional UserLanguage language = 3;\n    optional string preferences = 4;\n}"
+        ));
+    }
+
+    #[test]
+    fn circular_links_fall_back_to_ids() {
+        let schema = mock_schema();
+        let links = get_message_links(&schema);
+        let mut lock = ProtobufLock::default();
+
+        let content = create_model_protobuf(&schema, &schema.models[1], &links, &mut lock);
+
+        assert!(!content.contains("import"));
+        assert!(
+            content.contains("message Preferences {\n    string id = 1;\n    string user = 2;\n}")
+        );
+    }
+
+    #[test]
+    fn root_protobuf_references_qualified_types() {
+        let schema = mock_schema();
+        let mut lock = ProtobufLock::default();
+
+        let content = create_root_protobuf(&schema, &mut lock);
+
+        assert!(content.contains("    auth.User current_user = 1;\n"));
+        assert!(content.contains("    structs.User user = 3;\n"));
+        assert!(content.contains("    structs.Preferences preferences = 3;\n"));
+    }
+
+    #[test]
+    fn many_to_many_links_are_sent_as_repeated_ids() {
+        let mut schema = mock_schema();
+        schema.models[0].fields.push(ModelFields {
+            name: String::from("favorites"),
+            links: Some(String::from("preferences")),
+            through: Some(String::from("user_favorites")),
+            ..Default::default()
+        });
+        schema.models.push(Models {
+            name: String::from("user_favorites"),
+            ..Default::default()
+        });
+        let links = get_message_links(&schema);
+        let mut lock = ProtobufLock::default();
+
+        let content = create_model_protobuf(&schema, &schema.models[0], &links, &mut lock);
+
+        assert!(content.contains("    repeated string favorites = 6;\n}"));
+        assert!(!content.contains("optional string favorites"));
+
+        // The join model is stored in the join table, it isn't synced on its own
+        let content = create_root_protobuf(&schema, &mut lock);
+        assert!(!content.contains("user_favorites"));
+    }
+
+    #[test]
+    fn richer_kinds_map_to_proto_types() {
+        let mut schema = mock_schema();
+        schema.models[1].fields = vec![
+            ModelFields {
+                name: String::from("balance"),
+                kind: ModelKind::Money,
+                required: false,
+                ..Default::default()
+            },
+            ModelFields {
+                name: String::from("nicknames"),
+                kind: ModelKind::List(Box::new(ModelKind::String)),
+                ..Default::default()
+            },
+            ModelFields {
+                name: String::from("birthday"),
+                kind: ModelKind::Date,
+                required: false,
+                ..Default::default()
+            },
+        ];
+        let links = get_message_links(&schema);
+        let mut lock = ProtobufLock::default();
+
+        let content = create_model_protobuf(&schema, &schema.models[1], &links, &mut lock);
+
+        assert!(content.contains("import \"common.proto\";"));
+        assert!(content.contains(
+            "message Preferences {\n    common.Money balance = 1;\n    repeated string nicknames = 2;\n    optional string birthday = 3;\n}"
+        ));
+    }
+
+    #[test]
+    fn owned_models_are_synced_inside_their_owner() {
+        let mut schema = mock_schema();
+        schema.models[1].has_owner = Some(String::from("user.id"));
+        schema.models[1].mode = RelationshipMode::OneToMany;
+        let mut lock = ProtobufLock::default();
+
+        let content = create_root_protobuf(&schema, &mut lock);
+
+        assert!(content.contains("    UserSync user = 3;\n}"));
+        assert!(content.contains(
+            "message UserSync {\n    structs.User user = 1;\n    repeated structs.Preferences preferences = 2;\n}"
+        ));
+
+        // Models that moved into their owner keep their old tag reserved
+        let mut schema = mock_schema();
+        let mut lock = ProtobufLock::default();
+        create_root_protobuf(&schema, &mut lock);
+        schema.models[1].has_owner = Some(String::from("user.id"));
+        retire_removed_fields(&schema, &mut lock);
+
+        let content = create_root_protobuf(&schema, &mut lock);
+        assert!(content
+            .contains("    reserved 4;\n    reserved \"preferences\";\n    UserSync user = 3;\n"));
+    }
+
+    #[test]
+    fn removed_fields_are_reserved_in_every_message() {
+        let mut schema = mock_schema();
+        let mut lock = ProtobufLock::default();
+        let links = get_message_links(&schema);
+        create_model_protobuf(&schema, &schema.models[1], &links, &mut lock);
+
+        schema.models[1].fields.pop();
+        retire_removed_fields(&schema, &mut lock);
+        let content = create_model_protobuf(&schema, &schema.models[1], &links, &mut lock);
+
+        assert_eq!(
+            content
+                .matches("    reserved 2;\n    reserved \"user\";\n")
+                .count(),
+            3
+        );
+    }
+}
@@ -0,0 +1,610 @@
+// Copyright © 2024 Navarrotech
+
+// Project wide refactors of the project's identity: its name and its copyright header
+// Only the exact spots Anubis wrote the name or header into are rewritten, everything else is left untouched
+
+// Lib
+use chrono::Datelike;
+use clap::Args;
+
+// Core structs
+use crate::cli::common::get_copyright_header;
+use crate::cli::relics::anubis_schema::escape_single_quotes;
+use crate::error::AnubisError;
+use crate::logger::info;
+use crate::manifest::{hash_content, FileStatus, Manifest};
+use crate::schema::AnubisSchema;
+use crate::vfs::{read_file, write_file, FileClass};
+
+#[derive(Args)]
+pub struct RenameArgs {
+    /// The new project name
+    pub name: String,
+
+    #[clap(short = 'd', long, default_value = "")]
+    pub directory: String,
+}
+
+#[derive(Args)]
+pub struct CopyrightArgs {
+    /// Use `{YYYY}` for the current year, i.e. "Copyright © {YYYY} MyCompany"
+    pub header: String,
+
+    #[clap(short = 'd', long, default_value = "")]
+    pub directory: String,
+}
+
+pub fn rename_project(schema: &AnubisSchema, args: &RenameArgs) -> Result<(), AnubisError> {
+    let old_name = schema.project_name.clone();
+    let new_name = args.name.trim().to_string();
+    if new_name.is_empty() {
+        return Err(AnubisError::Usage {
+            message: String::from("The project name can't be empty"),
+        });
+    }
+
+    update_anubis_yaml(schema, &["name"], &new_name)?;
+
+    let mut updated = vec![];
+    for (path, replacements) in get_name_replacements(schema, &old_name, &new_name) {
+        if rewrite_tracked_file(schema, &path, |content| {
+            replacements
+                .iter()
+                .fold(content.to_string(), |content, (old, new)| {
+                    content.replace(old, new)
+                })
+        })? {
+            updated.push(path);
+        }
+    }
+
+    info!("Renamed project from `{old_name}` to `{new_name}`");
+    for path in updated {
+        info!("  updated {path}");
+    }
+    info!("Run `cargo anubis generate` to update the generated files");
+
+    Ok(())
+}
+
+pub fn change_copyright(schema: &AnubisSchema, args: &CopyrightArgs) -> Result<(), AnubisError> {
+    let year = chrono::Utc::now().year().to_string();
+    let old_header = schema.copyright_header.clone();
+    let new_header = args.header.trim().to_string();
+    let new_header_formatted = new_header.replace("{YYYY}", &year);
+
+    update_anubis_yaml(schema, &["copyright_header", "copyright"], &new_header)?;
+
+    // Anubis.yaml isn't tracked in the manifest, but carries a header too
+    let schema_path = schema.install_directory.join("Anubis.yaml");
+    if let Some(content) = read_file(&schema_path) {
+        let rewritten =
+            replace_copyright_header(&content, "Anubis.yaml", &old_header, &new_header_formatted);
+        write_file(&schema_path, &rewritten)?;
+    }
+
+    let manifest = Manifest::read(&schema.install_directory)?;
+    let mut updated = 0;
+    for path in manifest.files.keys() {
+        // Applied migrations are checksummed by sqlx, they must never change
+        if path.starts_with("api/migrations/") {
+            continue;
+        }
+
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        let changed = rewrite_tracked_file(schema, path, |content| {
+            let content =
+                replace_copyright_header(content, file_name, &old_header, &new_header_formatted);
+            match file_name {
+                ".eslintrc.cjs" => replace_eslint_header_rule(
+                    &content,
+                    &old_header,
+                    &new_header,
+                    &new_header_formatted,
+                ),
+                _ => content,
+            }
+        })?;
+
+        if changed {
+            updated += 1;
+        }
+    }
+
+    match new_header_formatted.is_empty() {
+        true => info!("Removed the copyright header from {updated} file(s)"),
+        false => {
+            info!("Changed the copyright header to `{new_header_formatted}` in {updated} file(s)")
+        }
+    }
+
+    Ok(())
+}
+
+// Where the project name was written, and the exact text it was written as
+fn get_name_replacements(
+    schema: &AnubisSchema,
+    old_name: &str,
+    new_name: &str,
+) -> Vec<(String, Vec<(String, String)>)> {
+    let dashed = |name: &str| name.replace(' ', "-");
+
+    let mut replacements: Vec<(&str, Vec<(String, String)>)> = vec![
+        (
+            "frontend/package.json",
+            vec![(
+                format!("\"name\": \"{}\"", dashed(old_name)),
+                format!("\"name\": \"{}\"", dashed(new_name)),
+            )],
+        ),
+        (
+            "frontend/public/manifest.json",
+            vec![
+                (
+                    format!("\"short_name\": \"{old_name}\""),
+                    format!("\"short_name\": \"{new_name}\""),
+                ),
+                (
+                    format!("\"name\": \"{old_name}\""),
+                    format!("\"name\": \"{new_name}\""),
+                ),
+            ],
+        ),
+        (
+            "frontend/index.html",
+            vec![
+                (
+                    format!("<title>{old_name}</title>"),
+                    format!("<title>{new_name}</title>"),
+                ),
+                (
+                    format!("\"og:title\" content=\"{old_name}\""),
+                    format!("\"og:title\" content=\"{new_name}\""),
+                ),
+                (
+                    format!("\"twitter:title\" content=\"{old_name}\""),
+                    format!("\"twitter:title\" content=\"{new_name}\""),
+                ),
+            ],
+        ),
+        (
+            ".github/workflows/build.yml",
+            vec![
+                (
+                    format!("name: Build & Test {old_name}\n"),
+                    format!("name: Build & Test {new_name}\n"),
+                ),
+                (
+                    format!("/{old_name}-api-rust:"),
+                    format!("/{new_name}-api-rust:"),
+                ),
+            ],
+        ),
+        (
+            ".circleci/config.yml",
+            vec![(
+                format!("/{}api-rust:", dashed(old_name)),
+                format!("/{}api-rust:", dashed(new_name)),
+            )],
+        ),
+        (
+            ".gitlab-ci.yml",
+            vec![(
+                format!("/{}-api-rust:", dashed(old_name).to_lowercase()),
+                format!("/{}-api-rust:", dashed(new_name).to_lowercase()),
+            )],
+        ),
+    ];
+
+    // Every locale has its own brand_name, a translated one no longer matches and is left alone
+    let locale_paths = get_translation_paths(schema);
+    for path in locale_paths.iter() {
+        replacements.push((
+            path,
+            vec![(
+                format!("\"brand_name\": \"{old_name}\""),
+                format!("\"brand_name\": \"{new_name}\""),
+            )],
+        ));
+    }
+
+    replacements
+        .into_iter()
+        .map(|(path, replacements)| (path.to_string(), replacements))
+        .collect()
+}
+
+// The translation file in every locale folder, i.e. frontend/public/locales/de/translation.json
+fn get_translation_paths(schema: &AnubisSchema) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(schema.install_directory.join("frontend/public/locales"))
+    else {
+        return vec![];
+    };
+
+    let mut paths: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| {
+            format!(
+                "frontend/public/locales/{}/translation.json",
+                entry.file_name().to_string_lossy()
+            )
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+// Rewrites a project file (and its synthetic cache), returning true if it changed
+// Files that matched the manifest keep matching it, so they aren't mistaken for hand edits
+fn rewrite_tracked_file(
+    schema: &AnubisSchema,
+    path: &str,
+    rewrite: impl Fn(&str) -> String,
+) -> Result<bool, AnubisError> {
+    let install_directory = &schema.install_directory;
+    let Some(content) = read_file(&install_directory.join(path)) else {
+        return Ok(false);
+    };
+
+    let rewritten = rewrite(&content);
+    if rewritten == content {
+        return Ok(false);
+    }
+
+    let mut manifest = Manifest::read(install_directory)?;
+    let unmodified = manifest.get_status(install_directory, path) == FileStatus::Unmodified;
+
+    write_file(&install_directory.join(path), &rewritten)?;
+
+    if let Some(entry) = manifest.files.get_mut(path) {
+        if unmodified {
+            entry.hash = hash_content(&rewritten);
+        }
+
+        // The cache is the merge base for synthetics, it needs the same change or the merge would undo it
+        if entry.class == FileClass::Synthetic {
+            let cache_path = install_directory.join(".anubis/cache").join(path);
+            if let Some(cached) = read_file(&cache_path) {
+                write_file(&cache_path, &rewrite(&cached))?;
+            }
+        }
+    }
+
+    manifest.write(install_directory)?;
+    Ok(true)
+}
+
+// Swaps the header line written by get_copyright_header, matching any year where the old header had {YYYY}
+pub fn replace_copyright_header(
+    content: &str,
+    file_name: &str,
+    old_header: &str,
+    new_header_formatted: &str,
+) -> String {
+    let new_line = get_header_line(file_name, new_header_formatted);
+    let old_line = get_header_line(file_name, old_header);
+
+    // Files without a comment syntax never get a header
+    if old_line.is_empty() && new_line.is_empty() {
+        return content.to_string();
+    }
+
+    let first_line_length = content
+        .find('\n')
+        .map(|index| index + 1)
+        .unwrap_or(content.len());
+    let first_line = &content[..first_line_length];
+
+    if !old_line.is_empty() && matches_template(first_line, &old_line) {
+        return format!("{new_line}{}", &content[first_line_length..]);
+    }
+
+    // Without a previous header, files start with the blank line that's followed by the preamble
+    if old_header.is_empty() && content.starts_with('\n') {
+        return format!("{new_line}{content}");
+    }
+
+    content.to_string()
+}
+
+fn get_header_line(file_name: &str, header: &str) -> String {
+    let header_schema = AnubisSchema {
+        copyright_header_formatted: header.to_string(),
+        ..AnubisSchema::default()
+    };
+    get_copyright_header(&header_schema, file_name)
+}
+
+// True if `text` equals `template`, where every {YYYY} in the template matches any 4 digit year
+fn matches_template(text: &str, template: &str) -> bool {
+    let mut remaining = text;
+
+    for (index, part) in template.split("{YYYY}").enumerate() {
+        if index > 0 {
+            let year = remaining.get(..4).unwrap_or_default();
+            if year.len() != 4 || !year.chars().all(|character| character.is_ascii_digit()) {
+                return false;
+            }
+            remaining = &remaining[4..];
+        }
+
+        match remaining.strip_prefix(part) {
+            Some(rest) => remaining = rest,
+            None => return false,
+        }
+    }
+
+    remaining.is_empty()
+}
+
+// The eslint header rule enforces the copyright header, so it has to follow it
+fn replace_eslint_header_rule(
+    content: &str,
+    old_header: &str,
+    new_header: &str,
+    new_header_formatted: &str,
+) -> String {
+    let old_pattern = old_header.replace("{YYYY}", "\\d{4}");
+    let new_pattern = new_header.replace("{YYYY}", "\\d{4}");
+
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            let trimmed = line.trim();
+
+            if trimmed == format!("pattern: '{old_pattern}',") {
+                format!("{indent}pattern: '{new_pattern}',\n")
+            } else if trimmed.starts_with("template: '")
+                && matches_template(trimmed, &format!("template: '{old_header}'"))
+            {
+                format!("{indent}template: '{new_header_formatted}'\n")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect()
+}
+
+// Sets a value under `project:` in Anubis.yaml, leaving the rest of the file as the user wrote it
+// `keys` is the field name followed by its aliases, whichever one the file already uses is kept
+fn update_anubis_yaml(
+    schema: &AnubisSchema,
+    keys: &[&str],
+    value: &str,
+) -> Result<(), AnubisError> {
+    let schema_path = schema.install_directory.join("Anubis.yaml");
+    let Some(content) = read_file(&schema_path) else {
+        return Err(AnubisError::MissingSchema { path: schema_path });
+    };
+
+    write_file(&schema_path, &set_project_field(&content, keys, value))
+}
+
+pub fn set_project_field(content: &str, keys: &[&str], value: &str) -> String {
+    let value = escape_single_quotes(value);
+    let mut lines: Vec<String> = vec![];
+    let mut in_project = false;
+    let mut project_line = None;
+    let mut replaced = false;
+
+    for line in content.lines() {
+        if !line.starts_with([' ', '\t']) && !line.trim().is_empty() {
+            in_project = line.trim_end() == "project:";
+            if in_project {
+                project_line = Some(lines.len());
+            }
+        } else if let Some(key) = keys
+            .iter()
+            .find(|key| in_project && line.trim_start().starts_with(&format!("{key}:")))
+        {
+            // Only one of the aliases may stay, otherwise the yaml has the field twice
+            if !replaced {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                lines.push(format!("{indent}{key}: '{value}'"));
+                replaced = true;
+            }
+            continue;
+        }
+
+        lines.push(line.to_string());
+    }
+
+    if !replaced {
+        let new_line = format!("  {}: '{value}'", keys[0]);
+        match project_line {
+            Some(index) => lines.insert(index + 1, new_line),
+            None => {
+                lines.push(String::from("project:"));
+                lines.push(new_line);
+            }
+        }
+    }
+
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated
+}
+
+#[cfg(test)]
+mod identity_tests {
+    use super::*;
+    use crate::cli::generate::command::{generate, GenerateArgs};
+    use crate::cli::init::init;
+    use crate::cli::parse::{parse_schema_str, parse_schema_yaml};
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn mock_project() -> AnubisSchema {
+        let schema = AnubisSchema {
+            install_directory: tempdir().unwrap().into_path(),
+            project_name: String::from("Bank"),
+            copyright_header: String::from("Copyright © {YYYY} Bank Inc"),
+            copyright_header_formatted: String::from("Copyright © 2023 Bank Inc"),
+            ..AnubisSchema::default()
+        };
+
+        init(&schema).unwrap();
+        generate(&schema, &GenerateArgs::default()).unwrap();
+        schema
+    }
+
+    fn read(schema: &AnubisSchema, path: &str) -> String {
+        std::fs::read_to_string(schema.install_directory.join(path)).unwrap()
+    }
+
+    #[test]
+    fn sets_project_fields_in_place() {
+        let content = "# Header\n\nproject:\n  name: 'Old'\n  version: '1.0.0'\n\nmodels: {}\n";
+
+        assert_eq!(
+            set_project_field(content, &["name"], "It's new"),
+            "# Header\n\nproject:\n  name: 'It''s new'\n  version: '1.0.0'\n\nmodels: {}\n"
+        );
+        assert_eq!(
+            set_project_field(content, &["copyright_header", "copyright"], "Copyright"),
+            "# Header\n\nproject:\n  copyright_header: 'Copyright'\n  name: 'Old'\n  version: '1.0.0'\n\nmodels: {}\n"
+        );
+    }
+
+    #[test]
+    fn copyright_aliases_are_replaced_in_place() {
+        let keys = ["copyright_header", "copyright"];
+
+        let content = "project:\n  name: 'Old'\n  copyright_header: 'Old header'\n";
+        let updated = set_project_field(content, &keys, "New header");
+        assert_eq!(
+            updated,
+            "project:\n  name: 'Old'\n  copyright_header: 'New header'\n"
+        );
+        let schema = parse_schema_str(&format!("{updated}models: {{}}\n"), PathBuf::new()).unwrap();
+        assert_eq!(schema.copyright_header, "New header");
+
+        let content = "project:\n  copyright: 'Old header'\n  name: 'Old'\n";
+        assert_eq!(
+            set_project_field(content, &keys, "New header"),
+            "project:\n  copyright: 'New header'\n  name: 'Old'\n"
+        );
+    }
+
+    #[test]
+    fn headers_match_any_year() {
+        assert!(matches_template(
+            "// Copyright © 2021 Bank\n",
+            "// Copyright © {YYYY} Bank\n"
+        ));
+        assert!(!matches_template(
+            "// Copyright © 21 Bank\n",
+            "// Copyright © {YYYY} Bank\n"
+        ));
+        assert_eq!(
+            replace_copyright_header(
+                "// Old 2021\n\n// preamble\n",
+                "main.rs",
+                "Old {YYYY}",
+                "New"
+            ),
+            "// New\n\n// preamble\n"
+        );
+        assert_eq!(
+            replace_copyright_header("\n// preamble\n", "main.rs", "", "New"),
+            "// New\n\n// preamble\n"
+        );
+        assert_eq!(
+            replace_copyright_header("// Old\n\n// preamble\n", "main.rs", "Old", ""),
+            "\n// preamble\n"
+        );
+    }
+
+    #[test]
+    fn renaming_only_touches_the_name() {
+        let schema = mock_project();
+        let custom_rs = read(&schema, "api/src/custom.rs");
+
+        // Other locales are copies of the default one, some with a translated brand name
+        let translation = read(&schema, "frontend/public/locales/en/translation.json");
+        for (locale, content) in [
+            ("de", translation.clone()),
+            ("fr", translation.replace("\"Bank\"", "\"Banque\"")),
+        ] {
+            let locale_directory = schema
+                .install_directory
+                .join("frontend/public/locales")
+                .join(locale);
+            std::fs::create_dir_all(&locale_directory).unwrap();
+            std::fs::write(locale_directory.join("translation.json"), content).unwrap();
+        }
+
+        rename_project(
+            &schema,
+            &RenameArgs {
+                name: String::from("Piggy Bank"),
+                directory: String::new(),
+            },
+        )
+        .unwrap();
+
+        let renamed = parse_schema_yaml(schema.install_directory.clone()).unwrap();
+        assert_eq!(renamed.project_name, "Piggy Bank");
+        assert!(read(&schema, "frontend/package.json").contains("\"name\": \"Piggy-Bank\""));
+        assert!(read(&schema, "frontend/index.html").contains("<title>Piggy Bank</title>"));
+        assert!(read(&schema, "frontend/public/manifest.json")
+            .contains("\"short_name\": \"Piggy Bank\""));
+        assert!(read(&schema, "frontend/public/locales/en/translation.json")
+            .contains("\"brand_name\": \"Piggy Bank\""));
+        assert!(read(&schema, "frontend/public/locales/de/translation.json")
+            .contains("\"brand_name\": \"Piggy Bank\""));
+        assert!(read(&schema, "frontend/public/locales/fr/translation.json")
+            .contains("\"brand_name\": \"Banque\""));
+        assert!(read(&schema, ".github/workflows/build.yml")
+            .starts_with("# Copyright © 2023 Bank Inc\n\n"));
+        assert!(read(&schema, ".github/workflows/build.yml")
+            .contains("name: Build & Test Piggy Bank\n"));
+        assert_eq!(read(&schema, "api/src/custom.rs"), custom_rs);
+    }
+
+    #[test]
+    fn copyright_headers_are_rewritten_everywhere() {
+        let schema = mock_project();
+        let year = chrono::Utc::now().year();
+        let custom_rs = read(&schema, "api/src/custom.rs");
+
+        change_copyright(
+            &schema,
+            &CopyrightArgs {
+                header: String::from("Copyright © {YYYY} Piggy LLC"),
+                directory: String::new(),
+            },
+        )
+        .unwrap();
+
+        let header = format!("Copyright © {year} Piggy LLC");
+        assert!(read(&schema, "Anubis.yaml").starts_with(&format!("# {header}\n")));
+        assert!(read(&schema, "api/src/main.rs").starts_with(&format!("// {header}\n")));
+        assert!(read(&schema, ".github/workflows/build.yml").starts_with(&format!("# {header}\n")));
+        assert!(read(&schema, "frontend/.eslintrc.cjs")
+            .contains("pattern: 'Copyright © \\d{4} Piggy LLC',"));
+
+        // Relic content below the header is untouched
+        assert_eq!(
+            read(&schema, "api/src/custom.rs")
+                .lines()
+                .skip(1)
+                .collect::<Vec<_>>(),
+            custom_rs.lines().skip(1).collect::<Vec<_>>()
+        );
+
+        // Automatrons still match the manifest, so they aren't mistaken for hand edits
+        assert!(
+            crate::manifest::get_edited_automatrons(&schema.install_directory)
+                .unwrap()
+                .is_empty()
+        );
+
+        let parsed = parse_schema_yaml(schema.install_directory.clone()).unwrap();
+        assert_eq!(parsed.copyright_header, "Copyright © {YYYY} Piggy LLC");
+    }
+}
@@ -3,48 +3,254 @@
 // Lib
 use clap::Args;
 use std::io;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+// Dialoguer
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, Input};
 
 // Setup sub-functions
 use crate::cli::init::setup::setup_directories;
+use crate::cli::install::PackageManager;
 use crate::cli::relics::anubis_schema::setup_anubis_schema;
-use crate::cli::relics::cicd::setup_cicd;
+use crate::cli::relics::cicd::{choose_cicd_provider, setup_cicd, CICDProvider};
 use crate::cli::relics::frontend::setup_frontend;
 use crate::cli::relics::gitignore::generate_gitignore;
+use crate::error::AnubisError;
+use crate::logger::{info, step};
+use crate::manifest::update_manifest;
 use crate::schema::AnubisSchema;
+use crate::settings::Settings;
+use crate::vfs::{start_recording, stop_recording};
+
+const DEFAULT_PROJECT_NAME: &str = "My Project";
+const DEFAULT_VERSION: &str = "1.0.0";
 
-#[derive(Args)]
+#[derive(Args, Default)]
 pub struct InitArgs {
     #[clap(short = 'd', long, default_value = "")]
     pub directory: String,
-    #[clap(default_value = "My Project")]
-    pub name: String,
 
-    #[clap(short = 'c', long = "copyright", default_value = "")]
-    pub copy: String,
+    /// The project name can be given as `init <NAME>` or `init --name <NAME>`
+    #[clap(value_name = "NAME")]
+    pub project_name: Option<String>,
+    /// The project name, same as the NAME argument
+    #[clap(short = 'n', long)]
+    pub name: Option<String>,
+
+    /// Use `{YYYY}` for the current year, i.e. "Copyright © {YYYY} MyCompany"
+    #[clap(short = 'c', long = "copyright")]
+    pub copy: Option<String>,
+
+    /// A short description of the project
+    #[clap(long)]
+    pub description: Option<String>,
+
+    /// The project version, defaults to 1.0.0
+    #[clap(long)]
+    pub version: Option<String>,
+
+    /// Which CI/CD pipeline to add
+    #[clap(long, value_enum)]
+    pub ci: Option<CICDProvider>,
+
+    /// Saved to Anubis.yaml's settings, so generated scripts and CI use it too
+    #[clap(long, value_enum)]
+    pub package_manager: Option<PackageManager>,
+
+    /// Install dependencies once the project is generated, asked for when neither flag is given
+    #[clap(long, conflicts_with = "no_install")]
+    pub install: bool,
+    /// Don't install dependencies once the project is generated
+    #[clap(long)]
+    pub no_install: bool,
+
+    /// Never prompt, anything that wasn't given as a flag takes its default
+    #[clap(short = 'y', long, visible_alias = "no-input")]
+    pub yes: bool,
 }
 
-pub fn init(schema: &AnubisSchema) -> io::Result<()> {
-    println!("Running initialization...");
+// The choices made here are saved to the new Anubis.yaml, in `project:` and `settings:`
+pub struct InitOptions {
+    pub schema: AnubisSchema,
+    pub install: bool,
+}
+
+// Prompts are skipped with --yes, or when stdin isn't a terminal (scripts, containers, CI)
+pub fn is_interactive(args: &InitArgs) -> bool {
+    !args.yes && io::stdin().is_terminal()
+}
+
+// Every init decision comes from a flag first, then a prompt, then a default
+pub fn get_init_options(
+    args: &InitArgs,
+    install_directory: PathBuf,
+    year: i32,
+    interactive: bool,
+) -> Result<InitOptions, AnubisError> {
+    let project_name = match args.name.clone().or(args.project_name.clone()) {
+        Some(name) => name,
+        None if interactive => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Project name")
+            .default(String::from(DEFAULT_PROJECT_NAME))
+            .interact_text()?,
+        None => String::from(DEFAULT_PROJECT_NAME),
+    };
+
+    let copyright_header = match args.copy.clone() {
+        Some(copyright) => copyright,
+        None if interactive => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Copyright header: (Leave blank for none)")
+            .with_initial_text(String::from("Copyright © {YYYY} MyCompany"))
+            .allow_empty(true)
+            .interact_text()?,
+        None => String::new(),
+    };
+
+    let description = match args.description.clone() {
+        Some(description) => description,
+        None if interactive => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Description: (Leave blank for none)")
+            .allow_empty(true)
+            .interact_text()?,
+        None => String::new(),
+    };
+
+    let version = match args.version.clone() {
+        Some(version) => version,
+        None if interactive => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Version")
+            .default(String::from(DEFAULT_VERSION))
+            .interact_text()?,
+        None => String::from(DEFAULT_VERSION),
+    };
+
+    let cicd = match args.ci.clone() {
+        Some(cicd) => cicd,
+        None if interactive => choose_cicd_provider()?,
+        None => CICDProvider::GitHubActions,
+    };
+
+    let install = match (args.install, args.no_install) {
+        (true, _) => true,
+        (_, true) => false,
+        _ if interactive => Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Install dependencies now?")
+            .default(true)
+            .interact()?,
+        _ => true,
+    };
+
+    let copyright_header_formatted = copyright_header.replace("{YYYY}", &year.to_string());
+
+    Ok(InitOptions {
+        schema: AnubisSchema {
+            project_name,
+            description,
+            version,
+            copyright_header,
+            copyright_header_formatted,
+            install_directory,
+            settings: Settings {
+                ci: cicd,
+                package_manager: args.package_manager,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        install,
+    })
+}
+
+pub fn init(schema: &AnubisSchema) -> Result<(), AnubisError> {
+    info!("Running initialization...");
+    start_recording(false);
 
     // Setup base directories
-    setup_directories(schema.install_directory.as_path());
+    step("Created directories", || {
+        setup_directories(schema.install_directory.as_path())
+    })?;
 
     // Base gitignore:
-    generate_gitignore(schema);
+    step("Created .gitignore", || generate_gitignore(schema))?;
 
     // Setup base Anubis.yaml Schema
-    setup_anubis_schema(schema);
+    step("Created Anubis.yaml", || setup_anubis_schema(schema))?;
 
-    // Have the user choose which CI/CD they want to use
-    setup_cicd(schema);
+    // Setup the CI/CD the user chose
+    step("Set up CI/CD", || setup_cicd(schema, &schema.settings.ci))?;
 
     // Setup frontend
-    setup_frontend(schema);
+    step("Set up the frontend", || setup_frontend(schema))?;
 
-    // TODO: Create Dockerfiles
     // TODO: Create README
 
+    update_manifest(&schema.install_directory, &stop_recording(), "init")?;
+
     Ok(())
 }
 
 pub mod setup;
+
+#[cfg(test)]
+mod init_tests {
+    use super::*;
+
+    #[test]
+    fn flags_are_used_without_prompting() {
+        let args = InitArgs {
+            name: Some(String::from("Bank")),
+            copy: Some(String::from("Copyright © {YYYY} Bank Inc")),
+            description: Some(String::from("Banking for everyone")),
+            version: Some(String::from("0.1.0")),
+            ci: Some(CICDProvider::GitLabCI),
+            package_manager: Some(PackageManager::Pnpm),
+            no_install: true,
+            yes: true,
+            ..Default::default()
+        };
+
+        let options = get_init_options(&args, PathBuf::from("/tmp/bank"), 2024, false).unwrap();
+
+        assert_eq!(options.schema.project_name, "Bank");
+        assert_eq!(
+            options.schema.copyright_header_formatted,
+            "Copyright © 2024 Bank Inc"
+        );
+        assert_eq!(options.schema.description, "Banking for everyone");
+        assert_eq!(options.schema.version, "0.1.0");
+        assert_eq!(options.schema.settings.ci, CICDProvider::GitLabCI);
+        assert_eq!(
+            options.schema.settings.package_manager,
+            Some(PackageManager::Pnpm)
+        );
+        assert!(!options.install);
+    }
+
+    #[test]
+    fn defaults_are_used_when_not_interactive() {
+        let args = InitArgs {
+            project_name: Some(String::from("Positional")),
+            ..Default::default()
+        };
+
+        let options = get_init_options(&args, PathBuf::from("/tmp/project"), 2024, false).unwrap();
+
+        assert_eq!(options.schema.project_name, "Positional");
+        assert_eq!(options.schema.copyright_header, "");
+        assert_eq!(options.schema.version, "1.0.0");
+        assert_eq!(options.schema.settings.ci, CICDProvider::GitHubActions);
+        assert!(options.install);
+    }
+
+    #[test]
+    fn yes_disables_prompts() {
+        let args = InitArgs {
+            yes: true,
+            ..Default::default()
+        };
+
+        assert!(!is_interactive(&args));
+    }
+}
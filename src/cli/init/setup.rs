@@ -3,6 +3,9 @@
 use std::fs;
 use std::path::Path;
 
+use crate::error::AnubisError;
+use crate::logger::debug;
+
 const DIRS: &[&str] = &[
     "frontend/src",
     "frontend/public/locales/en",
@@ -26,14 +29,16 @@ const DIRS: &[&str] = &[
     "proto/",
 ];
 
-pub fn setup_directories(base_path: &Path) {
+pub fn setup_directories(base_path: &Path) -> Result<(), AnubisError> {
     for &dir in DIRS {
         let path = base_path.join(dir);
         if !path.exists() {
-            fs::create_dir_all(&path).expect("Failed to create base directory");
-            println!("Created directory: {}", path.display());
+            fs::create_dir_all(&path).map_err(|error| AnubisError::io(&path, error))?;
+            debug!("Created directory: {}", path.display());
         }
     }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -46,7 +51,7 @@ mod setup_tests {
         let temp_dir = tempdir().unwrap();
         let base_path = temp_dir.path();
 
-        setup_directories(base_path);
+        setup_directories(base_path).unwrap();
 
         for &dir in DIRS {
             let path = temp_dir.path().join(dir);
@@ -59,7 +64,7 @@ mod setup_tests {
         let temp_dir = tempdir().unwrap();
         let base_path = temp_dir.path().join("test_subdir");
 
-        setup_directories(base_path.as_path());
+        setup_directories(base_path.as_path()).unwrap();
 
         for &dir in DIRS {
             let path = temp_dir.path().join("test_subdir").join(dir);
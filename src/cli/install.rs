@@ -1,50 +1,330 @@
 // Copyright © 2024 Navarrotech
 
 // Lib
-use clap::Args;
-use std::process::Command;
+use clap::{Args, ValueEnum};
+use serde::Deserialize;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::thread;
 
 // Core structs
+use crate::error::AnubisError;
+use crate::logger::{self, info, warning, Level};
 use crate::schema::AnubisSchema;
 
-#[derive(Args)]
+#[derive(Args, Default)]
 pub struct InstallDependenciesArgs {
     #[clap(short = 'd', long, default_value = "")]
     pub directory: String,
+
+    /// Defaults to whichever lockfile the frontend already has, or yarn
+    #[clap(long, value_enum)]
+    pub package_manager: Option<PackageManager>,
 }
 
-pub fn install_dependencies(schema: &AnubisSchema, args: &InstallDependenciesArgs) {
-    // Run command to cd frontend && yarn install
-    let frontend_directory = schema.install_directory.clone().join("frontend");
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Yarn,
+    Npm,
+    Pnpm,
+    Bun,
+}
 
-    let install_frontend = thread::spawn(move || {
-        let status = Command::new("yarn")
-            .arg("install")
-            .current_dir(frontend_directory)
-            .status()
-            .expect("Failed to run yarn install in project1");
+impl PackageManager {
+    // The lockfile each package manager writes, in the order they're looked for
+    fn all() -> Vec<(PackageManager, &'static str)> {
+        vec![
+            (PackageManager::Yarn, "yarn.lock"),
+            (PackageManager::Pnpm, "pnpm-lock.yaml"),
+            (PackageManager::Bun, "bun.lockb"),
+            (PackageManager::Bun, "bun.lock"),
+            (PackageManager::Npm, "package-lock.json"),
+        ]
+    }
 
-        if status.success() {
-            println!("yarn install completed successfully in project1");
-        } else {
-            println!("yarn install failed in project1");
+    pub fn lockfile(&self) -> &'static str {
+        match self {
+            PackageManager::Yarn => "yarn.lock",
+            PackageManager::Npm => "package-lock.json",
+            PackageManager::Pnpm => "pnpm-lock.yaml",
+            PackageManager::Bun => "bun.lockb",
         }
-    });
+    }
+
+    // Installs the dependencies on a plain node image, where only npm and yarn come preinstalled
+    pub fn install_command(&self, frozen: bool) -> String {
+        let install = match (self, frozen) {
+            (PackageManager::Npm, true) => "npm ci",
+            (PackageManager::Npm, false) => "npm install",
+            (PackageManager::Yarn, true) => "yarn install --frozen-lockfile",
+            (PackageManager::Pnpm, true) => "corepack enable && pnpm install --frozen-lockfile",
+            (PackageManager::Pnpm, false) => "corepack enable && pnpm install",
+            (PackageManager::Bun, true) => "npm install -g bun && bun install --frozen-lockfile",
+            (PackageManager::Bun, false) => "npm install -g bun && bun install",
+            (PackageManager::Yarn, false) => "yarn install",
+        };
+        String::from(install)
+    }
+
+    // Runs a package.json script, i.e. `npm run build`
+    pub fn run_script(&self, script: &str) -> String {
+        match self {
+            PackageManager::Npm | PackageManager::Bun => format!("{self} run {script}"),
+            PackageManager::Yarn | PackageManager::Pnpm => format!("{self} {script}"),
+        }
+    }
+
+    // Runs a binary from node_modules, i.e. `npx tsc`
+    pub fn run_binary(&self, binary: &str) -> String {
+        match self {
+            PackageManager::Npm => format!("npx {binary}"),
+            PackageManager::Bun => format!("bunx {binary}"),
+            PackageManager::Yarn | PackageManager::Pnpm => format!("{self} {binary}"),
+        }
+    }
+}
 
-    // Run command to cd backend && cargo install
-    let backend_directory = schema.install_directory.clone().join("api");
-    let install_backend = thread::spawn(move || {
-        let status = Command::new("cargo")
-            .arg("build")
-            .current_dir(backend_directory)
-            .status()
-            .expect("Failed to run cargo build in project1");
-
-        if status.success() {
-            println!("cargo build completed successfully in project1");
-        } else {
-            println!("cargo build failed in project1");
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackageManager::Yarn => write!(f, "yarn"),
+            PackageManager::Npm => write!(f, "npm"),
+            PackageManager::Pnpm => write!(f, "pnpm"),
+            PackageManager::Bun => write!(f, "bun"),
         }
+    }
+}
+
+// One part of the project with its own dependencies, like the frontend or the api
+pub struct Subproject {
+    pub name: &'static str,
+    pub directory: PathBuf,
+    // The file that has to exist for the subproject to be installed, i.e. package.json
+    pub manifest: &'static str,
+    pub program: String,
+    pub arguments: Vec<&'static str>,
+}
+
+impl Subproject {
+    fn command_line(&self) -> String {
+        format!("{} {}", self.program, self.arguments.join(" "))
+    }
+}
+
+// The flag wins, then Anubis.yaml's settings, then an existing lockfile, then yarn
+pub fn get_package_manager(
+    frontend_directory: &Path,
+    choice: Option<PackageManager>,
+) -> PackageManager {
+    choice
+        .or_else(|| get_lockfile_package_manager(frontend_directory))
+        .unwrap_or(PackageManager::Yarn)
+}
+
+pub fn get_lockfile_package_manager(frontend_directory: &Path) -> Option<PackageManager> {
+    PackageManager::all()
+        .into_iter()
+        .find(|(_, lockfile)| frontend_directory.join(lockfile).exists())
+        .map(|(package_manager, _)| package_manager)
+}
+
+pub fn get_subprojects(schema: &AnubisSchema, args: &InstallDependenciesArgs) -> Vec<Subproject> {
+    let frontend_directory = schema.install_directory.join("frontend");
+    let package_manager = get_package_manager(
+        &frontend_directory,
+        args.package_manager.or(schema.settings.package_manager),
+    );
+
+    vec![
+        Subproject {
+            name: "frontend",
+            directory: frontend_directory,
+            manifest: "package.json",
+            program: package_manager.to_string(),
+            arguments: vec!["install"],
+        },
+        Subproject {
+            name: "api",
+            directory: schema.install_directory.join("api"),
+            manifest: "Cargo.toml",
+            program: String::from("cargo"),
+            arguments: vec!["build"],
+        },
+    ]
+}
+
+// Installs every subproject side by side, waiting for all of them before returning
+// Any that fail are reported together, so one failure doesn't hide the other
+pub fn install_dependencies(
+    schema: &AnubisSchema,
+    args: &InstallDependenciesArgs,
+) -> Result<(), AnubisError> {
+    run_subprojects(&get_subprojects(schema, args))
+}
+
+pub fn run_subprojects(subprojects: &[Subproject]) -> Result<(), AnubisError> {
+    let subprojects: Vec<&Subproject> = subprojects
+        .iter()
+        .filter(|subproject| {
+            let exists = subproject.directory.join(subproject.manifest).exists();
+            if !exists {
+                warning!(
+                    "Skipping {name}, {name}/{manifest} doesn't exist",
+                    name = subproject.name,
+                    manifest = subproject.manifest
+                );
+            }
+            exists
+        })
+        .collect();
+
+    let failures: Vec<String> = thread::scope(|scope| {
+        let handles: Vec<_> = subprojects
+            .iter()
+            .map(|subproject| scope.spawn(move || run_subproject(subproject)))
+            .collect();
+
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().expect("install thread panicked").err())
+            .collect()
     });
+
+    if !failures.is_empty() {
+        return Err(AnubisError::Tool { failures });
+    }
+
+    Ok(())
+}
+
+// Runs a subproject's install, streaming its output line by line with a `[name]` prefix
+fn run_subproject(subproject: &Subproject) -> Result<(), String> {
+    info!(
+        "[{}] Running `{}`",
+        subproject.name,
+        subproject.command_line()
+    );
+
+    let mut child = Command::new(&subproject.program)
+        .args(&subproject.arguments)
+        .current_dir(&subproject.directory)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| {
+            format!(
+                "{}: unable to run `{}`: {error}",
+                subproject.name,
+                subproject.command_line()
+            )
+        })?;
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+
+    // Both pipes are drained at once, a full pipe would otherwise block the child
+    thread::scope(|scope| {
+        scope.spawn(|| stream_output(subproject.name, stdout));
+        scope.spawn(|| stream_output(subproject.name, stderr));
+    });
+
+    let status = child
+        .wait()
+        .map_err(|error| format!("{}: {error}", subproject.name))?;
+
+    if !status.success() {
+        return Err(format!(
+            "{}: `{}` failed ({status})",
+            subproject.name,
+            subproject.command_line()
+        ));
+    }
+
+    info!(
+        "[{}] `{}` completed successfully",
+        subproject.name,
+        subproject.command_line()
+    );
+    Ok(())
+}
+
+fn stream_output(name: &str, output: impl Read) {
+    for line in BufReader::new(output).lines().map_while(Result::ok) {
+        logger::log_with(
+            Level::Info,
+            &format!("[{name}] {line}"),
+            json::object! { subproject: name },
+        );
+    }
+}
+
+#[cfg(test)]
+mod install_tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn mock_subproject(directory: &Path, program: &str) -> Subproject {
+        std::fs::write(directory.join("package.json"), "{}").unwrap();
+
+        Subproject {
+            name: "frontend",
+            directory: directory.to_path_buf(),
+            manifest: "package.json",
+            program: program.to_string(),
+            arguments: vec![],
+        }
+    }
+
+    #[test]
+    fn package_manager_follows_the_lockfile() {
+        let temp_directory = tempdir().unwrap().into_path();
+        assert_eq!(
+            get_package_manager(&temp_directory, None),
+            PackageManager::Yarn
+        );
+
+        std::fs::write(temp_directory.join("pnpm-lock.yaml"), "").unwrap();
+        assert_eq!(
+            get_package_manager(&temp_directory, None),
+            PackageManager::Pnpm
+        );
+
+        // The flag always wins
+        assert_eq!(
+            get_package_manager(&temp_directory, Some(PackageManager::Bun)),
+            PackageManager::Bun
+        );
+    }
+
+    #[test]
+    fn missing_subprojects_are_skipped() {
+        let schema = AnubisSchema {
+            install_directory: tempdir().unwrap().into_path(),
+            ..AnubisSchema::default()
+        };
+
+        assert!(install_dependencies(&schema, &InstallDependenciesArgs::default()).is_ok());
+    }
+
+    #[test]
+    fn failures_are_combined() {
+        let first = tempdir().unwrap().into_path();
+        let second = tempdir().unwrap().into_path();
+        let mut api = mock_subproject(&second, "anubis-missing-tool");
+        api.name = "api";
+
+        let result = run_subprojects(&[mock_subproject(&first, "anubis-missing-tool"), api]);
+
+        match result {
+            Err(AnubisError::Tool { failures }) => {
+                assert_eq!(failures.len(), 2);
+                assert!(failures[0].starts_with("frontend: unable to run"));
+                assert!(failures[1].starts_with("api: unable to run"));
+            }
+            _ => panic!("Expected both installs to fail"),
+        }
+    }
 }
@@ -1,10 +1,13 @@
 // Copyright © 2024 Navarrotech
 
 pub mod common;
+pub mod files;
 pub mod generate;
+pub mod identity;
 pub mod init;
 pub mod install;
 pub mod parse;
 pub mod relics;
+pub mod resolve;
 pub mod synthetics;
 pub mod validate;
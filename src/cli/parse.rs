@@ -1,194 +1,890 @@
 // Copyright © 2024 Navarrotech
 
 // Lib
-extern crate yaml_rust;
-use std::path::PathBuf;
+use std::fmt;
+use std::path::{Path, PathBuf};
 
 use chrono::Datelike;
-use yaml_rust::{Yaml, YamlLoader};
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
 
 // Custom modules
-use crate::models::{FormatChoice, ModelFields, ModelKind, Models, UseOption};
+use crate::cli::install::PackageManager;
+use crate::cli::relics::cicd::CICDProvider;
+use crate::models::{
+    FormatChoice, ModelFields, ModelKind, Models, RelationshipMode, Span, UseOption,
+};
+use crate::relationships::ReferentialAction;
 use crate::schema::AnubisSchema;
+use crate::settings::{ScalingMode, Settings, Styling};
+
+// The newest Anubis.yaml format this version of Anubis understands
+pub const SCHEMA_VERSION: u32 = 1;
+
+pub const SCHEMA_FILE: &str = "Anubis.yaml";
+
+// The typed document model of Anubis.yaml
+// These structs mirror the file 1:1, and are converted into AnubisSchema once parsed.
+// Unknown keys are rejected so typos don't silently get ignored.
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SchemaDocument {
+    #[serde(default = "default_schema_version")]
+    schema_version: u32,
+    project: ProjectDocument,
+    #[serde(default)]
+    settings: Option<SettingsDocument>,
+    // Globs relative to Anubis.yaml, i.e. `models/*.yaml`, for files with more models
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_models")]
+    models: Vec<(String, ModelDocument)>,
+}
 
-#[derive(Debug)]
-struct ProjectSchema {
-    name: Option<String>,
+// A file named by `include:`, which only holds models
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IncludeDocument {
+    #[serde(default, deserialize_with = "deserialize_models")]
+    models: Vec<(String, ModelDocument)>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProjectDocument {
+    name: String,
+    #[serde(default, deserialize_with = "deserialize_scalar")]
     version: Option<String>,
+    #[serde(default, alias = "copyright")]
     copyright_header: Option<String>,
+    #[serde(default)]
     description: Option<String>,
 }
 
-pub fn parse_schema_yaml(root_directory: PathBuf) -> AnubisSchema {
-    println!("Parsing schema.yaml...");
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SettingsDocument {
+    #[serde(default)]
+    scaling: Option<ScalingMode>,
+    #[serde(default, alias = "serverless")]
+    serverless_api: Option<bool>,
+    #[serde(default)]
+    json_api: Option<bool>,
+    #[serde(default)]
+    package_manager: Option<PackageManager>,
+    #[serde(default, alias = "cicd")]
+    ci: Option<CICDProvider>,
+    #[serde(default)]
+    styling: Option<Styling>,
+    #[serde(default, alias = "locale")]
+    default_locale: Option<String>,
+}
 
-    // Read schema.yaml file
-    let yaml_content = std::fs::read_to_string(
-        root_directory
-            .join("Anubis.yaml")
-            .to_str()
-            .expect("Could not convert schema.yaml path to string"),
-    )
-    .expect("Could not read schema.yaml file");
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModelDocument {
+    #[serde(default)]
+    mode: Option<RelationshipMode>,
+    #[serde(default)]
+    has_owner: Option<String>,
+    #[serde(default)]
+    fields: Vec<FieldDocument>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FieldDocument {
+    // Core fields, required
+    name: String,
+    #[serde(default)]
+    kind: Option<ModelKind>,
+
+    // Core fields, optional
+    #[serde(default, deserialize_with = "deserialize_scalar")]
+    default: Option<String>,
+
+    // Boolean fields
+    #[serde(default)]
+    primary_key: bool,
+    #[serde(default)]
+    required: Option<bool>,
+    // `encrypt` is the old name, the value was always hashed
+    #[serde(default, alias = "encrypt")]
+    hash: bool,
+    #[serde(default)]
+    replicate: Option<bool>,
+    #[serde(default)]
+    unique: bool,
+
+    // Enums
+    #[serde(default, rename = "use")]
+    use_method: Option<UseOption>,
+    #[serde(default)]
+    format: Option<FormatChoice>,
+
+    // Number fields
+    #[serde(default, alias = "minimum")]
+    min: Option<u32>,
+    #[serde(default, alias = "maximum")]
+    max: Option<u32>,
+
+    // Misc
+    #[serde(default, alias = "replaceAll")]
+    replace_all: Option<String>,
+    #[serde(default, alias = "onUnknown")]
+    on_unknown: Option<String>,
+    #[serde(default, rename = "match")]
+    use_match: Option<OneOrMany>,
+    #[serde(default, rename = "enum", deserialize_with = "deserialize_scalars")]
+    use_enum: Option<Vec<String>>,
+    #[serde(default)]
+    links: Option<String>,
+    #[serde(default)]
+    through: Option<String>,
+    #[serde(default, alias = "onDelete")]
+    on_delete: Option<ReferentialAction>,
+    #[serde(default, alias = "onUpdate")]
+    on_update: Option<ReferentialAction>,
+}
+
+// Some keys accept either a single value or a list of values
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug)]
+pub struct SchemaError {
+    pub message: String,
+    // Dotted path to the offending value when serde_yaml knows it, i.e. `models.user.fields[0].kind`
+    pub path: Option<String>,
+    // Relative to Anubis.yaml's directory, i.e. `models/user.yaml`
+    pub file: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl SchemaError {
+    fn new(file: &str, message: String) -> Self {
+        SchemaError {
+            message,
+            path: None,
+            file: file.to_string(),
+            line: None,
+            column: None,
+        }
+    }
 
-    // Parse schema.yaml file
-    let docs = YamlLoader::load_from_str(&yaml_content).unwrap();
-    // let schema: YamlSchema = serde_yaml::from_str(&yaml_content).expect("Could not parse schema.yaml file");
+    fn in_file(self, file: &str) -> Self {
+        SchemaError {
+            file: file.to_string(),
+            ..self
+        }
+    }
+}
 
-    let doc = &docs[0];
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "{}:{line}:{column}: ", self.file)?,
+            _ => write!(f, "{}: ", self.file)?,
+        }
+        if let Some(path) = &self.path {
+            write!(f, "{path}: ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<serde_yaml::Error> for SchemaError {
+    fn from(error: serde_yaml::Error) -> Self {
+        let location = error.location();
+
+        // serde_yaml appends the location to its messages, we report it separately
+        let mut message = error.to_string();
+        if let Some(index) = message.find(" at line ") {
+            message.truncate(index);
+        }
+
+        // Errors below the top level start with the path to the value, i.e. `models.user: ...`
+        let mut path = None;
+        if let Some((prefix, rest)) = message.split_once(": ") {
+            if !prefix.is_empty() && !prefix.contains([' ', '`']) {
+                path = Some(prefix.to_string());
+                message = rest.to_string();
+            }
+        }
 
-    if doc["project"].is_badvalue() {
-        panic!("Invalid schema.yaml file. 'project' is required.");
+        SchemaError {
+            message,
+            path,
+            file: String::from(SCHEMA_FILE),
+            line: location.as_ref().map(|location| location.line()),
+            column: location.as_ref().map(|location| location.column()),
+        }
     }
+}
+
+pub fn parse_schema_yaml(root_directory: PathBuf) -> Result<AnubisSchema, SchemaError> {
+    let yaml_content = read_schema_file(&root_directory, SCHEMA_FILE)?;
+
+    parse_schema_str(&yaml_content, root_directory)
+}
+
+fn read_schema_file(root_directory: &Path, file: &str) -> Result<String, SchemaError> {
+    let path = root_directory.join(file);
+    std::fs::read_to_string(&path).map_err(|error| {
+        SchemaError::new(
+            file,
+            format!("Could not read {path}: {error}", path = path.display()),
+        )
+    })
+}
 
-    if doc["project"]["name"].is_badvalue() {
-        panic!("Invalid schema.yaml file. 'project.name' is required.");
+// Every file matched by `include:`, relative to the root directory and in the order they're listed
+// Each pattern has to match at least one file, so a typo doesn't silently drop models
+pub fn get_included_files(
+    root_directory: &Path,
+    patterns: &[String],
+) -> Result<Vec<String>, SchemaError> {
+    let mut files: Vec<String> = vec![];
+
+    for pattern in patterns.iter() {
+        let full_pattern = root_directory.join(pattern);
+        let paths = glob::glob(&full_pattern.to_string_lossy()).map_err(|error| {
+            SchemaError::new(
+                SCHEMA_FILE,
+                format!("`include: {pattern}` is not a valid glob: {error}"),
+            )
+        })?;
+
+        let mut matched = false;
+        for path in paths.filter_map(Result::ok).filter(|path| path.is_file()) {
+            matched = true;
+
+            let file = path
+                .strip_prefix(root_directory)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            if file != SCHEMA_FILE && !files.contains(&file) {
+                files.push(file);
+            }
+        }
+
+        if !matched {
+            return Err(SchemaError::new(
+                SCHEMA_FILE,
+                format!("`include: {pattern}` didn't match any files"),
+            ));
+        }
     }
 
-    let project = parse_project_schema(&doc["project"]);
+    Ok(files)
+}
 
-    println!("Schema: {:?}", project);
-    println!("Schema parsed successfully!");
+pub fn parse_schema_str(
+    yaml_content: &str,
+    root_directory: PathBuf,
+) -> Result<AnubisSchema, SchemaError> {
+    let document: SchemaDocument = serde_yaml::from_str(yaml_content)?;
+
+    if document.schema_version > SCHEMA_VERSION {
+        return Err(SchemaError::new(
+            SCHEMA_FILE,
+            format!(
+                "schema_version {version} is not supported, please upgrade Anubis (newest supported version is {SCHEMA_VERSION})",
+                version = document.schema_version
+            ),
+        ));
+    }
 
     let now = chrono::Utc::now();
     let year = now.year();
 
-    let copyright_header = match project.copyright_header {
-        Some(ref s) => s.to_string(),
-        None => String::from(""),
-    };
+    let project = document.project;
+    let copyright_header = project.copyright_header.unwrap_or_default();
+    let copyright_header_formatted = copyright_header.replace("{YYYY}", &year.to_string());
+
+    let mut models: Vec<Models> = document
+        .models
+        .into_iter()
+        .map(|(name, model)| parse_model(name, model, SCHEMA_FILE))
+        .collect();
+    find_spans(yaml_content, &mut models);
+
+    // Models defined in more than one file are kept, validate reports them with both files
+    for file in get_included_files(&root_directory, &document.include)? {
+        let content = read_schema_file(&root_directory, &file)?;
+        let included: IncludeDocument = serde_yaml::from_str(&content)
+            .map_err(|error| SchemaError::from(error).in_file(&file))?;
+
+        let mut included_models: Vec<Models> = included
+            .models
+            .into_iter()
+            .map(|(name, model)| parse_model(name, model, &file))
+            .collect();
+        find_spans(&content, &mut included_models);
+        models.extend(included_models);
+    }
 
-    let copyright_header_formatted = match project.copyright_header {
-        Some(ref s) => s.to_string().replace("{YYYY}", &year.to_string()),
-        None => String::from(""),
-    };
+    Ok(AnubisSchema {
+        project_name: project.name,
+        version: project.version.unwrap_or_default(),
+        description: project.description.unwrap_or_default(),
+        copyright_header,
+        copyright_header_formatted,
+        install_directory: root_directory,
+        settings: parse_settings(document.settings),
+        models,
+    })
+}
 
-    let description = match project.description {
-        Some(ref s) => s.to_string(),
-        None => String::from(""),
+fn parse_settings(settings: Option<SettingsDocument>) -> Settings {
+    let defaults = Settings::default();
+    let Some(settings) = settings else {
+        return defaults;
     };
 
-    let version = match project.version {
-        Some(ref s) => s.to_string(),
-        None => String::from(""),
-    };
+    Settings {
+        scaling: settings.scaling.unwrap_or(defaults.scaling),
+        serverless_api: settings.serverless_api.unwrap_or(defaults.serverless_api),
+        json_api: settings.json_api.unwrap_or(defaults.json_api),
+        package_manager: settings.package_manager,
+        ci: settings.ci.unwrap_or(defaults.ci),
+        styling: settings.styling.unwrap_or(defaults.styling),
+        default_locale: settings.default_locale.unwrap_or(defaults.default_locale),
+    }
+}
 
-    let models = if doc["models"].is_badvalue() {
-        Vec::new()
-    } else {
-        parse_models(&doc["models"])
+fn parse_model(name: String, model: ModelDocument, source: &str) -> Models {
+    Models {
+        name,
+        source: source.to_string(),
+        span: None,
+        mode: model.mode.unwrap_or(RelationshipMode::OneToOne),
+        has_owner: model.has_owner,
+        fields: model.fields.into_iter().map(parse_field).collect(),
+    }
+}
+
+fn parse_field(field: FieldDocument) -> ModelFields {
+    // Fields are required by default, unless a default value is given or required is set to false
+    let required = field.required.unwrap_or(field.default.is_none());
+
+    ModelFields {
+        name: field.name,
+        kind: field.kind.unwrap_or(ModelKind::String),
+        default: field.default,
+        primary_key: field.primary_key,
+        required,
+        hash: field.hash,
+        replicate: field.replicate.unwrap_or(true),
+        unique: field.unique,
+        use_method: field.use_method,
+        format: field.format,
+        minimum: field.min,
+        maximum: field.max,
+        replace_all: field.replace_all,
+        on_unknown: field.on_unknown,
+        use_match: field.use_match.map(|use_match| match use_match {
+            OneOrMany::One(pattern) => vec![pattern],
+            OneOrMany::Many(patterns) => patterns,
+        }),
+        use_enum: field.use_enum,
+        links: field.links,
+        through: field.through,
+        on_delete: field.on_delete,
+        on_update: field.on_update,
+        span: None,
+    }
+}
+
+// serde_yaml doesn't keep positions, so models and fields are found again in the text of their file
+// Models are keys under the top level `models:`, fields are their `name:` lines, both in file order
+fn find_spans(content: &str, models: &mut [Models]) {
+    let lines: Vec<&str> = content.lines().collect();
+    let Some(mut cursor) = lines.iter().position(|line| line.trim_end() == "models:") else {
+        return;
     };
 
-    AnubisSchema {
-        project_name: project.name.unwrap(),
-        version,
-        description,
-        copyright_header,
-        copyright_header_formatted,
-        install_directory: std::env::current_dir().unwrap(),
-        models,
-        ..Default::default()
-    }
-}
-
-fn parse_models(yaml: &Yaml) -> Vec<Models> {
-    let mut models = Vec::new();
-
-    // For each model...
-    for (key, value) in yaml.as_hash().unwrap() {
-        let mut model = Models::default();
-
-        // If it has a 'fields' key...
-        if !value["fields"].is_badvalue() {
-            let mut model_fields = ModelFields::default();
-            let fields_array = value["fields"].as_vec().unwrap();
-
-            for field in fields_array {
-                let fields = field.as_hash().unwrap();
-
-                for (field_key, field_value) in fields {
-                    let field_name = field_key.as_str().unwrap();
-                    let field_value = field_value.as_str().unwrap_or("");
-
-                    match field_name {
-                        // Core fields, required
-                        "name" => model_fields.name = field_value.to_string(),
-                        "kind" => {
-                            model_fields.kind = match field_value {
-                                "string" => ModelKind::String,
-                                "number" => ModelKind::Number,
-                                "float" => ModelKind::Float,
-                                "boolean" => ModelKind::Boolean,
-                                "datetime" => ModelKind::DateTime,
-                                "date" => ModelKind::DateTime,
-                                _ => ModelKind::String,
-                            }
-                        }
-
-                        // Core fields, optional
-                        "default" => model_fields.default = Some(field_value.to_string()),
-
-                        // Boolean fields (default false)
-                        "primary_key" => model_fields.primary_key = true,
-                        "required" => model_fields.required = true,
-                        "encrypt" => model_fields.encrypt = true,
-                        "replicate" => model_fields.replicate = true,
-                        "unique" => model_fields.unique = true,
-
-                        // Enums
-                        "use" => {
-                            model_fields.use_method = match field_value {
-                                "uuid" => Some(UseOption::Uuid),
-                                "unique" => Some(UseOption::Unique),
-                                "owner" => Some(UseOption::OwnerLink),
-                                "created_at" => Some(UseOption::CreatedAt),
-                                "updated_at" => Some(UseOption::UpdatedAt),
-                                _ => None,
-                            }
-                        }
-                        "format" => {
-                            model_fields.format = match field_value {
-                                "email" => Some(FormatChoice::Email),
-                                "phone" => Some(FormatChoice::Phone),
-                                "password" => Some(FormatChoice::Password),
-                                "secret" => Some(FormatChoice::Secret),
-                                _ => None,
-                            }
-                        }
-
-                        // Number fields
-                        "min" | "minimum" => {
-                            model_fields.minimum = field_value.to_string().parse::<u32>().ok();
-                        }
-                        "max" | "maximum" => {
-                            model_fields.maximum = field_value.to_string().parse::<u32>().ok();
-                        }
-
-                        // Misc
-                        "replace_all" => {
-                            model_fields.replace_all = Some(field_value.parse().unwrap())
-                        }
-                        "match" => model_fields.use_match = Some(field_value.to_string()),
-                        "on_unknown" => model_fields.on_unknown = Some(field_value.to_string()),
-                        _ => (),
-                    }
-                }
+    for model in models.iter_mut() {
+        let key = format!("{}:", model.name);
+        let Some(start) = (cursor + 1..lines.len()).find(|&index| {
+            let line = lines[index];
+            get_indent(line) > 0 && line.trim() == key
+        }) else {
+            continue;
+        };
+        let indent = get_indent(lines[start]);
+        model.span = Some(Span {
+            line: start + 1,
+            column: indent + 1,
+        });
+
+        // The model ends at the next line that isn't indented deeper than its key
+        let end = (start + 1..lines.len())
+            .find(|&index| {
+                let trimmed = lines[index].trim();
+                !trimmed.is_empty()
+                    && !trimmed.starts_with('#')
+                    && get_indent(lines[index]) <= indent
+            })
+            .unwrap_or(lines.len());
+
+        let mut field_cursor = start + 1;
+        for field in model.fields.iter_mut() {
+            let found = (field_cursor..end).find(|&index| {
+                let trimmed = lines[index].trim_start().trim_start_matches("- ");
+                trimmed
+                    .strip_prefix("name:")
+                    .map(|value| value.trim().trim_matches(['\'', '"']) == field.name)
+                    .unwrap_or(false)
+            });
+            if let Some(index) = found {
+                let column = lines[index].find("name:").unwrap_or_default();
+                field.span = Some(Span {
+                    line: index + 1,
+                    column: column + 1,
+                });
+                field_cursor = index + 1;
             }
+        }
+
+        cursor = start;
+    }
+}
+
+fn get_indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
 
-            model.fields.push(model_fields);
+fn default_schema_version() -> u32 {
+    SCHEMA_VERSION
+}
+
+// Models are a map, but their order matters to generators so they're kept as a list of pairs
+fn deserialize_models<'de, D>(deserializer: D) -> Result<Vec<(String, ModelDocument)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct ModelsVisitor;
+
+    impl<'de> Visitor<'de> for ModelsVisitor {
+        type Value = Vec<(String, ModelDocument)>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a map of model names to models")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut models = Vec::new();
+            while let Some((name, model)) = map.next_entry::<String, ModelDocument>()? {
+                models.push((name, model));
+            }
+            Ok(models)
         }
 
-        models.push(model);
+        fn visit_unit<E>(self) -> Result<Self::Value, E> {
+            Ok(Vec::new())
+        }
     }
 
-    models
+    deserializer.deserialize_any(ModelsVisitor)
+}
+
+// YAML scalars such as `1.0.0`, `0` or `true` are read as strings
+fn deserialize_scalar<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<serde_yaml::Value>::deserialize(deserializer)?;
+    value.map(scalar_to_string).transpose()
+}
+
+fn deserialize_scalars<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values = Option::<Vec<serde_yaml::Value>>::deserialize(deserializer)?;
+    values
+        .map(|values| values.into_iter().map(scalar_to_string).collect())
+        .transpose()
+}
+
+fn scalar_to_string<E: serde::de::Error>(value: serde_yaml::Value) -> Result<String, E> {
+    match value {
+        serde_yaml::Value::String(s) => Ok(s),
+        serde_yaml::Value::Number(n) => Ok(n.to_string()),
+        serde_yaml::Value::Bool(b) => Ok(b.to_string()),
+        _ => Err(E::custom("expected a string, number or boolean")),
+    }
 }
 
-fn parse_project_schema(yaml: &Yaml) -> ProjectSchema {
-    ProjectSchema {
-        name: yaml["name"].as_str().map(|s| s.to_string()),
-        version: yaml["version"].as_str().map(|s| s.to_string()),
-        copyright_header: yaml["copyright_header"].as_str().map(|s| s.to_string()),
-        description: yaml["description"].as_str().map(|s| s.to_string()),
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    fn parse(yaml_content: &str) -> Result<AnubisSchema, SchemaError> {
+        parse_schema_str(yaml_content, PathBuf::from("/tmp"))
+    }
+
+    #[test]
+    fn parses_the_example_schema() {
+        let schema = parse(include_str!("../../examples/Anubis.yaml")).unwrap();
+
+        assert_eq!(schema.project_name, "Anubis");
+        assert_eq!(schema.version, "1.0.0");
+        assert_eq!(
+            schema
+                .models
+                .iter()
+                .map(|model| model.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec![
+                "user",
+                "preferences",
+                "transactions",
+                "tag",
+                "transaction_tags"
+            ]
+        );
+
+        let user = &schema.models[0];
+        let name = user.fields.iter().find(|f| f.name == "name").unwrap();
+        assert_eq!(name.replace_all, Some(String::from("/\\D/")));
+
+        let language = user.fields.iter().find(|f| f.name == "language").unwrap();
+        assert_eq!(
+            language.use_enum,
+            Some(vec![
+                String::from("en"),
+                String::from("es"),
+                String::from("fr")
+            ])
+        );
+        assert!(!language.required);
+
+        let password = user.fields.iter().find(|f| f.name == "password").unwrap();
+        assert_eq!(password.minimum, Some(8));
+        assert_eq!(password.maximum, Some(64));
+        assert!(!password.replicate);
+        assert_eq!(password.use_match.as_ref().map(|m| m.len()), Some(3));
+
+        let transactions = &schema.models[2];
+        assert!(transactions.mode == RelationshipMode::OneToMany);
+        assert_eq!(transactions.has_owner, Some(String::from("user.id")));
+
+        let tags = transactions
+            .fields
+            .iter()
+            .find(|f| f.name == "tags")
+            .unwrap();
+        assert_eq!(tags.links, Some(String::from("tag")));
+        assert_eq!(tags.through, Some(String::from("transaction_tags")));
+        assert!(schema.is_join_model("transaction_tags"));
+    }
+
+    #[test]
+    fn accepts_documented_aliases() {
+        let schema = parse(
+            "
+project:
+  name: Test
+  copyright: 'Copyright © {YYYY} Test'
+models:
+  user:
+    fields:
+      - name: age
+        kind: number
+        minimum: 1
+        max: 120
+      - name: code
+        replaceAll: /\\D/
+        on_unknown: '0'
+        match: /\\d+/
+      - name: password
+        encrypt: true
+",
+        )
+        .unwrap();
+
+        assert_eq!(schema.copyright_header, "Copyright © {YYYY} Test");
+
+        let age = &schema.models[0].fields[0];
+        assert!(age.kind == ModelKind::Number);
+        assert_eq!(age.minimum, Some(1));
+        assert_eq!(age.maximum, Some(120));
+
+        let code = &schema.models[0].fields[1];
+        assert_eq!(code.replace_all, Some(String::from("/\\D/")));
+        assert_eq!(code.use_match, Some(vec![String::from("/\\d+/")]));
+
+        assert!(schema.models[0].fields[2].hash);
+    }
+
+    #[test]
+    fn rejects_unknown_keys_with_location() {
+        let error = parse(
+            "
+project:
+  name: Test
+models:
+  user:
+    fields:
+      - name: id
+        kinds: string
+",
+        )
+        .err()
+        .unwrap();
+
+        assert!(error.message.contains("unknown field `kinds`"));
+        assert_eq!(error.line, Some(8));
+        assert_eq!(error.column, Some(9));
+    }
+
+    #[test]
+    fn rejects_unknown_kinds() {
+        let error = parse(
+            "
+project:
+  name: Test
+models:
+  user:
+    fields:
+      - name: id
+        kind: strung
+",
+        )
+        .err()
+        .unwrap();
+
+        assert!(error.message.contains("unknown variant `strung`"));
+        assert_eq!(error.line, Some(8));
+    }
+
+    #[test]
+    fn parses_parameterized_kinds() {
+        let schema = parse(
+            "
+project:
+  name: Test
+models:
+  invoice:
+    fields:
+      - name: total
+        kind: decimal(12, 2)
+      - name: due
+        kind: date
+      - name: lines
+        kind: list<json>
+",
+        )
+        .unwrap();
+        let kinds: Vec<&ModelKind> = schema.models[0]
+            .fields
+            .iter()
+            .map(|field| &field.kind)
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                &ModelKind::Decimal {
+                    precision: Some(12),
+                    scale: Some(2)
+                },
+                &ModelKind::Date,
+                &ModelKind::List(Box::new(ModelKind::Json)),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_parameterized_kinds() {
+        for kind in ["list<list<string>>", "decimal(2, 4)", "list"] {
+            let error = parse(&format!(
+                "
+project:
+  name: Test
+models:
+  user:
+    fields:
+      - name: id
+        kind: {kind}
+"
+            ))
+            .err()
+            .unwrap();
+
+            assert_eq!(error.line, Some(8), "{kind}");
+        }
+    }
+
+    #[test]
+    fn settings_fall_back_to_defaults() {
+        let schema = parse(
+            "
+project:
+  name: Test
+settings:
+  scaling: horizontal
+  package_manager: pnpm
+  ci: gitlab-ci
+",
+        )
+        .unwrap();
+
+        assert_eq!(schema.settings.scaling, ScalingMode::Horizontal);
+        assert_eq!(schema.settings.package_manager, Some(PackageManager::Pnpm));
+        assert_eq!(schema.settings.ci, CICDProvider::GitLabCI);
+        assert!(schema.settings.json_api);
+        assert_eq!(schema.settings.default_locale, "en");
+    }
+
+    #[test]
+    fn rejects_unknown_settings() {
+        let error = parse(
+            "
+project:
+  name: Test
+settings:
+  styling: tailwind
+",
+        )
+        .err()
+        .unwrap();
+
+        assert!(error.message.contains("unknown variant `tailwind`"));
+        assert_eq!(error.line, Some(5));
+    }
+
+    fn write_project(files: &[(&str, &str)]) -> PathBuf {
+        let root_directory = tempfile::tempdir().unwrap().into_path();
+        for (file, content) in files {
+            let path = root_directory.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        root_directory
+    }
+
+    #[test]
+    fn includes_models_from_other_files() {
+        let root_directory = write_project(&[
+            (
+                "Anubis.yaml",
+                "
+project:
+  name: Test
+include:
+  - models/*.yaml
+  - billing.yaml
+models:
+  user:
+    fields:
+      - name: id
+",
+            ),
+            (
+                "models/post.yaml",
+                "models:\n  post:\n    fields:\n      - name: id\n",
+            ),
+            (
+                "models/comment.yaml",
+                "models:\n  comment:\n    fields:\n      - name: id\n",
+            ),
+            (
+                "billing.yaml",
+                "models:\n  invoice:\n    fields:\n      - name: id\n",
+            ),
+        ]);
+
+        let schema = parse_schema_yaml(root_directory).unwrap();
+
+        // Spans point into the file each model came from
+        let post = schema.get_model("post").unwrap();
+        assert_eq!(post.span, Some(Span { line: 2, column: 3 }));
+        assert_eq!(post.fields[0].span, Some(Span { line: 4, column: 9 }));
+        assert_eq!(
+            schema.get_model("user").unwrap().span,
+            Some(Span { line: 8, column: 3 })
+        );
+
+        let models: Vec<(&str, &str)> = schema
+            .models
+            .iter()
+            .map(|model| (model.name.as_str(), model.source.as_str()))
+            .collect();
+
+        assert_eq!(
+            models,
+            vec![
+                ("user", "Anubis.yaml"),
+                ("comment", "models/comment.yaml"),
+                ("post", "models/post.yaml"),
+                ("invoice", "billing.yaml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_in_included_files_point_at_the_file() {
+        let root_directory = write_project(&[
+            (
+                "Anubis.yaml",
+                "project:\n  name: Test\ninclude:\n  - models/*.yaml\n",
+            ),
+            (
+                "models/user.yaml",
+                "models:\n  user:\n    fields:\n      - name: id\n        kind: strung\n",
+            ),
+        ]);
+
+        let error = parse_schema_yaml(root_directory).err().unwrap();
+
+        assert_eq!(error.file, "models/user.yaml");
+        assert_eq!(error.line, Some(5));
+        assert!(error.to_string().starts_with("models/user.yaml:5:"));
+        assert!(error.message.contains("unknown variant `strung`"));
+    }
+
+    #[test]
+    fn includes_must_match_a_file() {
+        let root_directory = write_project(&[(
+            "Anubis.yaml",
+            "project:\n  name: Test\ninclude:\n  - modles/*.yaml\n",
+        )]);
+
+        let error = parse_schema_yaml(root_directory).err().unwrap();
+
+        assert_eq!(error.file, "Anubis.yaml");
+        assert_eq!(
+            error.message,
+            "`include: modles/*.yaml` didn't match any files"
+        );
+    }
+
+    #[test]
+    fn requires_a_project_name() {
+        let error = parse("project:\n  version: 1.0.0\n").err().unwrap();
+
+        assert!(error.message.contains("missing field `name`"));
+    }
+
+    #[test]
+    fn rejects_newer_schema_versions() {
+        let error = parse("schema_version: 99\nproject:\n  name: Test\n")
+            .err()
+            .unwrap();
+
+        assert!(error.message.contains("schema_version 99 is not supported"));
     }
 }
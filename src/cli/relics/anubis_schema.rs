@@ -1,17 +1,26 @@
 // Copyright © 2024 Navarrotech
 
 // Custom modules
+use crate::cli::parse::SCHEMA_VERSION;
+use crate::error::AnubisError;
 use crate::schema::AnubisSchema;
 
-pub fn setup_anubis_schema(schema: &AnubisSchema) {
+pub fn setup_anubis_schema(schema: &AnubisSchema) -> Result<(), AnubisError> {
     let schema_path = schema.install_directory.clone().join("Anubis.yaml");
     let schema_content = create_anubis_schema(schema);
 
     // Write the schema to the file
-    std::fs::write(schema_path, schema_content).expect("Unable to write Anubis.yaml file");
+    std::fs::write(&schema_path, schema_content)
+        .map_err(|error| AnubisError::io(&schema_path, error))
 }
 
 pub fn create_anubis_schema(schema: &AnubisSchema) -> String {
+    let settings = &schema.settings;
+    // Left out when not chosen, so it keeps following the frontend's lockfile
+    let package_manager = match settings.package_manager {
+        Some(package_manager) => format!("  package_manager: {package_manager}\n"),
+        None => String::new(),
+    };
     let copyright = if schema.copyright_header_formatted.is_empty() {
         schema.copyright_header_formatted.clone()
     } else {
@@ -29,26 +38,54 @@ pub fn create_anubis_schema(schema: &AnubisSchema) -> String {
 # 
 # You can use the command `anubis validate` to ensure this file is valid.
 
+schema_version: {schema_version}
+
 project:
   name: '{project_name}'
   version: '{project_version}'
   copyright: '{copyright_unformatted}'
   description: '{description}'
+
+# Choices made during initialization, every generate follows them
+settings:
+  ci: {ci}
+{package_manager}  styling: {styling}
+  default_locale: '{default_locale}'
+  json_api: {json_api}
+  scaling: {scaling}
+  serverless_api: {serverless_api}
 ",
-        project_name = schema.project_name,
-        project_version = schema.version,
-        description = schema.description,
-        copyright_unformatted = schema.copyright_header,
-        copyright_formatted = copyright
+        project_name = escape_single_quotes(&schema.project_name),
+        project_version = escape_single_quotes(&schema.version),
+        description = escape_single_quotes(&schema.description),
+        copyright_unformatted = escape_single_quotes(&schema.copyright_header),
+        copyright_formatted = copyright,
+        schema_version = SCHEMA_VERSION,
+        ci = settings.ci.name(),
+        package_manager = package_manager,
+        styling = settings.styling.name(),
+        default_locale = escape_single_quotes(&settings.default_locale),
+        json_api = settings.json_api,
+        scaling = settings.scaling.name(),
+        serverless_api = settings.serverless_api,
     )
 }
 
+// Values can come straight from init flags, and a single quote would end the YAML string early
+pub fn escape_single_quotes(value: &str) -> String {
+    value.replace('\'', "''")
+}
+
 #[cfg(test)]
 mod check_anubis_schema {
     use super::*;
+    use crate::cli::install::PackageManager;
+    use crate::cli::parse::parse_schema_str;
+    use crate::cli::relics::cicd::CICDProvider;
     use crate::schema::AnubisSchema;
     use serde_yaml;
     use serde_yaml::Error;
+    use std::path::PathBuf;
     use tempfile::tempdir;
 
     fn is_valid_yaml(yaml_str: &str) -> Result<(), Error> {
@@ -56,12 +93,12 @@ mod check_anubis_schema {
     }
 
     fn mock_schema() -> AnubisSchema {
-        let mut test_schema = AnubisSchema::default();
-        test_schema.project_name = "Anubis Test".to_string();
-        test_schema.copyright_header = String::from("// Copyright © {YYYY} Navarrotech");
-        test_schema.copyright_header_formatted = String::from("// Copyright © 2024 Navarrotech");
-
-        test_schema
+        AnubisSchema {
+            project_name: "Anubis Test".to_string(),
+            copyright_header: String::from("// Copyright © {YYYY} Navarrotech"),
+            copyright_header_formatted: String::from("// Copyright © 2024 Navarrotech"),
+            ..AnubisSchema::default()
+        }
     }
 
     #[test]
@@ -73,6 +110,30 @@ mod check_anubis_schema {
         assert!(is_valid_yaml(content.as_str()).is_ok());
     }
 
+    #[test]
+    fn ensure_anubis_schema_yaml_is_valid_with_quotes() {
+        let mut test_schema = mock_schema();
+        test_schema.project_name = "Alex's Project".to_string();
+
+        let content = create_anubis_schema(&test_schema);
+        let parsed = serde_yaml::from_str::<serde_yaml::Value>(content.as_str()).unwrap();
+
+        assert_eq!(parsed["project"]["name"], "Alex's Project");
+    }
+
+    #[test]
+    fn settings_chosen_at_init_are_parsed_back() {
+        let mut test_schema = mock_schema();
+        test_schema.settings.ci = CICDProvider::CircleCI;
+        test_schema.settings.package_manager = Some(PackageManager::Bun);
+        test_schema.settings.default_locale = String::from("pt-BR");
+
+        let content = create_anubis_schema(&test_schema);
+        let parsed = parse_schema_str(&content, PathBuf::from("/tmp")).unwrap();
+
+        assert_eq!(parsed.settings, test_schema.settings);
+    }
+
     #[test]
     fn ensure_anubis_schema_writes_the_file() {
         let temp_directory = tempdir().unwrap().into_path();
@@ -80,7 +141,7 @@ mod check_anubis_schema {
         let mut test_schema = mock_schema();
         test_schema.install_directory = temp_directory.clone();
 
-        setup_anubis_schema(&test_schema);
+        setup_anubis_schema(&test_schema).unwrap();
 
         let file_path = temp_directory.join("Anubis.yaml");
         assert!(file_path.exists());
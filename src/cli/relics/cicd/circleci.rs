@@ -3,6 +3,13 @@
 use crate::schema::AnubisSchema;
 
 pub fn create_circleci(schema: &AnubisSchema) -> String {
+    let package_manager = schema.get_package_manager();
+    let install = package_manager.install_command(false);
+    let lockfile = package_manager.lockfile();
+    let test = package_manager.run_script("test");
+    let tsc = package_manager.run_binary("tsc");
+    let lint = package_manager.run_script("lint");
+    let build = package_manager.run_script("build");
     let project_name = schema.project_name.replace(' ', "-");
 
     format!("version: 2.1
@@ -16,7 +23,7 @@ pub fn create_circleci(schema: &AnubisSchema) -> String {
 #  - Optionally create a Docker image and push to Docker Hub if Docker Hub credentials are set
 #  
 #  To regenerate this file and restore all defaults, you can run:
-#  `anubis relics create circleci-actions`
+#  `anubis relics create circleci`
 
 executors:
   rust:
@@ -41,18 +48,18 @@ commands:
       # Cache dependencies
       - restore_cache:
           keys:
-            - node-dependencies-{{{{ checksum \"yarn.lock\" }}}}
+            - node-dependencies-{{{{ checksum \"{lockfile}\" }}}}
   
       # Install dependencies & make assets
       - run:
           name: Install dependencies
           command: |
             cd frontend
-            yarn install
+            {install}
             
       # Save cache
       - save_cache:
-          key: node-dependencies-{{{{ checksum \"yarn.lock\" }}}}
+          key: node-dependencies-{{{{ checksum \"{lockfile}\" }}}}
           paths:
             - frontend/node_modules
 
@@ -98,21 +105,21 @@ jobs:
           name: Run unit tests
           command: |
             cd frontend
-            yarn test
+            {test}
 
       # Check typescript
       - run:
           name: Check typescript
           command: |
             cd frontend
-            yarn tsc
+            {tsc}
   
       # Optional: Require ESLint checks to pass before building
       - run:
           name: Ensure eslint
           command: |
             cd frontend
-            yarn lint
+            {lint}
 
   test-api:
     executor: rust
@@ -177,7 +184,7 @@ jobs:
           name: Build frontend assets
           command: |
             cd frontend
-            yarn build
+            {build}
 
       # Upload artifacts
       - store_artifacts:
@@ -274,8 +281,10 @@ mod check_circleci {
 
     #[test]
     fn ensure_circleci_yaml_is_valid() {
-        let mut test_schema = AnubisSchema::default();
-        test_schema.project_name = "test".to_string();
+        let test_schema = AnubisSchema {
+            project_name: "test".to_string(),
+            ..AnubisSchema::default()
+        };
 
         let content = create_circleci(&test_schema);
 
@@ -284,8 +293,10 @@ mod check_circleci {
 
     #[test]
     fn ensure_circleci_yaml_is_valid_with_project_name_spaces() {
-        let mut test_schema = AnubisSchema::default();
-        test_schema.project_name = "name with spaces".to_string();
+        let test_schema = AnubisSchema {
+            project_name: "name with spaces".to_string(),
+            ..AnubisSchema::default()
+        };
 
         let content = create_circleci(&test_schema);
 
@@ -3,6 +3,13 @@
 use crate::schema::AnubisSchema;
 
 pub fn create_github_actions(schema: &AnubisSchema) -> String {
+    let package_manager = schema.get_package_manager();
+    let install = package_manager.install_command(false);
+    let lockfile = package_manager.lockfile();
+    let test = package_manager.run_script("test");
+    let lint = package_manager.run_script("lint");
+    let build = package_manager.run_script("build");
+
     format!("name: Build & Test {project_name}
 
 # This GitHub Actions workflow will:
@@ -175,33 +182,33 @@ jobs:
         uses: actions/cache@v4
         with:
           path: frontend/node_modules
-          key: ${{{{ runner.os }}}}-frontend-dependencies-${{{{ hashFiles('**/yarn.lock') }}}}
+          key: ${{{{ runner.os }}}}-frontend-dependencies-${{{{ hashFiles('**/{lockfile}') }}}}
 
       # Install dependencies & make assets:
       - name: Install dependencies
         if: steps.cache-frontend-deps.outputs.cache-hit != 'true'
         run: |
           cd frontend
-          yarn install
+          {install}
 
       # Testing:
       - name: Run unit tests
         run: |
           cd frontend
-          yarn test
+          {test}
 
       # Optional: Require ESLint checks to pass before building
       - name: Ensure eslint
         run: |
           cd frontend
-          yarn lint
+          {lint}
 
       # Build:
       - name: Build frontend assets
         if: github.ref == 'refs/heads/main'
         run: |
           cd frontend
-          yarn build
+          {build}
 
       - name: Save for next step
         uses: actions/upload-artifact@v4
@@ -215,7 +222,7 @@ jobs:
         uses: actions/cache/save@v4
         with:
           path: frontend/node_modules
-          key: ${{{{ runner.os }}}}-frontend-dependencies-${{{{ hashFiles('**/yarn.lock') }}}}
+          key: ${{{{ runner.os }}}}-frontend-dependencies-${{{{ hashFiles('**/{lockfile}') }}}}
 
     ", project_name = schema.project_name)
 }
@@ -233,8 +240,10 @@ mod check_github_actions {
 
     #[test]
     fn ensure_github_actions_yaml_is_valid() {
-        let mut test_schema = AnubisSchema::default();
-        test_schema.project_name = "test".to_string();
+        let test_schema = AnubisSchema {
+            project_name: "test".to_string(),
+            ..AnubisSchema::default()
+        };
 
         let content = create_github_actions(&test_schema);
 
@@ -243,8 +252,10 @@ mod check_github_actions {
 
     #[test]
     fn ensure_github_actions_yaml_is_valid_with_project_name_spaces() {
-        let mut test_schema = AnubisSchema::default();
-        test_schema.project_name = "name with spaces".to_string();
+        let test_schema = AnubisSchema {
+            project_name: "name with spaces".to_string(),
+            ..AnubisSchema::default()
+        };
 
         let content = create_github_actions(&test_schema);
 
@@ -0,0 +1,189 @@
+// Copyright © 2024 Navarrotech
+
+use crate::schema::AnubisSchema;
+
+pub fn create_gitlab_ci(schema: &AnubisSchema) -> String {
+    let package_manager = schema.get_package_manager();
+    let install = package_manager.install_command(false);
+    let lockfile = package_manager.lockfile();
+    let test = package_manager.run_script("test");
+    let tsc = package_manager.run_binary("tsc");
+    let lint = package_manager.run_script("lint");
+    let build = package_manager.run_script("build");
+    let project_name = schema.project_name.replace(' ', "-").to_lowercase();
+
+    format!("# This GitLab CI pipeline will:
+#  - Cache the Cargo registry, index and node_modules
+#  - Ensure unit tests are ran for frontend and backend
+#  - Ensure lint checks are ran for frontend and backend
+#  - Build the project in release mode
+#  - Keep the build artifacts in GitLab storage
+#  - Optionally create a Docker image and push to Docker Hub if Docker Hub credentials are set
+#  
+#  To regenerate this file and restore all defaults, you can run:
+#  `anubis relics create gitlab-ci`
+
+stages:
+  - test
+  - build
+  - package
+
+variables:
+  # Keep the Cargo registry inside the project directory so GitLab can cache it
+  CARGO_HOME: $CI_PROJECT_DIR/.cargo
+
+# Reusable cache definitions
+.api-cache: &api-cache
+  key:
+    prefix: cargo
+    files:
+      - api/Cargo.lock
+  paths:
+    - .cargo/registry
+    - .cargo/git
+    - api/target
+
+.frontend-cache: &frontend-cache
+  key:
+    prefix: frontend
+    files:
+      - frontend/{lockfile}
+  paths:
+    - frontend/node_modules
+
+test-api:
+  stage: test
+  image: rust:latest
+  cache: *api-cache
+  script:
+    - cd api
+    - cargo fetch
+
+    # Optional: Require Rust formatting
+    - rustup component add rustfmt clippy
+    - cargo fmt --all -- --check
+
+    # Optional: Require Rust clippy checks
+    - cargo clippy -- -D warnings
+
+    # Run unit tests
+    - cargo test
+
+test-frontend:
+  stage: test
+  image: node:latest
+  cache: *frontend-cache
+  script:
+    - cd frontend
+    - {install}
+
+    # Run unit tests
+    - {test}
+
+    # Check typescript
+    - {tsc}
+
+    # Optional: Require ESLint checks to pass before building
+    - {lint}
+
+build-api:
+  stage: build
+  image: rust:latest
+  cache: *api-cache
+  needs: [ test-api ]
+
+  # Only build on the default branch
+  # Ideal so your merge requests don't get cluttered with artifacts
+  rules:
+    - if: $CI_COMMIT_BRANCH == $CI_DEFAULT_BRANCH
+
+  script:
+    - cd api
+    - cargo build --release
+  artifacts:
+    paths:
+      - api/target/release
+    expire_in: 1 week
+
+build-frontend:
+  stage: build
+  image: node:latest
+  cache: *frontend-cache
+  needs: [ test-frontend ]
+  rules:
+    - if: $CI_COMMIT_BRANCH == $CI_DEFAULT_BRANCH
+  script:
+    - cd frontend
+    - {install}
+    - {build}
+  artifacts:
+    paths:
+      - frontend/dist
+    expire_in: 1 week
+
+# Optional, build a Docker image for the api service and push to Docker Hub
+# Only runs if DOCKER_HUB_USERNAME and DOCKER_HUB_ACCESS_TOKEN are set in your CI/CD variables
+package-docker:
+  stage: package
+  image: docker:latest
+  services:
+    - docker:dind
+  needs: [ build-api, build-frontend ]
+  rules:
+    - if: $CI_COMMIT_BRANCH == $CI_DEFAULT_BRANCH && $DOCKER_HUB_USERNAME && $DOCKER_HUB_ACCESS_TOKEN
+  script:
+    - cd api
+
+    # Log in to Docker Hub
+    - echo \"$DOCKER_HUB_ACCESS_TOKEN\" | docker login -u \"$DOCKER_HUB_USERNAME\" --password-stdin
+
+    # Build the Docker image and tag it as latest
+    - docker build . -t $DOCKER_HUB_USERNAME/{project_name}-api-rust:latest -f ./Dockerfile
+
+    # Push the latest tag
+    - docker push $DOCKER_HUB_USERNAME/{project_name}-api-rust:latest
+
+    # Tag the image with the short Git hash
+    - docker tag $DOCKER_HUB_USERNAME/{project_name}-api-rust:latest $DOCKER_HUB_USERNAME/{project_name}-api-rust:$CI_COMMIT_SHORT_SHA
+
+    # Push the image with the Git hash tag
+    - docker push $DOCKER_HUB_USERNAME/{project_name}-api-rust:$CI_COMMIT_SHORT_SHA
+", project_name = project_name)
+}
+
+#[cfg(test)]
+mod check_gitlab_ci {
+    use super::*;
+    use crate::schema::AnubisSchema;
+    use serde_yaml;
+    use serde_yaml::Error;
+
+    fn is_valid_yaml(yaml_str: &str) -> Result<(), Error> {
+        serde_yaml::from_str::<serde_yaml::Value>(yaml_str).map(|_| ())
+    }
+
+    #[test]
+    fn ensure_gitlab_ci_yaml_is_valid() {
+        let test_schema = AnubisSchema {
+            project_name: "test".to_string(),
+            ..AnubisSchema::default()
+        };
+
+        let content = create_gitlab_ci(&test_schema);
+
+        assert!(is_valid_yaml(content.as_str()).is_ok());
+    }
+
+    #[test]
+    fn ensure_gitlab_ci_yaml_is_valid_with_project_name_spaces() {
+        let test_schema = AnubisSchema {
+            project_name: "name with spaces".to_string(),
+            ..AnubisSchema::default()
+        };
+
+        let content = create_gitlab_ci(&test_schema);
+
+        assert!(is_valid_yaml(content.as_str()).is_ok());
+        assert!(content.contains("$DOCKER_HUB_USERNAME/name-with-spaces-api-rust:latest"));
+    }
+}
@@ -4,19 +4,32 @@
 use std::fmt;
 
 // Lib
+use clap::ValueEnum;
 use dialoguer::{theme::ColorfulTheme, Select};
+use serde::Deserialize;
 
 // Crates
 use crate::cli::relics::cicd::circleci::create_circleci;
 use crate::cli::relics::cicd::github_actions::create_github_actions;
+use crate::cli::relics::cicd::gitlab_ci::create_gitlab_ci;
+use crate::error::AnubisError;
+use crate::logger::{debug, info};
 use crate::relics::write::write_relic;
 use crate::schema::AnubisSchema;
 
-#[derive(Debug, Clone)]
-enum CICDProvider {
+#[derive(Debug, Clone, PartialEq, ValueEnum, Deserialize)]
+pub enum CICDProvider {
+    #[value(name = "github-actions")]
+    #[serde(rename = "github-actions")]
     GitHubActions,
+    #[value(name = "gitlab-ci")]
+    #[serde(rename = "gitlab-ci")]
     GitLabCI,
+    #[value(name = "circleci")]
+    #[serde(rename = "circleci")]
     CircleCI,
+    #[value(name = "none")]
+    #[serde(rename = "none")]
     Skip,
 }
 
@@ -30,6 +43,16 @@ impl CICDProvider {
             CICDProvider::Skip,
         ]
     }
+
+    // How it's written in Anubis.yaml and on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            CICDProvider::GitHubActions => "github-actions",
+            CICDProvider::GitLabCI => "gitlab-ci",
+            CICDProvider::CircleCI => "circleci",
+            CICDProvider::Skip => "none",
+        }
+    }
 }
 
 // Implement Display trait to convert enum to string
@@ -44,9 +67,7 @@ impl fmt::Display for CICDProvider {
     }
 }
 
-pub fn setup_cicd(schema: &AnubisSchema) {
-    println!("Setting up CI/CD pipeline...");
-
+pub fn choose_cicd_provider() -> Result<CICDProvider, AnubisError> {
     let items: Vec<String> = CICDProvider::all()
         .iter()
         .map(|provider| provider.to_string())
@@ -56,37 +77,44 @@ pub fn setup_cicd(schema: &AnubisSchema) {
         .with_prompt("Which CI/CD do you wish to use?")
         .items(&items)
         .default(0)
-        .interact()
-        .unwrap();
+        .interact()?;
 
-    // Handle the selection
-    let option = CICDProvider::all()[selection].clone();
+    Ok(CICDProvider::all()[selection].clone())
+}
+
+pub fn setup_cicd(schema: &AnubisSchema, option: &CICDProvider) -> Result<(), AnubisError> {
+    info!("Setting up CI/CD pipeline...");
 
     match option {
         CICDProvider::GitHubActions => {
             let content = create_github_actions(schema);
             let github_actions_path = schema.install_directory.join(".github/workflows/build.yml");
 
-            write_relic(schema, &content, &github_actions_path);
+            write_relic(schema, &content, &github_actions_path)?;
         }
         CICDProvider::GitLabCI => {
-            // TODO: Implement GitLab CI setup
-            println!("Setting up GitLab CI...");
+            let content = create_gitlab_ci(schema);
+            let gitlab_ci_path = schema.install_directory.join(".gitlab-ci.yml");
+
+            write_relic(schema, &content, &gitlab_ci_path)?;
         }
         CICDProvider::CircleCI => {
             let content = create_circleci(schema);
             let circleci_path = schema.install_directory.join(".circleci/config.yml");
 
-            write_relic(schema, &content, &circleci_path);
+            write_relic(schema, &content, &circleci_path)?;
         }
         CICDProvider::Skip => {
-            println!("Skipping CI/CD setup...");
+            info!("Skipping CI/CD setup...");
         }
     }
 
-    println!("Selected: {:?}", option);
-    println!("Done!");
+    debug!("Selected: {:?}", option);
+    debug!("Done!");
+
+    Ok(())
 }
 
 pub mod circleci;
 pub mod github_actions;
+pub mod gitlab_ci;
@@ -0,0 +1,74 @@
+// Copyright © 2024 Navarrotech
+
+// Lib
+use clap::{Args, Subcommand};
+
+// Core structs
+use crate::cli::relics::registry::{find_relic, RELICS};
+use crate::error::AnubisError;
+use crate::logger::info;
+use crate::manifest::update_manifest;
+use crate::relics::write::create_relic;
+use crate::schema::AnubisSchema;
+use crate::vfs::{start_recording, stop_recording};
+
+#[derive(Args)]
+pub struct RelicsArgs {
+    #[command(subcommand)]
+    pub command: RelicsCommand,
+}
+
+#[derive(Subcommand)]
+pub enum RelicsCommand {
+    // Creates a relic, or restores it to its defaults
+    Create(CreateRelicArgs),
+}
+
+#[derive(Args)]
+pub struct CreateRelicArgs {
+    /// The relic's name, like `github-actions`
+    pub name: String,
+
+    #[clap(short = 'd', long, default_value = "")]
+    pub directory: String,
+
+    /// Overwrite the relic if it already exists, your changes to it will be lost
+    #[clap(short = 'f', long)]
+    pub force: bool,
+}
+
+pub fn create_relic_by_name(
+    schema: &AnubisSchema,
+    args: &CreateRelicArgs,
+) -> Result<(), AnubisError> {
+    let Some(relic) = find_relic(&args.name) else {
+        let mut message = format!("There is no relic named `{}`, choose one of:", args.name);
+        for relic in RELICS.iter() {
+            message.push_str(&format!(
+                "\n  {:<16} {}",
+                relic.name,
+                relic.get_path(schema)
+            ));
+        }
+        return Err(AnubisError::Usage { message });
+    };
+
+    let relic_path = relic.get_path(schema);
+    let file_path = schema.install_directory.join(&relic_path);
+    if file_path.exists() && !args.force {
+        return Err(AnubisError::Refused {
+            message: format!("{relic_path} already exists"),
+            hint: String::from(
+                "Re-run with `--force` to restore it to its defaults, your changes to it will be lost",
+            ),
+        });
+    }
+
+    start_recording(false);
+    create_relic(schema, &(relic.create)(schema), &file_path)?;
+    update_manifest(&schema.install_directory, &stop_recording(), "relics")?;
+
+    info!("Created relic {relic_path}");
+
+    Ok(())
+}
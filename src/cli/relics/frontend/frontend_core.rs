@@ -1,61 +1,81 @@
 // Copyright © 2024 Navarrotech
 
 use crate::automatrons::write::write_automatron;
+use crate::error::AnubisError;
 use crate::relics::write::write_relic;
 use crate::schema::AnubisSchema;
 
-pub fn generate_frontend_core(schema: &AnubisSchema) {
-    generate_env(schema);
-    generate_vite_env(schema);
-    generate_root_tsx(schema);
-    generate_root_redux_store(schema);
+pub fn generate_frontend_core(schema: &AnubisSchema) -> Result<(), AnubisError> {
+    write_relic(
+        schema,
+        &create_env(),
+        &schema.install_directory.join("frontend/src/env.ts"),
+    )?;
+
+    write_relic(
+        schema,
+        &create_vite_env(),
+        &schema.install_directory.join("frontend/src/vite-env.d.ts"),
+    )?;
+
+    write_relic(
+        schema,
+        &create_main_tsx(),
+        &schema.install_directory.join("frontend/src/main.tsx"),
+    )?;
+
+    write_relic(
+        schema,
+        &create_store_index(),
+        &schema.install_directory.join("frontend/src/store/index.ts"),
+    )?;
+
+    write_relic(
+        schema,
+        &create_store_custom(),
+        &schema
+            .install_directory
+            .join("frontend/src/store/custom.ts"),
+    )?;
+
+    generate_root_redux_store(schema)?;
+
+    Ok(())
 }
 
-fn generate_env(schema: &AnubisSchema) {
-    let content = format!(
+pub fn create_env() -> String {
+    String::from(
         r#"
 export const NODE_ENV = import.meta.env.NODE_ENV || 'development'
 export const API_URL = import.meta.env.VITE_API_URL || 'http://localhost:3000'
 export const WEBSOCKET_URL = import.meta.env.VITE_GATEWAY_URL || 'ws://localhost:3000'
 
 console.log('Running in ' + NODE_ENV + ' mode')
-"#
-    );
-
-    write_relic(
-        schema,
-        &content,
-        &schema.install_directory.join("frontend/src/env.ts"),
-    );
+"#,
+    )
 }
 
-fn generate_vite_env(schema: &AnubisSchema) {
-    let content = format!(
+pub fn create_vite_env() -> String {
+    String::from(
         r#"/// <reference types="vite/client" />
 /// <reference types="vite-plugin-svgr/client" />
-"#
-    );
-
-    write_relic(
-        schema,
-        &content,
-        &schema.install_directory.join("frontend/src/vite-env.d.ts"),
-    );
+"#,
+    )
 }
 
-fn generate_root_tsx(schema: &AnubisSchema) {
-    let content = format!(
+pub fn create_main_tsx() -> String {
+    String::from(
         r#"
 // React.js
-import {{ createRoot }} from 'react-dom/client'
+import { createRoot } from 'react-dom/client'
 
 // Application
-import {{ Initialization }} from './store/Initialization'
-import {{ ApplicationRouter }} from './routes/Router'
+import { Initialization } from './store/Initialization'
+import { ApplicationRouter } from './routes/Router'
 
 // Redux
-import {{ Provider as ReduxProvider }} from 'react-redux'
-import {{ store }} from './store/store'
+import { Provider as ReduxProvider } from 'react-redux'
+import { store } from './store/store'
 
 // i18n
 import '@/modules/i18n'
@@ -67,68 +87,62 @@ const container = document.getElementById('root') as HTMLElement
 const root = createRoot(container)
 
 root.render(
-  <ReduxProvider store={{store}}>
+  <ReduxProvider store={store}>
     <Initialization>
       <ApplicationRouter />
     </Initialization>
   </ReduxProvider>
 )
-"#
-    );
-
-    write_relic(
-        schema,
-        &content,
-        &schema.install_directory.join("frontend/src/main.tsx"),
-    );
+"#,
+    )
 }
 
-fn generate_root_redux_store(schema: &AnubisSchema) {
-    write_relic(
-        schema,
-        &format!(
-            r#"
-import {{
+pub fn create_store_index() -> String {
+    String::from(
+        r#"
+import {
   type TypedUseSelectorHook,
   useDispatch as useDefaultDispatch,
   useSelector as useDefaultSelector
-}} from 'react-redux'
-import type {{ RootState, AppDispatch }} from './store'
+} from 'react-redux'
+import type { RootState, AppDispatch } from './store'
 
 type DispatchFunc = () => AppDispatch
 
 export const useDispatch: DispatchFunc = useDefaultDispatch
 export const useSelector: TypedUseSelectorHook<RootState> = useDefaultSelector
 
-export {{ dispatch, getState }} from './store'
-export type {{ AppDispatch, Thunk }} from './store'
-"#
-        ),
-        &schema.install_directory.join("frontend/src/store/index.ts"),
-    );
+export { dispatch, getState } from './store'
+export type { AppDispatch, Thunk } from './store'
+"#,
+    )
+}
 
-    write_relic(
-        schema,
-        &format!(
-            r#"
+pub fn create_store_custom() -> String {
+    String::from(
+        r#"
 // Add your own custom reducers here
-export const customReducerSlices = {{
+export const customReducerSlices = {
   // For example:
   // data: dataSlice.reducer,
-}}
-"#
-        ),
-        &schema
-            .install_directory
-            .join("frontend/src/store/custom.ts"),
-    );
+}
+"#,
+    )
+}
 
+fn generate_root_redux_store(schema: &AnubisSchema) -> Result<(), AnubisError> {
     // import { slice as userSlice } from '@/modules/auth/reducer'
     let mut reducer_imports = String::from("");
     // user: userSlice.reducer,
     let mut reducer_map = String::from("");
 
-    for (i, model) in schema.models.iter().enumerate() {
+    // Join models are stored with the model that links them, so they have no state of their own
+    let models = schema
+        .models
+        .iter()
+        .filter(|model| !schema.is_join_model(&model.name));
+
+    for (i, model) in models.enumerate() {
         let slice = format!("{model_name}Slice", model_name = model.name.to_lowercase());
         let reducer = format!(
             "{model_name}: {slice}.reducer",
@@ -142,8 +156,8 @@ export const customReducerSlices = {{
         );
 
         if i != 0 {
-            reducer_imports.push_str("\n");
-            reducer_map.push_str("\n");
+            reducer_imports.push('\n');
+            reducer_map.push('\n');
         }
 
         reducer_imports.push_str(&import);
@@ -191,5 +205,5 @@ export type Thunk = ThunkAction<void, RootState, unknown, Action>
         schema,
         &content,
         &schema.install_directory.join("frontend/src/store/store.ts"),
-    );
+    )
 }
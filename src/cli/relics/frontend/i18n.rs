@@ -1,10 +1,35 @@
 // Copyright © 2024 Navarrotech
 
+use crate::error::AnubisError;
 use crate::relics::write::write_relic;
 use crate::schema::AnubisSchema;
 
-pub fn generate_i18next(schema: &AnubisSchema) {
-    let auth_protobuf = format!(
+pub fn generate_i18n(schema: &AnubisSchema) -> Result<(), AnubisError> {
+    write_relic(
+        schema,
+        &create_i18next(schema),
+        &schema
+            .install_directory
+            .join("frontend/src/modules/i18n.ts"),
+    )?;
+
+    write_relic(
+        schema,
+        &create_translation_json(schema),
+        &schema.install_directory.join(get_translation_path(schema)),
+    )
+}
+
+// Translations start out in the default locale, add a folder next to it for every other language
+pub fn get_translation_path(schema: &AnubisSchema) -> String {
+    format!(
+        "frontend/public/locales/{}/translation.json",
+        schema.settings.default_locale
+    )
+}
+
+pub fn create_i18next(schema: &AnubisSchema) -> String {
+    format!(
         r#"
 import i18next from 'i18next'
 import {{ initReactI18next }} from 'react-i18next'
@@ -12,7 +37,7 @@ import Backend from 'i18next-http-backend'
 import LanguageDetector from 'i18next-browser-languagedetector'
 
 // Edit the default language here:
-export const defaultLanguage = 'en' as const
+export const defaultLanguage = '{locale}' as const
 
 // The core i18next instance that will be used throughout the app to manage language
 export const i18Instance = i18next
@@ -28,7 +53,7 @@ export const i18Instance = i18next
 
 // Add more languages here as you support them
 export const supportedLanguages = [
-  'en'
+  '{locale}'
 ] as const
 
 export const languageToFlag: Record<LanguageKey, string> = {{
@@ -110,20 +135,13 @@ export const languageLocalizedRecord: Record<string, string> = {{
 // Usable types
 export type SupportedLanguages = typeof supportedLanguages[number]
 export type LanguageKey = keyof typeof languageLocalizedRecord
-"#
-    );
-
-    write_relic(
-        schema,
-        &auth_protobuf,
-        &schema
-            .install_directory
-            .join("frontend/src/modules/i18n.ts"),
-    );
+"#,
+        locale = schema.settings.default_locale
+    )
 }
 
-pub fn generate_translation_json(schema: &AnubisSchema) {
-    let auth_protobuf = format!(
+pub fn create_translation_json(schema: &AnubisSchema) -> String {
+    format!(
         r#"
 {{
   "brand_name": "{project_name}",
@@ -152,13 +170,5 @@ pub fn generate_translation_json(schema: &AnubisSchema) {
 }}
 "#,
         project_name = schema.project_name
-    );
-
-    write_relic(
-        schema,
-        &auth_protobuf,
-        &schema
-            .install_directory
-            .join("frontend/public/locales/en/translation.json"),
-    );
+    )
 }
@@ -1,9 +1,23 @@
 // Copyright © 2024 Navarrotech
 
+use crate::cli::install::PackageManager;
 use crate::schema::AnubisSchema;
+use crate::settings::Styling;
 
 pub fn create_package_json(schema: &AnubisSchema) -> String {
     let project_name = schema.project_name.replace(' ', "-");
+    let package_manager = schema.get_package_manager();
+    let lint = package_manager.run_script("lint");
+
+    // Only yarn is pinned, the other package managers are checked by their lockfile
+    let engines = match package_manager {
+        PackageManager::Yarn => ",\n    \"yarn\": \">=1.22.0\"",
+        _ => "",
+    };
+    let styling = match schema.settings.styling {
+        Styling::Bulma => "\n    \"bulma\": \"^1.0.1\",\n    \"bulma-divider\": \"^0.2.0\",",
+        Styling::Plain => "",
+    };
 
     format!(
         "{{
@@ -12,25 +26,24 @@ pub fn create_package_json(schema: &AnubisSchema) -> String {
   \"version\": \"1.0.0\",
   \"type\": \"module\",
   \"scripts\": {{
-    \"dev\": \"yarn install && vite\",
+    \"dev\": \"{package_manager} install && vite\",
     \"build\": \"tsc && vite build\",
     \"preview\": \"vite preview\",
     \"analyze\": \"vite-bundle-visualizer\",
     \"test\": \"vitest run --dom --coverage src/\",
     \"test:dev\": \"DEBUG_PRINT_LIMIT=100000 vitest watch --ui --dom --coverage.enabled=true\",
     \"lint\": \"eslint . --ext ts,tsx --report-unused-disable-directives\",
-    \"lint:watch\": \"nodemon --exec 'yarn lint' --watch src --watch .eslintrc.js\"
+    \"lint:watch\": \"nodemon --exec '{lint}' --watch src --watch .eslintrc.js\"
   }},
   \"engines\": {{
-    \"node\": \">=20.11.0\",
-    \"yarn\": \">=1.22.0\"
+    \"node\": \">=20.11.0\"{engines}
   }},
   \"dependencies\": {{
     \"@fortawesome/fontawesome-svg-core\": \"^6.5.2\",
     \"@fortawesome/free-regular-svg-icons\": \"^6.5.2\",
     \"@fortawesome/free-solid-svg-icons\": \"^6.5.2\",
     \"@fortawesome/react-fontawesome\": \"^0.2.2\",
-    \"axios\": \"^1.7.2\",
+    \"axios\": \"^1.7.2\",{styling}
     \"i18next\": \"^23.11.3\",
     \"i18next-browser-languagedetector\": \"^8.0.0\",
     \"i18next-http-backend\": \"^2.5.1\",
@@ -97,12 +110,15 @@ pub fn create_package_json(schema: &AnubisSchema) -> String {
 mod check_package_json {
     use super::*;
     use crate::schema::AnubisSchema;
+    use crate::settings::Settings;
     use json;
 
     #[test]
     fn ensure_json_is_valid() {
-        let mut test_schema = AnubisSchema::default();
-        test_schema.project_name = "test".to_string();
+        let test_schema = AnubisSchema {
+            project_name: "test".to_string(),
+            ..AnubisSchema::default()
+        };
 
         let content = create_package_json(&test_schema);
         let parsed = json::parse(content.as_str());
@@ -110,10 +126,34 @@ mod check_package_json {
         assert!(parsed.is_ok());
     }
 
+    #[test]
+    fn scripts_use_the_package_manager_from_settings() {
+        let test_schema = AnubisSchema {
+            install_directory: tempfile::tempdir().unwrap().into_path(),
+            settings: Settings {
+                package_manager: Some(PackageManager::Npm),
+                styling: Styling::Plain,
+                ..Settings::default()
+            },
+            ..AnubisSchema::default()
+        };
+
+        let parsed = json::parse(&create_package_json(&test_schema)).unwrap();
+
+        assert_eq!(parsed["scripts"]["dev"], "npm install && vite");
+        assert!(parsed["scripts"]["lint:watch"]
+            .to_string()
+            .contains("'npm run lint'"));
+        assert!(!parsed["engines"].has_key("yarn"));
+        assert!(!parsed["dependencies"].has_key("bulma"));
+    }
+
     #[test]
     fn ensure_json_is_valid_with_project_name_spaces() {
-        let mut test_schema = AnubisSchema::default();
-        test_schema.project_name = "name with spaces".to_string();
+        let test_schema = AnubisSchema {
+            project_name: "name with spaces".to_string(),
+            ..AnubisSchema::default()
+        };
 
         let content = create_package_json(&test_schema);
         let parsed = json::parse(content.as_str());
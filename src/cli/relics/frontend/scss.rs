@@ -1,25 +1,63 @@
 // Copyright © 2024 Navarrotech
 
+use crate::error::AnubisError;
 use crate::relics::write::write_relic;
 use crate::schema::AnubisSchema;
+use crate::settings::Styling;
 
-pub fn generate_scss(schema: &AnubisSchema) {
+pub fn generate_scss(schema: &AnubisSchema) -> Result<(), AnubisError> {
     write_relic(
         schema,
-        &format!(
-            r#"
-// Sass modules
-@import "@/sass/bulma.scss"
-@import "@/sass/fonts.sass"
-"#
-        ),
+        &create_index_sass(schema),
         &schema.install_directory.join("frontend/src/index.sass"),
-    );
+    )?;
 
     write_relic(
         schema,
-        &format!(
-            r#"
+        &create_theme_sass(),
+        &schema
+            .install_directory
+            .join("frontend/src/sass/theme.sass"),
+    )?;
+
+    write_relic(
+        schema,
+        &create_fonts_sass(),
+        &schema
+            .install_directory
+            .join("frontend/src/sass/fonts.sass"),
+    )?;
+
+    if schema.settings.styling != Styling::Bulma {
+        return Ok(());
+    }
+
+    write_relic(
+        schema,
+        &create_bulma_scss(),
+        &schema
+            .install_directory
+            .join("frontend/src/sass/bulma.scss"),
+    )
+}
+
+pub fn create_index_sass(schema: &AnubisSchema) -> String {
+    let framework = match schema.settings.styling {
+        Styling::Bulma => "@import \"@/sass/bulma.scss\"\n",
+        Styling::Plain => "",
+    };
+
+    format!(
+        r#"
+// Sass modules
+{framework}@import "@/sass/fonts.sass"
+"#
+    )
+}
+
+pub fn create_theme_sass() -> String {
+    String::from(
+        r#"
 // Branding
 $primary: #48d0fd
 $link: #48d0fd
@@ -47,33 +85,25 @@ $tablet: 769px
 $desktop: 960px + 2 * $gap
 $widescreen: 1152px + 2 * $gap
 $fullhd: 1344px + 2 * $gap
-"#
-        ),
-        &schema
-            .install_directory
-            .join("frontend/src/sass/theme.sass"),
-    );
+"#,
+    )
+}
 
-    write_relic(
-        schema,
-        &format!(
-            r#"// https://fonts.google.com/share?selection.family=Montserrat:ital,wght@0,100..900;1,100..900
+pub fn create_fonts_sass() -> String {
+    String::from(
+        r#"// https://fonts.google.com/share?selection.family=Montserrat:ital,wght@0,100..900;1,100..900
 @import url('https://fonts.googleapis.com/css2?family=Montserrat:ital,wght@0,100..900;1,100..900&display=swap')
 
 // https://fonts.google.com/share?selection.family=Archivo+Black
 @import url('https://fonts.googleapis.com/css2?family=Archivo+Black&display=swap')
 
-"#
-        ),
-        &schema
-            .install_directory
-            .join("frontend/src/sass/fonts.sass"),
-    );
+"#,
+    )
+}
 
-    write_relic(
-        schema,
-        &format!(
-            r#"
+pub fn create_bulma_scss() -> String {
+    String::from(
+        r#"
 $family-titles: "Archivo Black", Roboto, sans-serif;
 $family-primary: "Montserrat", Arial, sans-serif;
 
@@ -88,10 +118,6 @@ $family-primary: "Montserrat", Arial, sans-serif;
 
 // Bulma extensions
 @import "bulma-divider";
-"#
-        ),
-        &schema
-            .install_directory
-            .join("frontend/src/sass/fonts.sass"),
-    );
+"#,
+    )
 }
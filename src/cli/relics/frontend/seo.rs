@@ -1,69 +1,84 @@
 // Copyright © 2024 Navarrotech
 
+use crate::error::AnubisError;
 use crate::relics::write::write_relic;
 use crate::schema::AnubisSchema;
 
-pub fn generate_seo(schema: &AnubisSchema) {
-    generate_robots_txt(schema);
-    generate_manifest(schema);
+pub fn generate_seo(schema: &AnubisSchema) -> Result<(), AnubisError> {
+    write_relic(
+        schema,
+        &create_robots_txt(),
+        &schema.install_directory.join("frontend/public/robots.txt"),
+    )?;
+
+    write_relic(
+        schema,
+        &create_web_manifest(schema),
+        &schema
+            .install_directory
+            .join("frontend/public/manifest.json"),
+    )
 }
 
 // TODO: Build this out
-fn generate_robots_txt(schema: &AnubisSchema) {
-    let auth_protobuf = format!(
+pub fn create_robots_txt() -> String {
+    String::from(
         r#"
 # https://www.robotstxt.org/robotstxt.html
 User-agent: *
 Disallow:
 
-"#
-    );
-
-    write_relic(
-        schema,
-        &auth_protobuf,
-        &schema.install_directory.join("frontend/public/robots.txt"),
-    );
+"#,
+    )
 }
 
 // TODO: Build this out
-fn generate_manifest(schema: &AnubisSchema) {
-    let auth_protobuf = format!(
-        r#"
+pub fn create_web_manifest(schema: &AnubisSchema) -> String {
+    format!(
+        r##"
 {{
-  \"short_name\": \"{name}\",
-  \"name\": \"{name}\",
-  \"icons\": [
+  "short_name": "{name}",
+  "name": "{name}",
+  "icons": [
     {{
-      \"src\": \"favicon.ico\",
-      \"sizes\": \"64x64 32x32 24x24 16x16\",
-      \"type\": \"image/x-icon\"
+      "src": "favicon.ico",
+      "sizes": "64x64 32x32 24x24 16x16",
+      "type": "image/x-icon"
     }},
     {{
-      \"src\": \"logo192.png\",
-      \"type\": \"image/png\",
-      \"sizes\": \"192x192\"
+      "src": "logo192.png",
+      "type": "image/png",
+      "sizes": "192x192"
     }},
     {{
-      \"src\": \"logo512.png\",
-      \"type\": \"image/png\",
-      \"sizes\": \"512x512\"
+      "src": "logo512.png",
+      "type": "image/png",
+      "sizes": "512x512"
     }}
   ],
-  \"start_url\": \".\",
-  \"display\": \"standalone\",
-  \"theme_color\": \"\#000000\",
-  \"background_color\": \"\#ffffff\"
+  "start_url": ".",
+  "display": "standalone",
+  "theme_color": "#000000",
+  "background_color": "#ffffff"
 }}
-"#,
+"##,
         name = schema.project_name
-    );
+    )
+}
 
-    write_relic(
-        schema,
-        &auth_protobuf,
-        &schema
-            .install_directory
-            .join("frontend/public/manifest.json"),
-    );
+#[cfg(test)]
+mod check_seo {
+    use super::*;
+
+    #[test]
+    fn ensure_web_manifest_is_valid_json() {
+        let test_schema = AnubisSchema {
+            project_name: String::from("Piggy Bank"),
+            ..AnubisSchema::default()
+        };
+
+        let manifest = json::parse(&create_web_manifest(&test_schema)).unwrap();
+        assert_eq!(manifest["name"], "Piggy Bank");
+        assert_eq!(manifest["theme_color"], "#000000");
+    }
 }
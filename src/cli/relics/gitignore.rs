@@ -1,10 +1,11 @@
 // Copyright © 2024 Navarrotech
 
 use crate::automatrons::write::write_automatron;
+use crate::error::AnubisError;
 use crate::schema::AnubisSchema;
 
-pub fn generate_gitignore(schema: &AnubisSchema) {
-    let auth_protobuf = format!(
+pub fn generate_gitignore(schema: &AnubisSchema) -> Result<(), AnubisError> {
+    let auth_protobuf = String::from(
         r#"
 # Unit testing results
 test-results/
@@ -15,12 +16,12 @@ api/target/
 # Frontend
 node_modules/
 yarn-error.log
-"#
+"#,
     );
 
     write_automatron(
         schema,
         &auth_protobuf,
         &schema.install_directory.join("./.gitignore"),
-    );
+    )
 }
@@ -2,5 +2,7 @@
 
 pub mod anubis_schema;
 pub mod cicd;
+pub mod command;
 pub mod frontend;
 pub mod gitignore;
+pub mod registry;
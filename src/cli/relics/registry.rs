@@ -0,0 +1,220 @@
+// Copyright © 2024 Navarrotech
+
+// Every relic Anubis knows how to create, by name
+// Relics are only written once, so this is how `relics create <name>` can restore one to its defaults
+
+use crate::cli::generate::api::cargo_toml::{create_cargo_toml, create_rocket_toml};
+use crate::cli::generate::api::server::create_custom_rs;
+use crate::cli::generate::protobufs::create_custom_protobuf;
+use crate::cli::relics::cicd::circleci::create_circleci;
+use crate::cli::relics::cicd::github_actions::create_github_actions;
+use crate::cli::relics::cicd::gitlab_ci::create_gitlab_ci;
+use crate::cli::relics::frontend::eslint::create_eslint;
+use crate::cli::relics::frontend::frontend_core::{
+    create_env, create_main_tsx, create_store_custom, create_store_index, create_vite_env,
+};
+use crate::cli::relics::frontend::i18n::{create_i18next, create_translation_json};
+use crate::cli::relics::frontend::package_json::create_package_json;
+use crate::cli::relics::frontend::scss::{
+    create_bulma_scss, create_fonts_sass, create_index_sass, create_theme_sass,
+};
+use crate::cli::relics::frontend::seo::{create_robots_txt, create_web_manifest};
+use crate::cli::relics::frontend::tsconfig::{create_tsconfig, create_tsconfig_node};
+use crate::cli::relics::frontend::vite::{create_vite_config, create_vitest_config};
+use crate::schema::AnubisSchema;
+
+pub struct RelicTemplate {
+    pub name: &'static str,
+    // Relative to the project root, `{locale}` is the default locale from Anubis.yaml
+    pub path: &'static str,
+    pub create: fn(&AnubisSchema) -> String,
+}
+
+impl RelicTemplate {
+    pub fn get_path(&self, schema: &AnubisSchema) -> String {
+        self.path
+            .replace("{locale}", &schema.settings.default_locale)
+    }
+}
+
+pub const RELICS: &[RelicTemplate] = &[
+    // CI/CD
+    RelicTemplate {
+        name: "github-actions",
+        path: ".github/workflows/build.yml",
+        create: create_github_actions,
+    },
+    RelicTemplate {
+        name: "gitlab-ci",
+        path: ".gitlab-ci.yml",
+        create: create_gitlab_ci,
+    },
+    RelicTemplate {
+        name: "circleci",
+        path: ".circleci/config.yml",
+        create: create_circleci,
+    },
+    // Api
+    RelicTemplate {
+        name: "api-cargo-toml",
+        path: "api/Cargo.toml",
+        create: create_cargo_toml,
+    },
+    RelicTemplate {
+        name: "rocket-toml",
+        path: "api/Rocket.toml",
+        create: |_| create_rocket_toml(),
+    },
+    RelicTemplate {
+        name: "api-custom",
+        path: "api/src/custom.rs",
+        create: |_| create_custom_rs(),
+    },
+    // Protobufs
+    RelicTemplate {
+        name: "custom-protobuf",
+        path: "proto/custom.proto",
+        create: |_| create_custom_protobuf(),
+    },
+    // Frontend
+    RelicTemplate {
+        name: "package-json",
+        path: "frontend/package.json",
+        create: create_package_json,
+    },
+    RelicTemplate {
+        name: "eslint",
+        path: "frontend/.eslintrc.cjs",
+        create: create_eslint,
+    },
+    RelicTemplate {
+        name: "tsconfig",
+        path: "frontend/tsconfig.json",
+        create: |_| create_tsconfig(),
+    },
+    RelicTemplate {
+        name: "tsconfig-node",
+        path: "frontend/tsconfig.node.json",
+        create: |_| create_tsconfig_node(),
+    },
+    RelicTemplate {
+        name: "vite-config",
+        path: "frontend/vite.config.ts",
+        create: |_| create_vite_config(),
+    },
+    RelicTemplate {
+        name: "vitest-config",
+        path: "frontend/vitest.config.ts",
+        create: |_| create_vitest_config(),
+    },
+    RelicTemplate {
+        name: "env",
+        path: "frontend/src/env.ts",
+        create: |_| create_env(),
+    },
+    RelicTemplate {
+        name: "vite-env",
+        path: "frontend/src/vite-env.d.ts",
+        create: |_| create_vite_env(),
+    },
+    RelicTemplate {
+        name: "main-tsx",
+        path: "frontend/src/main.tsx",
+        create: |_| create_main_tsx(),
+    },
+    RelicTemplate {
+        name: "store-index",
+        path: "frontend/src/store/index.ts",
+        create: |_| create_store_index(),
+    },
+    RelicTemplate {
+        name: "store-custom",
+        path: "frontend/src/store/custom.ts",
+        create: |_| create_store_custom(),
+    },
+    RelicTemplate {
+        name: "i18n",
+        path: "frontend/src/modules/i18n.ts",
+        create: create_i18next,
+    },
+    RelicTemplate {
+        name: "translations",
+        path: "frontend/public/locales/{locale}/translation.json",
+        create: create_translation_json,
+    },
+    RelicTemplate {
+        name: "index-sass",
+        path: "frontend/src/index.sass",
+        create: create_index_sass,
+    },
+    RelicTemplate {
+        name: "theme-sass",
+        path: "frontend/src/sass/theme.sass",
+        create: |_| create_theme_sass(),
+    },
+    RelicTemplate {
+        name: "fonts-sass",
+        path: "frontend/src/sass/fonts.sass",
+        create: |_| create_fonts_sass(),
+    },
+    RelicTemplate {
+        name: "bulma-scss",
+        path: "frontend/src/sass/bulma.scss",
+        create: |_| create_bulma_scss(),
+    },
+    RelicTemplate {
+        name: "robots-txt",
+        path: "frontend/public/robots.txt",
+        create: |_| create_robots_txt(),
+    },
+    RelicTemplate {
+        name: "web-manifest",
+        path: "frontend/public/manifest.json",
+        create: create_web_manifest,
+    },
+];
+
+pub fn find_relic(name: &str) -> Option<&'static RelicTemplate> {
+    RELICS.iter().find(|relic| relic.name == name)
+}
+
+#[cfg(test)]
+mod registry_tests {
+    use super::*;
+    use crate::cli::generate::command::{generate, GenerateArgs};
+    use crate::cli::init::init;
+    use std::collections::HashSet;
+    use tempfile::tempdir;
+
+    #[test]
+    fn relic_names_are_unique() {
+        let names: HashSet<&str> = RELICS.iter().map(|relic| relic.name).collect();
+        assert_eq!(names.len(), RELICS.len());
+    }
+
+    #[test]
+    fn every_relic_is_written_where_the_registry_says() {
+        let schema = AnubisSchema {
+            install_directory: tempdir().unwrap().into_path(),
+            ..AnubisSchema::default()
+        };
+
+        init(&schema).unwrap();
+        generate(&schema, &GenerateArgs::default()).unwrap();
+
+        for relic in RELICS
+            .iter()
+            .filter(|relic| !["gitlab-ci", "circleci"].contains(&relic.name))
+        {
+            assert!(
+                schema
+                    .install_directory
+                    .join(relic.get_path(&schema))
+                    .exists(),
+                "{} should be written to {}",
+                relic.name,
+                relic.get_path(&schema)
+            );
+        }
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright © 2024 Navarrotech
+
+// Lib
+use clap::Args;
+use std::fmt;
+use std::path::Path;
+
+// Dialoguer
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::Select;
+
+// Custom modules
+use crate::error::AnubisError;
+use crate::logger::info;
+use crate::synthetics::conflicts::{
+    clear_conflict, format_merge, get_conflicts, parse_conflict_markers,
+};
+use crate::synthetics::merge::MergeChunk;
+
+#[derive(Args)]
+pub struct ResolveArgs {
+    #[clap(short = 'd', long, default_value = "")]
+    pub directory: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    Anubis,
+    Yours,
+    Both,
+}
+
+impl Resolution {
+    fn all() -> Vec<Resolution> {
+        vec![Resolution::Anubis, Resolution::Yours, Resolution::Both]
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Resolution::Anubis => write!(f, "Keep Anubis' changes"),
+            Resolution::Yours => write!(f, "Keep your changes"),
+            Resolution::Both => write!(f, "Keep both"),
+        }
+    }
+}
+
+pub fn resolve(args: &ResolveArgs) -> Result<(), AnubisError> {
+    let root_directory = std::env::current_dir()
+        .map_err(|error| AnubisError::io(Path::new("."), error))?
+        .join(&args.directory);
+    let conflicts = get_conflicts(&root_directory)?;
+
+    if conflicts.is_empty() {
+        info!("There are no merge conflicts to resolve!");
+        return Ok(());
+    }
+
+    info!("Files with merge conflicts:\n  {}", conflicts.join("\n  "));
+
+    for conflict in conflicts.iter() {
+        let file_path = root_directory.join(conflict);
+
+        // The file may have been deleted or resolved by hand
+        if !file_path.exists() {
+            clear_conflict(&root_directory, conflict)?;
+            continue;
+        }
+
+        let content = std::fs::read_to_string(&file_path)
+            .map_err(|error| AnubisError::io(&file_path, error))?;
+
+        let chunks = parse_conflict_markers(&content);
+        let hunks = chunks
+            .iter()
+            .filter(|chunk| matches!(chunk, MergeChunk::Conflict { .. }))
+            .count();
+
+        let mut resolutions = vec![];
+        for (index, chunk) in chunks.iter().enumerate() {
+            if let MergeChunk::Conflict { .. } = chunk {
+                // The hunk is part of the prompt rather than a log, so `-q` never hides it
+                // It goes to stderr, where the prompt itself is drawn
+                eprintln!(
+                    "\n{conflict} (conflict {number} of {hunks})\n{}",
+                    format_merge(&chunks[index..index + 1]),
+                    number = resolutions.len() + 1
+                );
+
+                let items = Resolution::all();
+                let selection = Select::with_theme(&ColorfulTheme::default())
+                    .with_prompt("Which changes do you want to keep?")
+                    .items(&items)
+                    .default(1)
+                    .interact()?;
+
+                resolutions.push(items[selection].clone());
+            }
+        }
+
+        let mut resolved = resolve_chunks(&chunks, &resolutions);
+        if !resolved.ends_with('\n') {
+            resolved.push('\n');
+        }
+
+        std::fs::write(&file_path, resolved).map_err(|error| AnubisError::io(&file_path, error))?;
+
+        clear_conflict(&root_directory, conflict)?;
+        info!("Resolved {}", conflict);
+    }
+
+    info!("All merge conflicts have been resolved!");
+
+    Ok(())
+}
+
+// Applies one resolution per conflict hunk, in order, and joins the file back together
+pub fn resolve_chunks(chunks: &[MergeChunk], resolutions: &[Resolution]) -> String {
+    let mut resolutions = resolutions.iter();
+
+    let resolved_chunks: Vec<MergeChunk> = chunks
+        .iter()
+        .map(|chunk| match chunk {
+            MergeChunk::Resolved(resolved) => MergeChunk::Resolved(resolved.clone()),
+            MergeChunk::Conflict { generated, user } => match resolutions.next() {
+                Some(Resolution::Anubis) => MergeChunk::Resolved(generated.clone()),
+                Some(Resolution::Yours) => MergeChunk::Resolved(user.clone()),
+                Some(Resolution::Both) => {
+                    MergeChunk::Resolved([&generated[..], &user[..]].concat())
+                }
+                // Hunks without an answer keep their conflict markers
+                None => MergeChunk::Conflict {
+                    generated: generated.clone(),
+                    user: user.clone(),
+                },
+            },
+        })
+        .collect();
+
+    format_merge(&resolved_chunks)
+}
+
+#[cfg(test)]
+mod resolve_tests {
+    use super::*;
+
+    #[test]
+    fn resolves_each_hunk_independently() {
+        let content = "a\n<<<<<<< anubis\nb\n=======\nb1\n>>>>>>> yours\nc\n<<<<<<< anubis\nd\n=======\nd1\n>>>>>>> yours\ne\n<<<<<<< anubis\nf\n=======\nf1\n>>>>>>> yours";
+        let chunks = parse_conflict_markers(content);
+
+        let resolved = resolve_chunks(
+            &chunks,
+            &[Resolution::Anubis, Resolution::Yours, Resolution::Both],
+        );
+
+        assert_eq!(resolved, "a\nb\nc\nd1\ne\nf\nf1");
+    }
+
+    #[test]
+    fn unanswered_hunks_keep_their_markers() {
+        let content = "a\n<<<<<<< anubis\nb\n=======\nb1\n>>>>>>> yours";
+        let chunks = parse_conflict_markers(content);
+
+        assert_eq!(resolve_chunks(&chunks, &[]), content);
+    }
+}
@@ -5,7 +5,7 @@ use crate::schema::AnubisSchema;
 pub fn create_frontend_html(schema: &AnubisSchema) -> String {
     format!(
         "<!doctype html>
-<html lang=\"en\">
+<html lang=\"{locale}\">
   <head>
     <meta charset=\"UTF-8\" />
     <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\" />
@@ -41,6 +41,7 @@ pub fn create_frontend_html(schema: &AnubisSchema) -> String {
 </html>
 ",
         project_name = schema.project_name,
-        description = schema.description
+        description = schema.description,
+        locale = schema.settings.default_locale
     )
 }
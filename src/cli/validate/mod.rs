@@ -0,0 +1,249 @@
+// Copyright © 2024 Navarrotech
+
+use clap::{Args, ValueEnum};
+use std::path::Path;
+
+use crate::cli::generate::protobufs::lock::ProtobufLock;
+use crate::cli::parse::{parse_schema_yaml, SchemaError, SCHEMA_FILE};
+use crate::cli::validate::rules::{
+    validate_field_numbers, validate_schema, Severity, ValidationIssue,
+};
+use crate::error::AnubisError;
+use crate::logger::info;
+use crate::schema::AnubisSchema;
+
+#[derive(Args)]
+pub struct ValidateArgs {
+    #[clap(short = 'd', long, default_value = "")]
+    pub directory: String,
+
+    /// Report format, use `json` for editor integrations
+    #[clap(long, value_enum, default_value_t = ReportFormat::Text)]
+    pub format: ReportFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
+// Parses and validates Anubis.yaml, every issue is reported before an error is returned
+pub fn validate(args: &ValidateArgs) -> Result<AnubisSchema, AnubisError> {
+    let root_directory = std::env::current_dir()
+        .map_err(|error| AnubisError::io(Path::new("."), error))?
+        .join(&args.directory);
+
+    let schema_path = root_directory.join("Anubis.yaml");
+    if !schema_path.exists() {
+        return Err(AnubisError::MissingSchema { path: schema_path });
+    }
+
+    if args.format == ReportFormat::Text {
+        info!("Validating...");
+    }
+
+    let schema = match parse_schema_yaml(root_directory) {
+        Ok(schema) => schema,
+        Err(error) => {
+            let issues = [create_parse_issue(error)];
+            print_report(&issues, args.format);
+            return Err(create_schema_error(&issues));
+        }
+    };
+
+    let mut issues = validate_schema(&schema);
+    issues.extend(validate_field_numbers(
+        &schema,
+        &ProtobufLock::read(&schema.install_directory)?,
+    ));
+    print_report(&issues, args.format);
+
+    if count(&issues, Severity::Error) > 0 {
+        return Err(create_schema_error(&issues));
+    }
+
+    if args.format == ReportFormat::Text {
+        for (file, models) in get_models_by_file(&schema) {
+            info!("{file}: {}", models.join(", "));
+        }
+    }
+
+    Ok(schema)
+}
+
+// Which models each file from `include:` defined, empty when everything is in Anubis.yaml
+pub fn get_models_by_file(schema: &AnubisSchema) -> Vec<(String, Vec<String>)> {
+    let mut files: Vec<(String, Vec<String>)> = vec![];
+    if schema
+        .models
+        .iter()
+        .all(|model| model.source == SCHEMA_FILE)
+    {
+        return files;
+    }
+
+    for model in schema.models.iter() {
+        match files.iter_mut().find(|(file, _)| *file == model.source) {
+            Some((_, models)) => models.push(model.name.clone()),
+            None => files.push((model.source.clone(), vec![model.name.clone()])),
+        }
+    }
+
+    files
+}
+
+// A file that can't be parsed at all, which may be Anubis.yaml or one it includes
+fn create_parse_issue(error: SchemaError) -> ValidationIssue {
+    ValidationIssue {
+        severity: Severity::Error,
+        path: error.path.unwrap_or_default(),
+        message: error.message,
+        suggestion: None,
+        file: Some(error.file),
+        line: error.line,
+        column: error.column,
+    }
+}
+
+fn create_schema_error(issues: &[ValidationIssue]) -> AnubisError {
+    AnubisError::Schema {
+        message: format!(
+            "Anubis.yaml is invalid: {errors} error(s), {warnings} warning(s)",
+            errors = count(issues, Severity::Error),
+            warnings = count(issues, Severity::Warning)
+        ),
+    }
+}
+
+fn print_report(issues: &[ValidationIssue], format: ReportFormat) {
+    match format {
+        ReportFormat::Text => {
+            for issue in issues {
+                eprintln!("{}", format_issue(issue));
+            }
+
+            // When there are errors, validate returns them as an AnubisError and main reports the totals
+            let errors = count(issues, Severity::Error);
+            let warnings = count(issues, Severity::Warning);
+            if errors == 0 && warnings > 0 {
+                info!("Anubis.yaml is valid with {warnings} warning(s)");
+            }
+        }
+        ReportFormat::Json => println!("{}", create_json_report(issues)),
+    }
+}
+
+fn format_issue(issue: &ValidationIssue) -> String {
+    let file = issue.file.as_deref().unwrap_or(SCHEMA_FILE);
+    let mut location = match (issue.line, issue.column, &issue.file) {
+        (Some(line), Some(column), _) => format!("{file}:{line}:{column}"),
+        (_, _, Some(file)) => file.clone(),
+        _ => String::new(),
+    };
+    if !issue.path.is_empty() {
+        if !location.is_empty() {
+            location.push_str(": ");
+        }
+        location.push_str(&issue.path);
+    }
+
+    let mut formatted = format!(
+        "{severity}: {location}: {message}",
+        severity = issue.severity,
+        message = issue.message
+    );
+
+    if let Some(suggestion) = &issue.suggestion {
+        formatted.push_str(&format!("\n  hint: {suggestion}"));
+    }
+
+    formatted
+}
+
+pub fn create_json_report(issues: &[ValidationIssue]) -> String {
+    let mut report = json::object! {
+        valid: count(issues, Severity::Error) == 0,
+        errors: count(issues, Severity::Error),
+        warnings: count(issues, Severity::Warning),
+        issues: json::JsonValue::new_array(),
+    };
+
+    for issue in issues {
+        report["issues"]
+            .push(json::object! {
+                severity: issue.severity.to_string(),
+                path: issue.path.clone(),
+                message: issue.message.clone(),
+                suggestion: issue.suggestion.clone(),
+                file: issue.file.clone().unwrap_or(String::from(SCHEMA_FILE)),
+                line: issue.line,
+                column: issue.column,
+            })
+            .unwrap();
+    }
+
+    report.dump()
+}
+
+fn count(issues: &[ValidationIssue], severity: Severity) -> usize {
+    issues
+        .iter()
+        .filter(|issue| issue.severity == severity)
+        .count()
+}
+
+pub mod rules;
+
+#[cfg(test)]
+mod validate_tests {
+    use super::*;
+
+    #[test]
+    fn json_report_is_valid_json() {
+        let issues = vec![ValidationIssue {
+            severity: Severity::Error,
+            path: String::from("models.user.fields.age.min"),
+            message: String::from("`min` (10) is greater than `max` (5)"),
+            suggestion: Some(String::from("Swap the values or lower `min`")),
+            file: Some(String::from("models/user.yaml")),
+            line: None,
+            column: None,
+        }];
+
+        let report = json::parse(&create_json_report(&issues)).unwrap();
+
+        assert_eq!(report["valid"], false);
+        assert_eq!(report["errors"], 1);
+        assert_eq!(report["issues"][0]["path"], "models.user.fields.age.min");
+        assert_eq!(report["issues"][0]["file"], "models/user.yaml");
+        assert!(report["issues"][0]["line"].is_null());
+    }
+
+    #[test]
+    fn json_report_points_at_the_included_file() {
+        let root_directory = tempfile::tempdir().unwrap().into_path();
+        std::fs::create_dir_all(root_directory.join("models")).unwrap();
+        std::fs::write(
+            root_directory.join("Anubis.yaml"),
+            "project:\n  name: Test\ninclude:\n  - models/*.yaml\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root_directory.join("models/user.yaml"),
+            "models:\n  user:\n    fields:\n      - name: id\n        kind: strung\n",
+        )
+        .unwrap();
+
+        let error = parse_schema_yaml(root_directory).err().unwrap();
+        let report = json::parse(&create_json_report(&[create_parse_issue(error)])).unwrap();
+
+        assert_eq!(report["issues"][0]["file"], "models/user.yaml");
+        assert_eq!(report["issues"][0]["path"], "models.user.fields[0].kind");
+        assert_eq!(report["issues"][0]["line"], 5);
+        assert_eq!(report["issues"][0]["column"], 15);
+        assert!(report["issues"][0]["message"]
+            .to_string()
+            .starts_with("unknown variant `strung`"));
+    }
+}
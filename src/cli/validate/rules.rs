@@ -0,0 +1,1091 @@
+// Copyright © 2024 Navarrotech
+
+// Semantic checks over a parsed AnubisSchema
+// Every rule runs over the whole schema so the user sees all problems at once,
+// instead of fixing them one `anubis validate` at a time.
+
+use std::fmt;
+
+use crate::cli::common::to_pascal_case;
+use crate::cli::generate::protobufs::get_sync_message;
+use crate::cli::generate::protobufs::lock::ProtobufLock;
+use crate::cli::install::get_lockfile_package_manager;
+use crate::models::{FormatChoice, ModelFields, ModelKind, Models, UseOption};
+use crate::relationships::{ReferentialAction, RelationshipGraph};
+use crate::schema::AnubisSchema;
+use crate::settings::{is_locale, ScalingMode};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    // Dotted path to the offending value, i.e. 'models.user.fields.email.min'
+    pub path: String,
+    pub message: String,
+    pub suggestion: Option<String>,
+    // The schema file the issue is in, None for ones about Anubis.yaml as a whole like settings
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl ValidationIssue {
+    fn error(path: String, message: String, suggestion: Option<String>) -> Self {
+        ValidationIssue {
+            severity: Severity::Error,
+            path,
+            message,
+            suggestion,
+            file: None,
+            line: None,
+            column: None,
+        }
+    }
+
+    fn warning(path: String, message: String, suggestion: Option<String>) -> Self {
+        ValidationIssue {
+            severity: Severity::Warning,
+            ..ValidationIssue::error(path, message, suggestion)
+        }
+    }
+}
+
+pub fn validate_schema(schema: &AnubisSchema) -> Vec<ValidationIssue> {
+    let mut issues = vec![];
+
+    if schema.project_name.trim().is_empty() {
+        issues.push(ValidationIssue::error(
+            String::from("project.name"),
+            String::from("Project name cannot be empty"),
+            Some(String::from(
+                "Give your project a name, i.e. `name: 'My Project'`",
+            )),
+        ));
+    }
+
+    validate_settings(schema, &mut issues);
+
+    for (index, model) in schema.models.iter().enumerate() {
+        let path = format!("models.{}", model.name);
+        let first_issue = issues.len();
+
+        if let Some(other) = schema.models[..index]
+            .iter()
+            .find(|other| other.name == model.name)
+        {
+            let message = match other.source == model.source {
+                true => format!("Model `{}` is defined more than once", model.name),
+                false => format!(
+                    "Model `{}` is defined in both {} and {}",
+                    model.name, other.source, model.source
+                ),
+            };
+            issues.push(ValidationIssue::error(
+                path.clone(),
+                message,
+                Some(String::from("Merge both definitions into a single model")),
+            ));
+        }
+
+        validate_model(schema, model, &path, &mut issues);
+
+        // Duplicates share a name, so their issues are pinned to this definition now
+        for issue in issues[first_issue..].iter_mut() {
+            locate_issue(issue, model);
+        }
+    }
+
+    for cycle in RelationshipGraph::build(schema).find_ownership_cycles() {
+        let mut issue = ValidationIssue::error(
+            format!("models.{}.has_owner", cycle[0]),
+            format!("Ownership cycle: {}", cycle.join(" -> ")),
+            Some(String::from(
+                "Models can't own each other, remove `has_owner` from one of them",
+            )),
+        );
+        if let Some(model) = schema.get_model(&cycle[0]) {
+            locate_issue(&mut issue, model);
+        }
+        issues.push(issue);
+    }
+
+    issues
+}
+
+// Points an issue at the file the model came from, and at the field's `name:` line when it's about a field
+fn locate_issue(issue: &mut ValidationIssue, model: &Models) {
+    let fields_path = format!("models.{}.fields.", model.name);
+    let field_span = issue
+        .path
+        .strip_prefix(&fields_path)
+        .and_then(|rest| {
+            model
+                .fields
+                .iter()
+                .find(|field| rest == field.name || rest.starts_with(&format!("{}.", field.name)))
+        })
+        .and_then(|field| field.span);
+
+    issue.file = Some(model.source.clone());
+    if let Some(span) = field_span.or(model.span) {
+        issue.line = Some(span.line);
+        issue.column = Some(span.column);
+    }
+}
+
+fn validate_settings(schema: &AnubisSchema, issues: &mut Vec<ValidationIssue>) {
+    let settings = &schema.settings;
+
+    if !is_locale(&settings.default_locale) {
+        issues.push(ValidationIssue::error(
+            String::from("settings.default_locale"),
+            format!("`{}` is not a language code", settings.default_locale),
+            Some(String::from("Use a language code like `en` or `pt-BR`")),
+        ));
+    }
+
+    if settings.serverless_api && settings.scaling == ScalingMode::Horizontal {
+        issues.push(ValidationIssue::warning(
+            String::from("settings.scaling"),
+            String::from(
+                "`scaling` has no effect on a serverless api, the platform decides how many instances run",
+            ),
+            Some(String::from("Remove `scaling` from settings")),
+        ));
+    }
+
+    // The lockfile would be ignored, and the frontend installed with two package managers
+    let frontend_directory = schema.install_directory.join("frontend");
+    if let (Some(chosen), Some(found)) = (
+        settings.package_manager,
+        get_lockfile_package_manager(&frontend_directory),
+    ) {
+        if chosen != found {
+            issues.push(ValidationIssue::warning(
+                String::from("settings.package_manager"),
+                format!(
+                    "`package_manager` is {chosen}, but the frontend has a {} from {found}",
+                    found.lockfile()
+                ),
+                Some(format!(
+                    "Set `package_manager: {found}`, or delete frontend/{}",
+                    found.lockfile()
+                )),
+            ));
+        }
+    }
+}
+
+// Removed fields keep their protobuf tag and name reserved in .anubis/proto.lock,
+// bringing one back under the same name would hand deployed clients a field they can't read
+pub fn validate_field_numbers(schema: &AnubisSchema, lock: &ProtobufLock) -> Vec<ValidationIssue> {
+    let mut issues = vec![];
+
+    let graph = RelationshipGraph::build(schema);
+
+    for model in schema.models.iter() {
+        let first_issue = issues.len();
+        let sync_message = get_sync_message(schema, &graph, model);
+        if let Some(tag) = lock.get_retired_tag(&sync_message, &model.name) {
+            issues.push(ValidationIssue::error(
+                format!("models.{}", model.name),
+                format!(
+                    "Model `{}` was removed before and its protobuf tag {tag} is retired",
+                    model.name
+                ),
+                Some(String::from(
+                    "Give the model a new name, retired tags and names can't be reused",
+                )),
+            ));
+        }
+
+        let message = format!("structs.{}", to_pascal_case(&model.name));
+        for field in model.fields.iter() {
+            if let Some(tag) = lock.get_retired_tag(&message, &field.name) {
+                issues.push(ValidationIssue::error(
+                    format!("models.{}.fields.{}", model.name, field.name),
+                    format!(
+                        "Field `{}` was removed before and its protobuf tag {tag} is retired",
+                        field.name
+                    ),
+                    Some(String::from(
+                        "Give the field a new name, retired tags and names can't be reused",
+                    )),
+                ));
+            }
+        }
+
+        for issue in issues[first_issue..].iter_mut() {
+            locate_issue(issue, model);
+        }
+    }
+
+    issues
+}
+
+fn validate_model(
+    schema: &AnubisSchema,
+    model: &Models,
+    path: &str,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    if !is_identifier(&model.name) {
+        issues.push(ValidationIssue::error(
+            path.to_string(),
+            format!("Model name `{}` is not a valid identifier", model.name),
+            Some(String::from(
+                "Use lowercase letters, numbers and underscores, starting with a letter",
+            )),
+        ));
+    }
+
+    if model.fields.is_empty() {
+        issues.push(ValidationIssue::warning(
+            format!("{path}.fields"),
+            format!("Model `{}` has no fields", model.name),
+            Some(String::from("Add a `fields:` list to the model")),
+        ));
+    }
+
+    let primary_keys = model
+        .fields
+        .iter()
+        .filter(|field| field.primary_key)
+        .count();
+    if primary_keys > 1 {
+        issues.push(ValidationIssue::error(
+            format!("{path}.fields"),
+            format!(
+                "Model `{}` has {primary_keys} primary keys, only one is allowed",
+                model.name
+            ),
+            Some(String::from(
+                "Keep `primary_key: true` on one field, use `unique: true` on the others",
+            )),
+        ));
+    }
+
+    if let Some(has_owner) = &model.has_owner {
+        validate_owner(schema, has_owner, &format!("{path}.has_owner"), issues);
+    }
+
+    let has_owner_link = model
+        .fields
+        .iter()
+        .any(|field| field.use_method == Some(UseOption::OwnerLink));
+    if has_owner_link && model.has_owner.is_none() {
+        issues.push(ValidationIssue::warning(
+            format!("{path}.has_owner"),
+            format!(
+                "Model `{}` has a `use: owner` field but no `has_owner`",
+                model.name
+            ),
+            Some(String::from(
+                "Declare the owner of this model, i.e. `has_owner: user.id`",
+            )),
+        ));
+    }
+
+    for (index, field) in model.fields.iter().enumerate() {
+        let field_path = format!("{path}.fields.{}", field.name);
+
+        if model.fields[..index]
+            .iter()
+            .any(|other| other.name == field.name)
+        {
+            issues.push(ValidationIssue::error(
+                field_path.clone(),
+                format!(
+                    "Field `{}` is defined more than once in model `{}`",
+                    field.name, model.name
+                ),
+                Some(String::from("Rename or remove one of the fields")),
+            ));
+        }
+
+        validate_field(schema, field, &field_path, issues);
+
+        if let Some(through) = &field.through {
+            validate_through(schema, model, field, through, &field_path, issues);
+        }
+    }
+}
+
+// Many-to-many fields need both sides keyed, and a join table of their own
+fn validate_through(
+    schema: &AnubisSchema,
+    model: &Models,
+    field: &ModelFields,
+    through: &str,
+    path: &str,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    let path = format!("{path}.through");
+
+    let Some(links) = &field.links else {
+        issues.push(ValidationIssue::error(
+            path,
+            String::from("`through` can only be used together with `links`"),
+            Some(String::from(
+                "Add `links: <model>` to name the model on the other side",
+            )),
+        ));
+        return;
+    };
+
+    if !is_identifier(through) {
+        issues.push(ValidationIssue::error(
+            path.clone(),
+            format!("Join table name `{through}` is not a valid identifier"),
+            Some(String::from(
+                "Use lowercase letters, numbers and underscores, starting with a letter",
+            )),
+        ));
+    }
+
+    let unkeyed: Vec<&str> = [Some(model), schema.get_model(links)]
+        .into_iter()
+        .flatten()
+        .filter(|model| model.primary_key().is_none())
+        .map(|model| model.name.as_str())
+        .collect();
+    for name in unkeyed {
+        issues.push(ValidationIssue::error(
+            path.clone(),
+            format!("Model `{name}` needs a primary key to be linked many-to-many"),
+            Some(format!(
+                "Add a field with `primary_key: true` to model `{name}`"
+            )),
+        ));
+    }
+
+    let shared = schema.models.iter().find_map(|other| {
+        other
+            .fields
+            .iter()
+            .find(|other_field| {
+                other_field.through.as_deref() == Some(through)
+                    && !std::ptr::eq(*other_field, field)
+            })
+            .map(|other_field| format!("{}.{}", other.name, other_field.name))
+    });
+    if let Some(shared) = shared {
+        issues.push(ValidationIssue::error(
+            path.clone(),
+            format!("Join table `{through}` is also used by `{shared}`"),
+            Some(String::from(
+                "Give each many-to-many field its own `through:` name",
+            )),
+        ));
+    }
+
+    if let Some(join_model) = schema.get_model(through) {
+        if join_model.primary_key().is_some() {
+            issues.push(ValidationIssue::error(
+                format!("models.{through}.fields"),
+                format!(
+                    "Join model `{through}` can't have a primary key, it's keyed by the two linked ids"
+                ),
+                Some(String::from("Remove `primary_key: true` from its fields")),
+            ));
+        }
+    }
+}
+
+fn validate_field(
+    schema: &AnubisSchema,
+    field: &ModelFields,
+    path: &str,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    if !is_identifier(&field.name) {
+        issues.push(ValidationIssue::error(
+            format!("{path}.name"),
+            format!("Field name `{}` is not a valid identifier", field.name),
+            Some(String::from(
+                "Use lowercase letters, numbers and underscores, starting with a letter",
+            )),
+        ));
+    }
+
+    if let (Some(minimum), Some(maximum)) = (field.minimum, field.maximum) {
+        if minimum > maximum {
+            issues.push(ValidationIssue::error(
+                format!("{path}.min"),
+                format!("`min` ({minimum}) is greater than `max` ({maximum})"),
+                Some(String::from("Swap the values or lower `min`")),
+            ));
+        }
+    }
+
+    let has_no_range = matches!(
+        field.kind,
+        ModelKind::Boolean | ModelKind::Uuid | ModelKind::Json | ModelKind::Money
+    );
+    if has_no_range && (field.minimum.is_some() || field.maximum.is_some()) {
+        issues.push(ValidationIssue::warning(
+            format!("{path}.min"),
+            format!("`min` and `max` have no effect on {} fields", field.kind),
+            Some(String::from("Remove `min` and `max` from this field")),
+        ));
+    }
+
+    if field.primary_key && field.kind.is_structured() {
+        issues.push(ValidationIssue::error(
+            format!("{path}.primary_key"),
+            format!("A `kind: {}` field can't be a primary key", field.kind),
+            Some(String::from(
+                "Use a string, number or uuid field as the key",
+            )),
+        ));
+    }
+
+    if let Some(default) = &field.default {
+        if field.kind.is_structured() || field.kind == ModelKind::DateTime {
+            issues.push(ValidationIssue::error(
+                format!("{path}.default"),
+                format!("`kind: {}` fields can't have a default", field.kind),
+                Some(String::from("Remove `default` from this field")),
+            ));
+        } else if !field.kind.is_valid_default(default) {
+            issues.push(ValidationIssue::error(
+                format!("{path}.default"),
+                format!("`{default}` is not a valid {}", field.kind),
+                match field.kind {
+                    ModelKind::Date => Some(String::from("Write dates as YYYY-MM-DD")),
+                    ModelKind::Uuid => Some(String::from(
+                        "Write uuids as 00000000-0000-0000-0000-000000000000",
+                    )),
+                    _ => None,
+                },
+            ));
+        }
+    }
+
+    if field.use_method == Some(UseOption::Uuid)
+        && !matches!(field.kind, ModelKind::String | ModelKind::Uuid)
+    {
+        issues.push(ValidationIssue::error(
+            format!("{path}.use"),
+            format!("`use: uuid` can't fill a `kind: {}` field", field.kind),
+            Some(String::from("Change the field to `kind: uuid`")),
+        ));
+    }
+
+    if let Some(format) = &field.format {
+        if field.kind != ModelKind::String {
+            issues.push(ValidationIssue::error(
+                format!("{path}.format"),
+                format!(
+                    "`format: {}` can only be used with `kind: string`",
+                    format_name(format)
+                ),
+                Some(String::from("Change the field to `kind: string`")),
+            ));
+        }
+    }
+
+    if let Some(use_enum) = &field.use_enum {
+        if field.kind != ModelKind::String {
+            issues.push(ValidationIssue::error(
+                format!("{path}.enum"),
+                String::from("`enum` can only be used with `kind: string`"),
+                Some(String::from("Change the field to `kind: string`")),
+            ));
+        }
+
+        if use_enum.is_empty() {
+            issues.push(ValidationIssue::error(
+                format!("{path}.enum"),
+                String::from("`enum` must list at least one value"),
+                None,
+            ));
+        }
+
+        if let Some(default) = &field.default {
+            if !use_enum.contains(default) {
+                issues.push(ValidationIssue::error(
+                    format!("{path}.default"),
+                    format!("Default value `{default}` is not one of the `enum` values"),
+                    Some(format!("Use one of: {}", use_enum.join(", "))),
+                ));
+            }
+        }
+
+        if let Some(on_unknown) = &field.on_unknown {
+            if !use_enum.contains(on_unknown) {
+                issues.push(ValidationIssue::error(
+                    format!("{path}.on_unknown"),
+                    format!("`on_unknown` value `{on_unknown}` is not one of the `enum` values"),
+                    Some(format!("Use one of: {}", use_enum.join(", "))),
+                ));
+            }
+        }
+    } else if field.on_unknown.is_some() {
+        issues.push(ValidationIssue::warning(
+            format!("{path}.on_unknown"),
+            String::from("`on_unknown` has no effect without `enum`"),
+            Some(String::from("Add an `enum` list or remove `on_unknown`")),
+        ));
+    }
+
+    if let Some(links) = &field.links {
+        match schema.get_model(links) {
+            None => issues.push(ValidationIssue::error(
+                format!("{path}.links"),
+                format!("`links` points at model `{links}`, which does not exist"),
+                suggest_model(schema, links),
+            )),
+            Some(linked) if linked.primary_key().is_none() && field.is_column() => {
+                issues.push(ValidationIssue::error(
+                    format!("{path}.links"),
+                    format!("`links` points at model `{links}`, which has no primary key"),
+                    Some(format!(
+                        "Add a field with `primary_key: true` to model `{links}`"
+                    )),
+                ))
+            }
+            Some(_) => (),
+        }
+    }
+
+    validate_referential_actions(field, path, issues);
+}
+
+// `on_delete` and `on_update` only apply to the foreign key of a `links` or `use: owner` field
+fn validate_referential_actions(
+    field: &ModelFields,
+    path: &str,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    let has_foreign_key = (field.links.is_some() && field.is_column())
+        || field.use_method == Some(UseOption::OwnerLink);
+
+    for (key, action) in [
+        ("on_delete", field.on_delete),
+        ("on_update", field.on_update),
+    ] {
+        let Some(action) = action else {
+            continue;
+        };
+
+        if !has_foreign_key {
+            issues.push(ValidationIssue::warning(
+                format!("{path}.{key}"),
+                format!("`{key}` has no effect without `links` or `use: owner`"),
+                Some(format!("Remove `{key}` from this field")),
+            ));
+        } else if action == ReferentialAction::SetNull && !field.is_nullable() {
+            issues.push(ValidationIssue::error(
+                format!("{path}.{key}"),
+                format!("`{key}: set_null` needs the field to be optional"),
+                Some(String::from(
+                    "Add `required: false` to the field, or use `cascade` or `restrict`",
+                )),
+            ));
+        }
+    }
+}
+
+// has_owner is written as `model.field`, both must exist
+// and the field has to be unique, PostgreSQL only accepts foreign keys to unique columns
+fn validate_owner(
+    schema: &AnubisSchema,
+    has_owner: &str,
+    path: &str,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    let Some((model_name, field_name)) = has_owner.split_once('.') else {
+        issues.push(ValidationIssue::error(
+            path.to_string(),
+            format!("`has_owner: {has_owner}` must be written as `model.field`"),
+            Some(String::from("For example, `has_owner: user.id`")),
+        ));
+        return;
+    };
+
+    match schema.models.iter().find(|model| model.name == model_name) {
+        None => issues.push(ValidationIssue::error(
+            path.to_string(),
+            format!("`has_owner` points at model `{model_name}`, which does not exist"),
+            suggest_model(schema, model_name),
+        )),
+        Some(owner) => match owner.fields.iter().find(|field| field.name == field_name) {
+            None => issues.push(ValidationIssue::error(
+                path.to_string(),
+                format!(
+                    "`has_owner` points at field `{field_name}`, which does not exist on model `{model_name}`"
+                ),
+                Some(format!(
+                    "Use one of: {}",
+                    owner
+                        .fields
+                        .iter()
+                        .map(|field| format!("{model_name}.{}", field.name))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )),
+            )),
+            Some(field) if !field.primary_key && !field.unique => {
+                let unique_fields: Vec<String> = owner
+                    .fields
+                    .iter()
+                    .filter(|field| field.primary_key || field.unique)
+                    .map(|field| format!("{model_name}.{}", field.name))
+                    .collect();
+
+                issues.push(ValidationIssue::error(
+                    path.to_string(),
+                    format!(
+                        "`has_owner` points at `{model_name}.{field_name}`, which is neither a primary key nor unique"
+                    ),
+                    Some(match unique_fields.is_empty() {
+                        true => format!(
+                            "Mark `{model_name}.{field_name}` as `primary_key: true` or `unique: true`"
+                        ),
+                        false => format!(
+                            "Use one of: {}, or mark `{model_name}.{field_name}` as `unique: true`",
+                            unique_fields.join(", ")
+                        ),
+                    }),
+                ))
+            }
+            Some(_) => (),
+        },
+    }
+}
+
+fn suggest_model(schema: &AnubisSchema, name: &str) -> Option<String> {
+    schema
+        .models
+        .iter()
+        .map(|model| (edit_distance(&model.name, name), &model.name))
+        .filter(|(distance, _)| *distance <= 2 && *distance < name.len())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, model_name)| format!("Did you mean `{model_name}`?"))
+}
+
+fn format_name(format: &FormatChoice) -> &'static str {
+    match format {
+        FormatChoice::Email => "email",
+        FormatChoice::Phone => "phone",
+        FormatChoice::Password => "password",
+        FormatChoice::Secret => "secret",
+    }
+}
+
+// Names end up as table, column, protobuf and Rust identifiers
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_lowercase() => {
+            chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b_chars.len()]
+}
+
+#[cfg(test)]
+mod rules_tests {
+    use super::*;
+    use crate::cli::parse::parse_schema_str;
+    use std::path::PathBuf;
+
+    fn issues_for(yaml_content: &str) -> Vec<ValidationIssue> {
+        let schema = parse_schema_str(yaml_content, PathBuf::from("/tmp")).unwrap();
+        validate_schema(&schema)
+    }
+
+    fn paths(issues: &[ValidationIssue]) -> Vec<&str> {
+        issues.iter().map(|issue| issue.path.as_str()).collect()
+    }
+
+    #[test]
+    fn example_schema_has_no_errors() {
+        let issues = issues_for(include_str!("../../../examples/Anubis.yaml"));
+
+        assert!(issues.iter().all(|issue| issue.severity != Severity::Error));
+    }
+
+    #[test]
+    fn reports_every_problem_at_once() {
+        let issues = issues_for(
+            "
+project:
+  name: Test
+models:
+  user:
+    has_owner: usr.id
+    fields:
+      - name: id
+        primary_key: true
+      - name: id
+        primary_key: true
+      - name: age
+        kind: number
+        min: 10
+        max: 5
+        enum: [a, b]
+      - name: language
+        default: de
+        enum: [en, es]
+      - name: settings
+        links: setings
+  settings:
+    fields:
+      - name: id
+",
+        );
+
+        assert_eq!(
+            paths(&issues),
+            vec![
+                "models.user.fields",
+                "models.user.has_owner",
+                "models.user.fields.id",
+                "models.user.fields.age.min",
+                "models.user.fields.age.enum",
+                "models.user.fields.language.default",
+                "models.user.fields.settings.links",
+            ]
+        );
+        assert!(issues.iter().all(|issue| issue.severity == Severity::Error));
+        assert_eq!(
+            issues[1].suggestion,
+            Some(String::from("Did you mean `user`?"))
+        );
+        assert_eq!(
+            issues[6].suggestion,
+            Some(String::from("Did you mean `settings`?"))
+        );
+    }
+
+    #[test]
+    fn reports_invalid_many_to_many_fields() {
+        let issues = issues_for(
+            "
+project:
+  name: Test
+models:
+  user:
+    fields:
+      - name: id
+        primary_key: true
+      - name: tags
+        through: user_tags
+      - name: groups
+        links: group
+        through: memberships
+  group:
+    fields:
+      - name: name
+  memberships:
+    fields:
+      - name: id
+        primary_key: true
+",
+        );
+
+        assert_eq!(
+            paths(&issues),
+            vec![
+                "models.user.fields.tags.through",
+                "models.user.fields.groups.through",
+                "models.memberships.fields",
+            ]
+        );
+        assert_eq!(
+            issues[1].message,
+            "Model `group` needs a primary key to be linked many-to-many"
+        );
+    }
+
+    #[test]
+    fn reports_kinds_used_the_wrong_way() {
+        let issues = issues_for(
+            "
+project:
+  name: Test
+models:
+  invoice:
+    fields:
+      - name: lines
+        kind: list<string>
+        primary_key: true
+      - name: due
+        kind: date
+        default: 31/01/2024
+      - name: metadata
+        kind: json
+        default: '{}'
+      - name: code
+        kind: number
+        use: uuid
+      - name: reference
+        kind: uuid
+        max: 36
+",
+        );
+
+        assert_eq!(
+            paths(&issues),
+            vec![
+                "models.invoice.fields.lines.primary_key",
+                "models.invoice.fields.due.default",
+                "models.invoice.fields.metadata.default",
+                "models.invoice.fields.code.use",
+                "models.invoice.fields.reference.min",
+            ]
+        );
+        assert_eq!(issues[1].message, "`31/01/2024` is not a valid date");
+    }
+
+    #[test]
+    fn reports_ownership_cycles_and_bad_referential_actions() {
+        let issues = issues_for(
+            "
+project:
+  name: Test
+models:
+  team:
+    has_owner: member.id
+    fields:
+      - name: id
+        primary_key: true
+      - name: lead
+        links: member
+        on_delete: set_null
+      - name: name
+        on_update: cascade
+  member:
+    has_owner: team.id
+    fields:
+      - name: id
+        primary_key: true
+",
+        );
+
+        assert_eq!(
+            paths(&issues),
+            vec![
+                "models.team.fields.lead.on_delete",
+                "models.team.fields.name.on_update",
+                "models.team.has_owner",
+            ]
+        );
+        assert_eq!(issues[1].severity, Severity::Warning);
+        assert_eq!(issues[2].message, "Ownership cycle: team -> member -> team");
+    }
+
+    #[test]
+    fn reports_invalid_settings() {
+        let issues = issues_for(
+            "
+project:
+  name: Test
+settings:
+  default_locale: English
+  serverless_api: true
+  scaling: horizontal
+",
+        );
+
+        assert_eq!(
+            paths(&issues),
+            vec!["settings.default_locale", "settings.scaling"]
+        );
+        assert_eq!(issues[0].severity, Severity::Error);
+        assert_eq!(issues[1].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn reports_models_defined_in_two_files() {
+        let mut schema = parse_schema_str(
+            "
+project:
+  name: Test
+models:
+  user:
+    fields:
+      - name: id
+",
+            PathBuf::from("/tmp"),
+        )
+        .unwrap();
+        schema.models.push(Models {
+            name: String::from("user"),
+            source: String::from("models/user.yaml"),
+            fields: vec![ModelFields {
+                name: String::from("Id"),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        let issues = validate_schema(&schema);
+
+        assert_eq!(
+            paths(&issues),
+            vec!["models.user", "models.user.fields.Id.name"]
+        );
+        assert_eq!(
+            issues[0].message,
+            "Model `user` is defined in both Anubis.yaml and models/user.yaml"
+        );
+        assert!(issues
+            .iter()
+            .all(|issue| issue.file.as_deref() == Some("models/user.yaml")));
+    }
+
+    #[test]
+    fn reports_duplicate_models() {
+        let issues = issues_for(
+            "
+project:
+  name: Test
+models:
+  user:
+    fields:
+      - name: id
+  user:
+    fields:
+      - name: id
+",
+        );
+
+        assert_eq!(paths(&issues), vec!["models.user"]);
+    }
+
+    #[test]
+    fn reports_missing_owner_fields() {
+        let issues = issues_for(
+            "
+project:
+  name: Test
+models:
+  user:
+    fields:
+      - name: id
+  posts:
+    has_owner: user.uuid
+    fields:
+      - name: id
+",
+        );
+
+        assert_eq!(paths(&issues), vec!["models.posts.has_owner"]);
+        assert_eq!(
+            issues[0].suggestion,
+            Some(String::from("Use one of: user.id"))
+        );
+    }
+
+    #[test]
+    fn reports_owner_fields_that_are_not_keys() {
+        let issues = issues_for(
+            "
+project:
+  name: Test
+models:
+  user:
+    fields:
+      - name: id
+        primary_key: true
+      - name: email
+        unique: true
+      - name: name
+  posts:
+    has_owner: user.name
+    fields:
+      - name: id
+        primary_key: true
+  comments:
+    has_owner: user.email
+    fields:
+      - name: id
+        primary_key: true
+",
+        );
+
+        assert_eq!(paths(&issues), vec!["models.posts.has_owner"]);
+        assert_eq!(
+            issues[0].message,
+            "`has_owner` points at `user.name`, which is neither a primary key nor unique"
+        );
+        assert_eq!(
+            issues[0].suggestion,
+            Some(String::from(
+                "Use one of: user.id, user.email, or mark `user.name` as `unique: true`"
+            ))
+        );
+    }
+
+    #[test]
+    fn reports_reused_protobuf_tags() {
+        let schema = parse_schema_str(
+            r#"
+project:
+  name: Test
+models:
+  user:
+    fields:
+      - name: id
+        primary_key: true
+      - name: nickname
+"#,
+            PathBuf::from("/tmp"),
+        )
+        .unwrap();
+
+        let mut lock = ProtobufLock::default();
+        for field in ["id", "nickname", "age"] {
+            lock.get_tag("structs.User", field, 1);
+        }
+        lock.retire_missing("structs.User", &["id"]);
+
+        let issues = validate_field_numbers(&schema, &lock);
+
+        assert_eq!(paths(&issues), vec!["models.user.fields.nickname"]);
+        assert_eq!(
+            issues[0].message,
+            "Field `nickname` was removed before and its protobuf tag 2 is retired"
+        );
+    }
+
+    #[test]
+    fn measures_edit_distance() {
+        assert_eq!(edit_distance("user", "user"), 0);
+        assert_eq!(edit_distance("usr", "user"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright © 2024 Navarrotech
+
+// Every way an Anubis command can fail
+// Functions return these instead of panicking, and main turns them into a friendly message,
+// an optional hint, and an exit code so scripts can tell failures apart
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum AnubisError {
+    // Anubis.yaml doesn't exist in the project directory
+    MissingSchema { path: PathBuf },
+    // Anubis.yaml (or a file generated from it) is invalid
+    Schema { message: String },
+    // Reading, writing or parsing a file failed
+    Io { path: PathBuf, source: io::Error },
+    // External tools like yarn or cargo are missing or failed, one line for each that failed
+    Tool { failures: Vec<String> },
+    // Synthetic files with merge conflicts must be resolved before they can be merged again
+    Conflicts { files: Vec<String> },
+    // Going ahead would lose the user's work or data, so nothing was done
+    Refused { message: String, hint: String },
+    // `generate --check` found generated files that don't match Anubis.yaml
+    OutOfDate { files: usize },
+    // Bad input on the command line, i.e. an unknown relic name
+    Usage { message: String },
+}
+
+impl AnubisError {
+    pub fn io(path: &Path, source: io::Error) -> AnubisError {
+        AnubisError::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    // A file Anubis wrote itself (manifest, lock, snapshot) that can no longer be parsed
+    pub fn invalid_data(path: &Path, message: impl fmt::Display) -> AnubisError {
+        AnubisError::io(
+            path,
+            io::Error::new(io::ErrorKind::InvalidData, message.to_string()),
+        )
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AnubisError::OutOfDate { .. } => 1,
+            AnubisError::Usage { .. } => 2,
+            AnubisError::MissingSchema { .. } | AnubisError::Schema { .. } => 3,
+            AnubisError::Io { .. } => 4,
+            AnubisError::Tool { .. } => 5,
+            AnubisError::Conflicts { .. } => 6,
+            AnubisError::Refused { .. } => 7,
+        }
+    }
+
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            AnubisError::MissingSchema { .. } => Some(String::from(
+                "Run `cargo anubis init` to create a project, or pass `-d` to point at an existing one",
+            )),
+            AnubisError::Schema { .. } => None,
+            AnubisError::Io { source, .. } => match source.kind() {
+                io::ErrorKind::PermissionDenied => {
+                    Some(String::from("Check the file permissions of the project directory"))
+                }
+                io::ErrorKind::InvalidData => Some(String::from(
+                    "This file is managed by Anubis, restore it from version control or delete it to start over",
+                )),
+                _ => None,
+            },
+            AnubisError::Tool { .. } => Some(String::from(
+                "Check the output above, and that each tool is installed and available on your PATH",
+            )),
+            AnubisError::Conflicts { .. } => {
+                Some(String::from("Run `cargo anubis resolve` to resolve them"))
+            }
+            AnubisError::Refused { hint, .. } => Some(hint.clone()),
+            AnubisError::OutOfDate { .. } => Some(String::from(
+                "Run `cargo anubis generate` to update them",
+            )),
+            AnubisError::Usage { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for AnubisError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnubisError::MissingSchema { path } => {
+                write!(f, "Could not find {}", path.display())
+            }
+            AnubisError::Schema { message } => write!(f, "{message}"),
+            AnubisError::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            AnubisError::Tool { failures } => {
+                write!(f, "{} external tool(s) failed:", failures.len())?;
+                for failure in failures.iter() {
+                    write!(f, "\n  {failure}")?;
+                }
+                Ok(())
+            }
+            AnubisError::Conflicts { files } => {
+                write!(
+                    f,
+                    "Cannot generate while these files have unresolved merge conflicts:"
+                )?;
+                for file in files.iter() {
+                    write!(f, "\n  {file}")?;
+                }
+                Ok(())
+            }
+            AnubisError::Refused { message, .. } => write!(f, "{message}"),
+            AnubisError::OutOfDate { files } => {
+                write!(f, "{files} generated file(s) are out of date")
+            }
+            AnubisError::Usage { message } => write!(f, "{message}"),
+        }
+    }
+}
+
+// Prompts fail when the terminal goes away, i.e. the user closed it mid prompt
+impl From<dialoguer::Error> for AnubisError {
+    fn from(error: dialoguer::Error) -> Self {
+        let dialoguer::Error::IO(source) = error;
+        AnubisError::io(Path::new("stdin"), source)
+    }
+}
+
+impl std::error::Error for AnubisError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AnubisError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    #[test]
+    fn every_kind_of_error_has_its_own_exit_code() {
+        let errors = [
+            AnubisError::OutOfDate { files: 1 },
+            AnubisError::Usage {
+                message: String::new(),
+            },
+            AnubisError::Schema {
+                message: String::new(),
+            },
+            AnubisError::io(Path::new("a"), io::Error::other("b")),
+            AnubisError::Tool { failures: vec![] },
+            AnubisError::Conflicts { files: vec![] },
+            AnubisError::Refused {
+                message: String::new(),
+                hint: String::new(),
+            },
+        ];
+
+        let mut codes: Vec<i32> = errors.iter().map(|error| error.exit_code()).collect();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+        assert!(!codes.contains(&0));
+    }
+
+    #[test]
+    fn messages_point_at_the_file() {
+        let error = AnubisError::invalid_data(Path::new(".anubis/manifest.json"), "bad json");
+
+        assert_eq!(error.to_string(), ".anubis/manifest.json: bad json");
+        assert!(error.hint().is_some());
+    }
+}
@@ -1,10 +0,0 @@
-// Copyright © 2024 Navarrotech
-
-// This list is used when Anubis needs to modify a global
-// For example, when changing a copyright header globally
-// Or when changing the project name globally
-
-const FILES_USED_BY_ANUBIS: &[&str] = &[
-    ".github/workflows/build.yml",
-    ".circleci/config.yml"
-];
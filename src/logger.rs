@@ -0,0 +1,165 @@
+// Copyright © 2024 Navarrotech
+
+// The one place Anubis reports what it's doing
+// Commands log through the macros below instead of println!, so `-q`, `-v` and `-vv` hide or
+// reveal the same messages everywhere, and `--log-format json` turns each one into a JSON line
+//
+// Text logs at info level go to stdout like before, everything else goes to stderr
+// so piping a command's output never picks up warnings or debug noise
+
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::time::Instant;
+
+use clap::ValueEnum;
+
+use crate::error::AnubisError;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+    Trace = 4,
+}
+
+impl Level {
+    // `-q` only keeps warnings and errors, each `-v` reveals one more level
+    pub fn from_flags(quiet: bool, verbose: u8) -> Level {
+        match (quiet, verbose) {
+            (true, _) => Level::Warn,
+            (false, 0) => Level::Info,
+            (false, 1) => Level::Debug,
+            (false, _) => Level::Trace,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warning",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+static JSON: AtomicBool = AtomicBool::new(false);
+
+pub fn init(level: Level, format: LogFormat) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+    JSON.store(format == LogFormat::Json, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+pub fn is_enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+pub fn log(level: Level, message: &str) {
+    log_with(level, message, json::JsonValue::new_object());
+}
+
+// Logs a message with extra structured fields, which only show up in JSON logs
+pub fn log_with(level: Level, message: &str, fields: json::JsonValue) {
+    if !is_enabled(level) {
+        return;
+    }
+
+    if is_json() {
+        eprintln!("{}", create_json_line(level, message, fields));
+        return;
+    }
+
+    match level {
+        Level::Info => println!("{message}"),
+        _ => eprintln!("{}: {message}", level.name()),
+    }
+}
+
+pub fn create_json_line(level: Level, message: &str, fields: json::JsonValue) -> String {
+    let mut line = json::object! {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        level: level.name(),
+        message: message,
+    };
+
+    for (key, value) in fields.entries() {
+        line[key] = value.clone();
+    }
+
+    line.dump()
+}
+
+// Runs one step of a command, logging how long it took once it succeeds
+pub fn step<T>(
+    summary: &str,
+    run: impl FnOnce() -> Result<T, AnubisError>,
+) -> Result<T, AnubisError> {
+    let started = Instant::now();
+    let result = run()?;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+
+    log_with(
+        Level::Info,
+        &format!("{summary} ({elapsed_ms}ms)"),
+        json::object! { step: summary, elapsed_ms: elapsed_ms },
+    );
+
+    Ok(result)
+}
+
+// `warn` is taken by the built-in lint attribute
+macro_rules! warning {
+    ($($arg:tt)*) => { $crate::logger::log($crate::logger::Level::Warn, &format!($($arg)*)) };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => { $crate::logger::log($crate::logger::Level::Info, &format!($($arg)*)) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => { $crate::logger::log($crate::logger::Level::Debug, &format!($($arg)*)) };
+}
+
+pub(crate) use {debug, info, warning};
+
+#[cfg(test)]
+mod logger_tests {
+    use super::*;
+
+    #[test]
+    fn flags_pick_the_level() {
+        assert_eq!(Level::from_flags(true, 2), Level::Warn);
+        assert_eq!(Level::from_flags(false, 0), Level::Info);
+        assert_eq!(Level::from_flags(false, 1), Level::Debug);
+        assert_eq!(Level::from_flags(false, 2), Level::Trace);
+    }
+
+    #[test]
+    fn json_lines_carry_their_fields() {
+        let line = create_json_line(
+            Level::Debug,
+            "create automatron proto/root.proto",
+            json::object! { path: "proto/root.proto" },
+        );
+        let parsed = json::parse(&line).unwrap();
+
+        assert_eq!(parsed["level"], "debug");
+        assert_eq!(parsed["message"], "create automatron proto/root.proto");
+        assert_eq!(parsed["path"], "proto/root.proto");
+        assert!(parsed["timestamp"].is_string());
+    }
+}
@@ -2,96 +2,174 @@
 
 // Lib
 use chrono::Datelike;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use cli::files::{list_files, FilesArgs};
 use cli::generate::command::{generate, GenerateArgs};
+use cli::identity::{change_copyright, rename_project, CopyrightArgs, RenameArgs};
 use cli::install::{install_dependencies, InstallDependenciesArgs};
-use cli::validate::{validate, ValidateArgs};
+use cli::relics::command::{create_relic_by_name, RelicsArgs, RelicsCommand};
+use cli::resolve::{resolve, ResolveArgs};
+use cli::validate::{validate, ReportFormat, ValidateArgs};
+use error::AnubisError;
+use logger::{Level, LogFormat};
 use std::env;
-
-// Dialoguer
-use dialoguer::theme::ColorfulTheme;
-use dialoguer::Input;
+use std::path::Path;
 
 // Custom modules
-use crate::cli::init::{init, InitArgs};
-use crate::schema::AnubisSchema;
+use crate::cli::init::{get_init_options, init, is_interactive, InitArgs};
+use crate::synthetics::conflicts::get_conflicts;
 
 #[derive(Parser)]
 #[clap(version = "1.0", author = "Alex Navarro")]
 #[command(name = "cargo", bin_name = "cargo")]
+struct Cli {
+    #[command(subcommand)]
+    command: CargoCli,
+
+    /// Only show warnings and errors
+    #[clap(short = 'q', long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Show every file written with `-v`, and even more with `-vv`
+    #[clap(short = 'v', long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Use `--log-format json` for one JSON object per line, i.e. for CI logs
+    #[clap(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+}
+
+#[derive(Subcommand)]
 enum CargoCli {
     Init(InitArgs),
     Validate(ValidateArgs),
     Generate(GenerateArgs),
     Install(InstallDependenciesArgs),
+    Resolve(ResolveArgs),
+    Relics(RelicsArgs),
+    Files(FilesArgs),
+    Rename(RenameArgs),
+    Copyright(CopyrightArgs),
 }
 
-fn main() -> std::io::Result<()> {
-    let cli = CargoCli::parse();
+fn main() {
+    let cli = Cli::parse();
+    logger::init(Level::from_flags(cli.quiet, cli.verbose), cli.log_format);
+
+    if let Err(error) = run(cli.command) {
+        report_error(&error);
+        std::process::exit(error.exit_code());
+    }
+}
+
+fn report_error(error: &AnubisError) {
+    let hint = error.hint();
+
+    if logger::is_json() {
+        logger::log_with(
+            Level::Error,
+            &error.to_string(),
+            json::object! { hint: hint, exit_code: error.exit_code() },
+        );
+        return;
+    }
+
+    match hint {
+        Some(hint) => logger::log(Level::Error, &format!("{error}\n  hint: {hint}")),
+        None => logger::log(Level::Error, &error.to_string()),
+    }
+}
 
+fn run(cli: CargoCli) -> Result<(), AnubisError> {
     let now = chrono::Utc::now();
     let year = now.year();
 
     match cli {
         CargoCli::Init(args) => {
-            let project_name: String = match args.name.is_empty() {
-                true => Input::with_theme(&ColorfulTheme::default())
-                    .with_prompt("Project name: ")
-                    .default(String::from(""))
-                    .interact_text()
-                    .unwrap(),
-                false => args.name,
-            };
-
-            let copyright_header: String = match args.copy.is_empty() {
-                true => Input::with_theme(&ColorfulTheme::default())
-                    .with_prompt("Copyright header: (Leave blank for none)")
-                    .with_initial_text(String::from("Copyright © {YYYY} MyCompany"))
-                    .interact_text()
-                    .unwrap(),
-                false => args.copy,
-            };
-
-            let copyright_header_formatted = copyright_header.replace("{YYYY}", &year.to_string());
-            let install_directory = env::current_dir()?.join(&args.directory);
-
-            let schema = AnubisSchema {
-                project_name,
-                copyright_header,
-                copyright_header_formatted,
-                install_directory: install_directory.clone(),
-                ..Default::default()
-            };
-
-            init(&schema)?;
+            let install_directory = env::current_dir()
+                .map_err(|error| AnubisError::io(Path::new("."), error))?
+                .join(&args.directory);
+            let interactive = is_interactive(&args);
+            let options = get_init_options(&args, install_directory.clone(), year, interactive)?;
+
+            init(&options.schema)?;
             generate(
-                &schema,
+                &options.schema,
                 &GenerateArgs {
-                    directory: install_directory.to_str().unwrap().to_string(),
-                },
-            );
-            install_dependencies(
-                &schema,
-                &InstallDependenciesArgs {
-                    directory: install_directory.to_str().unwrap().to_string(),
+                    directory: install_directory.to_string_lossy().to_string(),
+                    ..Default::default()
                 },
-            );
+            )?;
+
+            if options.install {
+                install_dependencies(
+                    &options.schema,
+                    &InstallDependenciesArgs {
+                        directory: install_directory.to_string_lossy().to_string(),
+                        ..Default::default()
+                    },
+                )?;
+            }
         }
         CargoCli::Validate(args) => {
-            validate(&args);
-            println!("Your Anubis.yaml file is valid!");
+            validate(&args)?;
+            if args.format == ReportFormat::Text {
+                logger::info!("Your Anubis.yaml file is valid!");
+            }
         }
         CargoCli::Generate(args) => {
             let schema = validate(&ValidateArgs {
                 directory: args.directory.clone(),
-            });
-            generate(&schema, &args);
+                format: ReportFormat::Text,
+            })?;
+
+            // Synthetic files with merge conflicts must be resolved before they can be merged again
+            let conflicts = get_conflicts(&schema.install_directory)?;
+            if !conflicts.is_empty() {
+                return Err(AnubisError::Conflicts { files: conflicts });
+            }
+
+            generate(&schema, &args)?;
         }
         CargoCli::Install(args) => {
             let schema = validate(&ValidateArgs {
                 directory: args.directory.clone(),
-            });
-            install_dependencies(&schema, &args);
+                format: ReportFormat::Text,
+            })?;
+            install_dependencies(&schema, &args)?;
+        }
+        CargoCli::Resolve(args) => {
+            resolve(&args)?;
+        }
+        CargoCli::Relics(args) => match args.command {
+            RelicsCommand::Create(args) => {
+                let schema = validate(&ValidateArgs {
+                    directory: args.directory.clone(),
+                    format: ReportFormat::Text,
+                })?;
+                create_relic_by_name(&schema, &args)?;
+            }
+        },
+        CargoCli::Files(args) => {
+            let schema = validate(&ValidateArgs {
+                directory: args.directory.clone(),
+                format: ReportFormat::Text,
+            })?;
+            list_files(&schema)?;
+        }
+        CargoCli::Rename(args) => {
+            let schema = validate(&ValidateArgs {
+                directory: args.directory.clone(),
+                format: ReportFormat::Text,
+            })?;
+            rename_project(&schema, &args)?;
+        }
+        CargoCli::Copyright(args) => {
+            let schema = validate(&ValidateArgs {
+                directory: args.directory.clone(),
+                format: ReportFormat::Text,
+            })?;
+            change_copyright(&schema, &args)?;
         }
     }
 
@@ -100,7 +178,13 @@ fn main() -> std::io::Result<()> {
 
 mod automatrons;
 mod cli;
+mod error;
+mod logger;
+mod manifest;
 mod models;
+mod relationships;
 mod relics;
 mod schema;
+mod settings;
 mod synthetics;
+mod vfs;
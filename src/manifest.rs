@@ -0,0 +1,361 @@
+// Copyright © 2024 Navarrotech
+
+// .anubis/manifest.json lists every file Anubis owns, with the hash of what Anubis last wrote to it
+// It's how Anubis knows which files were edited by hand, and which automatrons are left over
+// after a model is removed from Anubis.yaml
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::AnubisError;
+use crate::logger::{info, warning};
+use crate::vfs::{read_file, remove_generated_file, write_file, FileAction, FileChange, FileClass};
+
+const MANIFEST_FILE: &str = ".anubis/manifest.json";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    pub class: FileClass,
+    // Sha256 of the content Anubis last wrote
+    pub hash: String,
+    // The Anubis version that wrote it
+    pub version: String,
+    // The command that wrote it, i.e. "init" or "generate"
+    pub source: String,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Manifest {
+    // Project relative path -> entry
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileStatus {
+    Unmodified,
+    Modified,
+    Missing,
+}
+
+impl fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileStatus::Unmodified => f.pad("unmodified"),
+            FileStatus::Modified => f.pad("modified"),
+            FileStatus::Missing => f.pad("missing"),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn read(install_directory: &Path) -> Result<Manifest, AnubisError> {
+        let manifest_path = install_directory.join(MANIFEST_FILE);
+        let Some(content) = read_file(&manifest_path) else {
+            return Ok(Manifest::default());
+        };
+
+        let parsed = json::parse(&content)
+            .map_err(|error| AnubisError::invalid_data(&manifest_path, error))?;
+
+        let mut files = BTreeMap::new();
+        for (path, entry) in parsed["files"].entries() {
+            let Some(class) = entry["class"].as_str().and_then(FileClass::from_name) else {
+                continue;
+            };
+
+            files.insert(
+                path.to_string(),
+                ManifestEntry {
+                    class,
+                    hash: entry["hash"].to_string(),
+                    version: entry["version"].to_string(),
+                    source: entry["source"].to_string(),
+                },
+            );
+        }
+
+        Ok(Manifest { files })
+    }
+
+    pub fn write(&self, install_directory: &Path) -> Result<(), AnubisError> {
+        let mut files = json::JsonValue::new_object();
+        for (path, entry) in self.files.iter() {
+            files[path.as_str()] = json::object! {
+                class: entry.class.to_string(),
+                hash: entry.hash.clone(),
+                version: entry.version.clone(),
+                source: entry.source.clone(),
+            };
+        }
+
+        write_file(
+            &install_directory.join(MANIFEST_FILE),
+            &json::stringify_pretty(json::object! { files: files }, 2),
+        )
+    }
+
+    // Updates the manifest with every file a command wrote, skipped or deleted
+    pub fn record(&mut self, install_directory: &Path, changes: &[FileChange], source: &str) {
+        for change in changes {
+            let path = get_manifest_path(install_directory, &change.path);
+
+            match change.action {
+                FileAction::Delete => {
+                    self.files.remove(&path);
+                }
+                // A skipped relic keeps what Anubis originally wrote to it
+                FileAction::Skip if self.files.contains_key(&path) => (),
+                _ => {
+                    self.files.insert(
+                        path,
+                        ManifestEntry {
+                            class: change.class,
+                            hash: hash_content(&change.after),
+                            version: env!("CARGO_PKG_VERSION").to_string(),
+                            source: source.to_string(),
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn get_status(&self, install_directory: &Path, path: &str) -> FileStatus {
+        let Some(content) = read_file(&install_directory.join(path)) else {
+            return FileStatus::Missing;
+        };
+
+        match self.files.get(path) {
+            Some(entry) if entry.hash == hash_content(&content) => FileStatus::Unmodified,
+            _ => FileStatus::Modified,
+        }
+    }
+}
+
+// Reads the manifest, records a command's changes and writes it back
+pub fn update_manifest(
+    install_directory: &Path,
+    changes: &[FileChange],
+    source: &str,
+) -> Result<(), AnubisError> {
+    let mut manifest = Manifest::read(install_directory)?;
+    manifest.record(install_directory, changes, source);
+    manifest.write(install_directory)
+}
+
+// Deletes automatrons that `generate` wrote last time, but didn't write this time
+// Must run while the vfs is recording, after every generator ran
+pub fn remove_orphaned_automatrons(
+    install_directory: &Path,
+    changes: &[FileChange],
+) -> Result<(), AnubisError> {
+    let manifest = Manifest::read(install_directory)?;
+    let written: Vec<String> = changes
+        .iter()
+        .map(|change| get_manifest_path(install_directory, &change.path))
+        .collect();
+
+    for (path, entry) in manifest.files.iter() {
+        if entry.class != FileClass::Automatron
+            || entry.source != "generate"
+            || written.contains(path)
+        {
+            continue;
+        }
+
+        match manifest.get_status(install_directory, path) {
+            FileStatus::Unmodified => {
+                info!("Removing {path}, it's no longer generated");
+                remove_generated_file(FileClass::Automatron, &install_directory.join(path))?;
+            }
+            FileStatus::Modified => {
+                warning!("{path} is no longer generated, but it was edited so it was kept");
+            }
+            FileStatus::Missing => (),
+        }
+    }
+
+    Ok(())
+}
+
+// Automatrons whose content no longer matches what Anubis last wrote
+pub fn get_edited_automatrons(install_directory: &Path) -> Result<Vec<String>, AnubisError> {
+    let manifest = Manifest::read(install_directory)?;
+
+    Ok(manifest
+        .files
+        .iter()
+        .filter(|(path, entry)| {
+            entry.class == FileClass::Automatron
+                && manifest.get_status(install_directory, path) == FileStatus::Modified
+        })
+        .map(|(path, _)| path.clone())
+        .collect())
+}
+
+// Copies files into .anubis/backups/<timestamp>/, keeping their project relative paths
+pub fn backup_files(
+    install_directory: &Path,
+    paths: &[String],
+    timestamp: &str,
+) -> Result<PathBuf, AnubisError> {
+    let backup_directory = install_directory.join(".anubis/backups").join(timestamp);
+
+    for path in paths {
+        if let Some(content) = read_file(&install_directory.join(path)) {
+            write_file(&backup_directory.join(path), &content)?;
+        }
+    }
+
+    Ok(backup_directory)
+}
+
+pub fn hash_content(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+// Project relative, with forward slashes so the manifest is the same on every platform
+pub fn get_manifest_path(install_directory: &Path, file_path: &Path) -> String {
+    let local_path: PathBuf = file_path
+        .strip_prefix(install_directory)
+        .unwrap_or(file_path)
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect();
+
+    local_path
+        .iter()
+        .map(|part| part.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod manifest_tests {
+    use super::*;
+    use crate::vfs::{start_recording, stop_recording, write_generated_file};
+    use tempfile::tempdir;
+
+    #[test]
+    fn records_survive_a_round_trip() {
+        let temp_directory = tempdir().unwrap().into_path();
+
+        start_recording(false);
+        write_generated_file(
+            FileClass::Automatron,
+            &temp_directory.join("./proto/root.proto"),
+            "root",
+        )
+        .unwrap();
+        let changes = stop_recording();
+        update_manifest(&temp_directory, &changes, "generate").unwrap();
+
+        let manifest = Manifest::read(&temp_directory).unwrap();
+        let entry = manifest.files.get("proto/root.proto").unwrap();
+        assert_eq!(entry.class, FileClass::Automatron);
+        assert_eq!(entry.hash, hash_content("root"));
+        assert_eq!(entry.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(entry.source, "generate");
+    }
+
+    #[test]
+    fn hand_edits_are_detected() {
+        let temp_directory = tempdir().unwrap().into_path();
+        let file_path = temp_directory.join("api/src/main.rs");
+
+        start_recording(false);
+        write_generated_file(FileClass::Automatron, &file_path, "fn main() {}").unwrap();
+        update_manifest(&temp_directory, &stop_recording(), "generate").unwrap();
+
+        let manifest = Manifest::read(&temp_directory).unwrap();
+        assert_eq!(
+            manifest.get_status(&temp_directory, "api/src/main.rs"),
+            FileStatus::Unmodified
+        );
+
+        std::fs::write(&file_path, "fn main() { edited() }").unwrap();
+        assert_eq!(
+            manifest.get_status(&temp_directory, "api/src/main.rs"),
+            FileStatus::Modified
+        );
+
+        std::fs::remove_file(&file_path).unwrap();
+        assert_eq!(
+            manifest.get_status(&temp_directory, "api/src/main.rs"),
+            FileStatus::Missing
+        );
+    }
+
+    #[test]
+    fn orphaned_automatrons_are_removed_unless_edited() {
+        let temp_directory = tempdir().unwrap().into_path();
+        let kept = temp_directory.join("proto/structs/user.proto");
+        let orphan = temp_directory.join("proto/structs/account.proto");
+        let edited = temp_directory.join("proto/structs/bank.proto");
+
+        start_recording(false);
+        write_generated_file(FileClass::Automatron, &kept, "user").unwrap();
+        write_generated_file(FileClass::Automatron, &orphan, "account").unwrap();
+        write_generated_file(FileClass::Automatron, &edited, "bank").unwrap();
+        update_manifest(&temp_directory, &stop_recording(), "generate").unwrap();
+        std::fs::write(&edited, "bank, edited").unwrap();
+
+        // The next run only generates the user model
+        start_recording(false);
+        write_generated_file(FileClass::Automatron, &kept, "user").unwrap();
+        remove_orphaned_automatrons(&temp_directory, &crate::vfs::get_recorded_changes()).unwrap();
+        let changes = stop_recording();
+        update_manifest(&temp_directory, &changes, "generate").unwrap();
+
+        assert!(kept.exists());
+        assert!(!orphan.exists());
+        assert!(edited.exists());
+
+        let manifest = Manifest::read(&temp_directory).unwrap();
+        assert!(manifest.files.contains_key("proto/structs/user.proto"));
+        assert!(!manifest.files.contains_key("proto/structs/account.proto"));
+    }
+
+    #[test]
+    fn edited_automatrons_can_be_backed_up() {
+        let temp_directory = tempdir().unwrap().into_path();
+        let edited = temp_directory.join("api/src/main.rs");
+
+        start_recording(false);
+        write_generated_file(FileClass::Automatron, &edited, "generated").unwrap();
+        write_generated_file(
+            FileClass::Automatron,
+            &temp_directory.join("api/src/config.rs"),
+            "generated",
+        )
+        .unwrap();
+        write_generated_file(
+            FileClass::Relic,
+            &temp_directory.join("api/src/custom.rs"),
+            "generated",
+        )
+        .unwrap();
+        update_manifest(&temp_directory, &stop_recording(), "generate").unwrap();
+
+        std::fs::write(&edited, "edited").unwrap();
+        std::fs::write(temp_directory.join("api/src/custom.rs"), "edited").unwrap();
+
+        // Relics are meant to be edited
+        let edited_automatrons = get_edited_automatrons(&temp_directory).unwrap();
+        assert_eq!(edited_automatrons, vec!["api/src/main.rs"]);
+
+        let backup_directory =
+            backup_files(&temp_directory, &edited_automatrons, "20240101-000000").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(backup_directory.join("api/src/main.rs")).unwrap(),
+            "edited"
+        );
+        assert!(backup_directory.ends_with(".anubis/backups/20240101-000000"));
+    }
+}
@@ -1,24 +1,325 @@
 // Copyright © 2024 Navarrotech
 
-#[derive(PartialEq)]
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::cli::parse::SCHEMA_FILE;
+use crate::relationships::ReferentialAction;
+
+// Written in Anubis.yaml as `string`, `decimal(12, 2)`, `list<uuid>` and so on
+#[derive(Debug, Clone, PartialEq)]
 pub enum ModelKind {
     String,
     Number,
     Float,
     Boolean,
     DateTime,
+    Date,
+    // Exact numbers, unconstrained when no precision is given
+    Decimal {
+        precision: Option<u32>,
+        scale: Option<u32>,
+    },
+    // An exact amount together with its ISO 4217 currency code
+    Money,
+    Uuid,
+    Json,
+    Bytes,
+    List(Box<ModelKind>),
+}
+
+// Kinds without parameters, in the order they're suggested when an unknown kind is used
+const KIND_NAMES: &[&str] = &[
+    "string", "number", "float", "boolean", "datetime", "date", "decimal", "money", "uuid", "json",
+    "bytes", "list",
+];
+
+// Money is stored as a composite type, created by the first migration that needs it
+pub const MONEY_SQL_TYPE: &str = "anubis_money";
+
+impl ModelKind {
+    // The PostgreSQL type used for this kind, string lengths are applied by the snapshot
+    pub fn sql_type(&self) -> String {
+        match self {
+            ModelKind::String => String::from("TEXT"),
+            ModelKind::Number => String::from("INTEGER"),
+            ModelKind::Float => String::from("DOUBLE PRECISION"),
+            ModelKind::Boolean => String::from("BOOLEAN"),
+            ModelKind::DateTime => String::from("TIMESTAMPTZ"),
+            ModelKind::Date => String::from("DATE"),
+            ModelKind::Decimal { precision, scale } => match (precision, scale) {
+                (Some(precision), Some(scale)) => format!("NUMERIC({precision}, {scale})"),
+                (Some(precision), None) => format!("NUMERIC({precision})"),
+                _ => String::from("NUMERIC"),
+            },
+            ModelKind::Money => String::from(MONEY_SQL_TYPE),
+            ModelKind::Uuid => String::from("UUID"),
+            ModelKind::Json => String::from("JSONB"),
+            ModelKind::Bytes => String::from("BYTEA"),
+            ModelKind::List(kind) => format!("{}[]", kind.sql_type()),
+        }
+    }
+
+    // The Rust type used for this kind in the generated api/ crate
+    pub fn rust_type(&self) -> String {
+        match self {
+            ModelKind::String => String::from("String"),
+            ModelKind::Number => String::from("i32"),
+            ModelKind::Float => String::from("f64"),
+            ModelKind::Boolean => String::from("bool"),
+            ModelKind::DateTime => String::from("chrono::DateTime<chrono::Utc>"),
+            ModelKind::Date => String::from("chrono::NaiveDate"),
+            ModelKind::Decimal { .. } => String::from("rust_decimal::Decimal"),
+            ModelKind::Money => String::from("crate::utility::money::Money"),
+            ModelKind::Uuid => String::from("uuid::Uuid"),
+            ModelKind::Json => String::from("serde_json::Value"),
+            ModelKind::Bytes => String::from("Vec<u8>"),
+            ModelKind::List(kind) => format!("Vec<{}>", kind.rust_type()),
+        }
+    }
+
+    // The proto3 type used for this kind in generated protobufs
+    // Decimals are sent as strings so no precision is lost on the way
+    pub fn proto_type(&self) -> String {
+        match self {
+            ModelKind::String => String::from("string"),
+            ModelKind::Number => String::from("int32"),
+            ModelKind::Float => String::from("double"),
+            ModelKind::Boolean => String::from("bool"),
+            ModelKind::DateTime => String::from("google.protobuf.Timestamp"),
+            ModelKind::Date => String::from("string"),
+            ModelKind::Decimal { .. } => String::from("string"),
+            ModelKind::Money => String::from("common.Money"),
+            ModelKind::Uuid => String::from("string"),
+            ModelKind::Json => String::from("google.protobuf.Value"),
+            ModelKind::Bytes => String::from("bytes"),
+            ModelKind::List(kind) => format!("repeated {}", kind.proto_type()),
+        }
+    }
+
+    // The file a protobuf using this kind has to import
+    pub fn proto_import(&self) -> Option<&'static str> {
+        match self {
+            ModelKind::DateTime => Some("google/protobuf/timestamp.proto"),
+            ModelKind::Json => Some("google/protobuf/struct.proto"),
+            ModelKind::Money => Some("common.proto"),
+            ModelKind::List(kind) => kind.proto_import(),
+            _ => None,
+        }
+    }
+
+    // The TypeScript type of this kind in JSON responses from the api
+    pub fn typescript_type(&self) -> String {
+        match self {
+            ModelKind::String => String::from("string"),
+            ModelKind::Number | ModelKind::Float => String::from("number"),
+            ModelKind::Boolean => String::from("boolean"),
+            ModelKind::DateTime | ModelKind::Date => String::from("string"),
+            ModelKind::Decimal { .. } => String::from("string"),
+            ModelKind::Money => String::from("{ amount: string, currency: string }"),
+            ModelKind::Uuid => String::from("string"),
+            ModelKind::Json => String::from("unknown"),
+            ModelKind::Bytes => String::from("number[]"),
+            ModelKind::List(kind) => match kind.as_ref() {
+                ModelKind::Money => format!("Array<{}>", kind.typescript_type()),
+                kind => format!("{}[]", kind.typescript_type()),
+            },
+        }
+    }
+
+    // Whether `default:` can be used with this kind, and the value fits it
+    pub fn is_valid_default(&self, value: &str) -> bool {
+        match self {
+            ModelKind::String => true,
+            ModelKind::Number => value.parse::<i32>().is_ok(),
+            ModelKind::Float => value.parse::<f64>().is_ok(),
+            ModelKind::Boolean => value.parse::<bool>().is_ok(),
+            // Plain decimal notation, which Postgres and rust_decimal both read exactly
+            ModelKind::Decimal { .. } => {
+                let digits = value.trim().trim_start_matches('-');
+                let mut parts = digits.splitn(2, '.');
+                let whole = parts.next().unwrap_or_default();
+                let fraction = parts.next().unwrap_or("0");
+                !whole.is_empty()
+                    && !fraction.is_empty()
+                    && whole
+                        .chars()
+                        .chain(fraction.chars())
+                        .all(|c| c.is_ascii_digit())
+            }
+            ModelKind::Date => chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+            ModelKind::Uuid => {
+                value.len() == 36
+                    && value.chars().enumerate().all(|(index, c)| match index {
+                        8 | 13 | 18 | 23 => c == '-',
+                        _ => c.is_ascii_hexdigit(),
+                    })
+            }
+            // Datetimes only default to now with `use:`, structured kinds have no literal form
+            _ => false,
+        }
+    }
+
+    // Kinds that hold a value as a whole, so they can't be compared, keyed or given a default
+    pub fn is_structured(&self) -> bool {
+        matches!(
+            self,
+            ModelKind::Money | ModelKind::Json | ModelKind::Bytes | ModelKind::List(_)
+        )
+    }
+}
+
+impl fmt::Display for ModelKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelKind::String => write!(f, "string"),
+            ModelKind::Number => write!(f, "number"),
+            ModelKind::Float => write!(f, "float"),
+            ModelKind::Boolean => write!(f, "boolean"),
+            ModelKind::DateTime => write!(f, "datetime"),
+            ModelKind::Date => write!(f, "date"),
+            ModelKind::Decimal { precision, scale } => match (precision, scale) {
+                (Some(precision), Some(scale)) => write!(f, "decimal({precision}, {scale})"),
+                (Some(precision), None) => write!(f, "decimal({precision})"),
+                _ => write!(f, "decimal"),
+            },
+            ModelKind::Money => write!(f, "money"),
+            ModelKind::Uuid => write!(f, "uuid"),
+            ModelKind::Json => write!(f, "json"),
+            ModelKind::Bytes => write!(f, "bytes"),
+            ModelKind::List(kind) => write!(f, "list<{kind}>"),
+        }
+    }
+}
+
+impl FromStr for ModelKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+
+        if let Some(inner) = value
+            .strip_prefix("list<")
+            .and_then(|inner| inner.strip_suffix('>'))
+        {
+            // Protobuf can't repeat a repeated field
+            return match inner.parse::<ModelKind>()? {
+                ModelKind::List(_) => Err(String::from(
+                    "lists can't be nested, i.e. `list<list<string>>`",
+                )),
+                kind => Ok(ModelKind::List(Box::new(kind))),
+            };
+        }
+
+        if let Some(arguments) = value
+            .strip_prefix("decimal(")
+            .and_then(|arguments| arguments.strip_suffix(')'))
+        {
+            return parse_decimal(arguments);
+        }
+
+        match value {
+            "string" => Ok(ModelKind::String),
+            "number" => Ok(ModelKind::Number),
+            "float" => Ok(ModelKind::Float),
+            "boolean" => Ok(ModelKind::Boolean),
+            "datetime" => Ok(ModelKind::DateTime),
+            "date" => Ok(ModelKind::Date),
+            "decimal" => Ok(ModelKind::Decimal {
+                precision: None,
+                scale: None,
+            }),
+            "money" => Ok(ModelKind::Money),
+            "uuid" => Ok(ModelKind::Uuid),
+            "json" => Ok(ModelKind::Json),
+            "bytes" => Ok(ModelKind::Bytes),
+            "list" => Err(String::from(
+                "`list` needs the kind of its items, i.e. `list<string>`",
+            )),
+            _ => Err(format!(
+                "unknown variant `{value}`, expected one of `{}`",
+                KIND_NAMES.join("`, `")
+            )),
+        }
+    }
+}
+
+// `decimal(precision)` or `decimal(precision, scale)`, the same limits Postgres has
+fn parse_decimal(arguments: &str) -> Result<ModelKind, String> {
+    let numbers: Vec<u32> = arguments
+        .split(',')
+        .map(|number| number.trim().parse::<u32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| {
+            format!("`decimal({arguments})` must be written as `decimal(precision, scale)`")
+        })?;
+
+    let (precision, scale) = match numbers[..] {
+        [precision] => (precision, None),
+        [precision, scale] => (precision, Some(scale)),
+        _ => {
+            return Err(format!(
+                "`decimal({arguments})` must be written as `decimal(precision, scale)`"
+            ))
+        }
+    };
+
+    if !(1..=1000).contains(&precision) {
+        return Err(format!(
+            "decimal precision must be between 1 and 1000, got {precision}"
+        ));
+    }
+    if scale.is_some_and(|scale| scale > precision) {
+        return Err(format!(
+            "decimal scale can't be larger than its precision ({precision})"
+        ));
+    }
+
+    Ok(ModelKind::Decimal {
+        precision: Some(precision),
+        scale,
+    })
+}
+
+impl<'de> Deserialize<'de> for ModelKind {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(ModelKindVisitor)
+    }
 }
 
-#[derive(PartialEq)]
+// Parsing inside a visitor lets serde_yaml point errors at the `kind:` value itself
+struct ModelKindVisitor;
+
+impl serde::de::Visitor<'_> for ModelKindVisitor {
+    type Value = ModelKind;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a field kind, i.e. `string` or `decimal(12, 2)`")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<ModelKind, E> {
+        value.parse().map_err(E::custom)
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum UseOption {
     Uuid,
     Unique,
+    #[serde(rename = "owner")]
     OwnerLink,
     CreatedAt,
     UpdatedAt,
 }
 
-#[derive(PartialEq)]
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum FormatChoice {
     Email,
     Phone,
@@ -26,12 +327,22 @@ pub enum FormatChoice {
     Secret,
 }
 
-#[derive(PartialEq)]
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum RelationshipMode {
     OneToOne,
     OneToMany,
 }
 
+// A 1-based line and column in a schema file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+// TODO: Not every option is consumed by a generator yet
+#[allow(dead_code)]
 pub struct ModelFields {
     // Core fields, required
     pub name: String,
@@ -43,7 +354,8 @@ pub struct ModelFields {
     // Boolean fields (default false)
     pub primary_key: bool,
     pub required: bool,
-    pub encrypt: bool,
+    // One-way, for values like passwords that are only ever checked, never read back
+    pub hash: bool,
     pub replicate: bool,
     pub unique: bool,
 
@@ -58,9 +370,17 @@ pub struct ModelFields {
     // Misc
     pub replace_all: Option<String>,
     pub on_unknown: Option<String>,
-    pub use_match: Option<String>,
+    pub use_match: Option<Vec<String>>,
     pub use_enum: Option<Vec<String>>,
     pub links: Option<String>,
+    // Makes `links` many-to-many, naming the join table that holds each linked pair
+    pub through: Option<String>,
+    // What happens to this row when the row `links` or `use: owner` points at changes
+    pub on_delete: Option<ReferentialAction>,
+    pub on_update: Option<ReferentialAction>,
+
+    // Where `name:` is written in its schema file
+    pub span: Option<Span>,
 }
 
 impl Default for ModelFields {
@@ -76,29 +396,153 @@ impl Default for ModelFields {
             replace_all: None,
             format: None,
             required: false,
-            encrypt: false,
-            replicate: false,
+            hash: false,
+            replicate: true,
             use_match: None,
             on_unknown: None,
             use_enum: None,
             unique: false,
             links: None,
+            through: None,
+            on_delete: None,
+            on_update: None,
+            span: None,
         }
     }
 }
 
+impl ModelFields {
+    // Values that the server fills in itself, clients never send these
+    pub fn is_generated(&self) -> bool {
+        matches!(
+            self.use_method,
+            Some(UseOption::Uuid) | Some(UseOption::CreatedAt) | Some(UseOption::UpdatedAt)
+        )
+    }
+
+    // Whether the column can hold NULL, fields with a default always get a value on insert
+    pub fn is_nullable(&self) -> bool {
+        !self.required && !self.primary_key && self.default.is_none() && !self.is_generated()
+    }
+
+    // Many-to-many fields live in their join table, they're never a column of their own model
+    pub fn is_column(&self) -> bool {
+        self.through.is_none()
+    }
+
+    // Passwords, secrets and fields with `replicate: false` are never sent to clients
+    pub fn is_private(&self) -> bool {
+        !self.replicate
+            || self.format == Some(FormatChoice::Password)
+            || self.format == Some(FormatChoice::Secret)
+    }
+}
+
 pub struct Models {
     pub name: String,
+    // The schema file it was defined in, Anubis.yaml or a file from its `include:` list
+    pub source: String,
+    // Where the model's key is written in its source file
+    pub span: Option<Span>,
     pub mode: RelationshipMode,
+    pub has_owner: Option<String>,
     pub fields: Vec<ModelFields>,
 }
 
+impl Models {
+    pub fn primary_key(&self) -> Option<&ModelFields> {
+        self.fields.iter().find(|field| field.primary_key)
+    }
+}
+
 impl Default for Models {
     fn default() -> Self {
         Models {
             name: String::new(),
+            source: String::from(SCHEMA_FILE),
+            span: None,
             mode: RelationshipMode::OneToOne,
+            has_owner: None,
             fields: Vec::from([ModelFields::default()]),
         }
     }
 }
+
+// The table behind a `through:` field, one row for each linked pair
+// Its extra columns come from the model `through:` names, if there is one
+pub struct JoinTable<'a> {
+    pub name: String,
+    pub model: &'a Models,
+    pub field: &'a ModelFields,
+    pub linked: &'a Models,
+    pub join_model: Option<&'a Models>,
+    // Join tables are only built for models with a primary key, these are those keys
+    pub model_key: &'a ModelFields,
+    pub linked_key: &'a ModelFields,
+}
+
+impl JoinTable<'_> {
+    // The column pointing back at the model with the `through:` field, i.e. transactions_id
+    pub fn model_column(&self) -> String {
+        format!("{}_{}", self.model.name, self.model_key.name)
+    }
+
+    // The column pointing at the linked model, i.e. tag_id
+    // A model that links to itself names it after the field instead, i.e. friends_id
+    pub fn linked_column(&self) -> String {
+        match self.linked.name == self.model.name {
+            true => format!("{}_{}", self.field.name, self.linked_key.name),
+            false => format!("{}_{}", self.linked.name, self.linked_key.name),
+        }
+    }
+
+    // Columns stored on each link besides the two keys, i.e. when a tag was added
+    pub fn extra_fields(&self) -> Vec<&ModelFields> {
+        match self.join_model {
+            Some(join_model) => join_model
+                .fields
+                .iter()
+                .filter(|field| field.is_column())
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod models_tests {
+    use super::*;
+
+    #[test]
+    fn every_kind_has_a_typescript_type() {
+        let kinds = [
+            (ModelKind::String, "string"),
+            (ModelKind::Number, "number"),
+            (ModelKind::Float, "number"),
+            (ModelKind::Boolean, "boolean"),
+            (ModelKind::DateTime, "string"),
+            (ModelKind::Date, "string"),
+            (
+                ModelKind::Decimal {
+                    precision: Some(10),
+                    scale: Some(2),
+                },
+                "string",
+            ),
+            (ModelKind::Money, "{ amount: string, currency: string }"),
+            (ModelKind::Uuid, "string"),
+            (ModelKind::Json, "unknown"),
+            (ModelKind::Bytes, "number[]"),
+            (ModelKind::List(Box::new(ModelKind::Number)), "number[]"),
+            (ModelKind::List(Box::new(ModelKind::Bytes)), "number[][]"),
+            (
+                ModelKind::List(Box::new(ModelKind::Money)),
+                "Array<{ amount: string, currency: string }>",
+            ),
+        ];
+
+        for (kind, typescript_type) in kinds {
+            assert_eq!(kind.typescript_type(), typescript_type, "{kind:?}");
+        }
+    }
+}
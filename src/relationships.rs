@@ -0,0 +1,316 @@
+// Copyright © 2024 Navarrotech
+
+// The relationship graph between models
+// Every `links` field and every `has_owner` is an edge from the model holding the reference
+// to the model it points at. Foreign keys, sync messages and delete handlers are all built from it
+
+use serde::Deserialize;
+
+use crate::models::{ModelFields, Models, UseOption};
+use crate::schema::AnubisSchema;
+
+// What happens to a row when the row it points at is deleted or its key changes
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferentialAction {
+    Cascade,
+    Restrict,
+    SetNull,
+}
+
+impl ReferentialAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReferentialAction::Cascade => "cascade",
+            ReferentialAction::Restrict => "restrict",
+            ReferentialAction::SetNull => "set_null",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ReferentialAction> {
+        match name {
+            "cascade" => Some(ReferentialAction::Cascade),
+            "restrict" => Some(ReferentialAction::Restrict),
+            "set_null" => Some(ReferentialAction::SetNull),
+            _ => None,
+        }
+    }
+
+    pub fn sql(&self) -> &'static str {
+        match self {
+            ReferentialAction::Cascade => "CASCADE",
+            ReferentialAction::Restrict => "RESTRICT",
+            ReferentialAction::SetNull => "SET NULL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelationshipKind {
+    // A `links: model` field
+    Link,
+    // `has_owner: model.field`, stored in the model's `use: owner` field if it has one
+    Owner,
+}
+
+pub struct Relationship<'a> {
+    pub kind: RelationshipKind,
+    pub model: &'a Models,
+    // The column holding the reference, None when `has_owner` has no `use: owner` field
+    pub field: Option<&'a ModelFields>,
+    pub target: &'a Models,
+    pub target_field: &'a ModelFields,
+    pub on_delete: ReferentialAction,
+    pub on_update: ReferentialAction,
+}
+
+pub struct RelationshipGraph<'a> {
+    pub relationships: Vec<Relationship<'a>>,
+}
+
+impl<'a> RelationshipGraph<'a> {
+    // Edges pointing at a model or field that doesn't exist are left out, validate reports them
+    // Many-to-many fields aren't edges, their join table points at both sides instead
+    pub fn build(schema: &'a AnubisSchema) -> RelationshipGraph<'a> {
+        let mut relationships = vec![];
+
+        for model in schema.models.iter() {
+            for field in model.fields.iter() {
+                let Some(links) = &field.links else {
+                    continue;
+                };
+                if !field.is_column() {
+                    continue;
+                }
+                let Some(target) = schema.get_model(links) else {
+                    continue;
+                };
+                let Some(target_field) = target.primary_key() else {
+                    continue;
+                };
+
+                relationships.push(Relationship {
+                    kind: RelationshipKind::Link,
+                    model,
+                    field: Some(field),
+                    target,
+                    target_field,
+                    on_delete: field.on_delete.unwrap_or(ReferentialAction::Restrict),
+                    on_update: field.on_update.unwrap_or(ReferentialAction::Cascade),
+                });
+            }
+
+            let Some((target, target_field)) = get_owner_target(schema, model) else {
+                continue;
+            };
+            let field = model
+                .fields
+                .iter()
+                .find(|field| field.use_method == Some(UseOption::OwnerLink));
+
+            // Owned rows go with their owner
+            relationships.push(Relationship {
+                kind: RelationshipKind::Owner,
+                model,
+                field,
+                target,
+                target_field,
+                on_delete: field
+                    .and_then(|field| field.on_delete)
+                    .unwrap_or(ReferentialAction::Cascade),
+                on_update: field
+                    .and_then(|field| field.on_update)
+                    .unwrap_or(ReferentialAction::Cascade),
+            });
+        }
+
+        RelationshipGraph { relationships }
+    }
+
+    pub fn get_field_relationship(&self, model: &str, field: &str) -> Option<&Relationship<'a>> {
+        self.relationships.iter().find(|relationship| {
+            relationship.model.name == model
+                && relationship.field.map(|field| field.name.as_str()) == Some(field)
+        })
+    }
+
+    // Edges with a column pointing at this model, i.e. the rows a delete affects
+    pub fn get_referencing(&self, target: &str) -> Vec<&Relationship<'a>> {
+        self.relationships
+            .iter()
+            .filter(|relationship| {
+                relationship.target.name == target && relationship.field.is_some()
+            })
+            .collect()
+    }
+
+    // Models caught in an ownership cycle have no owner, so walking up always ends
+    pub fn get_owner(&self, model: &str) -> Option<&'a Models> {
+        if self.is_in_ownership_cycle(model) {
+            return None;
+        }
+
+        self.relationships
+            .iter()
+            .find(|relationship| {
+                relationship.kind == RelationshipKind::Owner && relationship.model.name == model
+            })
+            .map(|relationship| relationship.target)
+    }
+
+    pub fn get_owned(&self, owner: &str) -> Vec<&'a Models> {
+        self.relationships
+            .iter()
+            .filter(|relationship| relationship.kind == RelationshipKind::Owner)
+            .map(|relationship| relationship.model)
+            .filter(|model| {
+                self.get_owner(&model.name).map(|owner| owner.name.as_str()) == Some(owner)
+            })
+            .collect()
+    }
+
+    // Each cycle is listed once, starting from the model declared first
+    pub fn find_ownership_cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles: Vec<Vec<String>> = vec![];
+
+        for relationship in self.relationships.iter() {
+            if relationship.kind != RelationshipKind::Owner {
+                continue;
+            }
+
+            let cycle = self.get_ownership_path(&relationship.model.name);
+            if cycle.last() != Some(&relationship.model.name) {
+                continue;
+            }
+
+            let members = &cycle[..cycle.len() - 1];
+            let already_found = cycles.iter().any(|found| {
+                found.len() == cycle.len() && members.iter().all(|name| found.contains(name))
+            });
+            if !already_found {
+                cycles.push(cycle);
+            }
+        }
+
+        cycles
+    }
+
+    fn is_in_ownership_cycle(&self, model: &str) -> bool {
+        let path = self.get_ownership_path(model);
+        path.len() > 1 && path.last().map(|name| name.as_str()) == Some(model)
+    }
+
+    // Follows owners up from a model, stopping once a model repeats
+    // i.e. [comment, post, user], or [a, b, a] when a and b own each other
+    fn get_ownership_path(&self, model: &str) -> Vec<String> {
+        let mut path = vec![model.to_string()];
+
+        loop {
+            let current = path.last().expect("the path starts with the model");
+            let Some(owner) = self.relationships.iter().find(|relationship| {
+                relationship.kind == RelationshipKind::Owner && &relationship.model.name == current
+            }) else {
+                return path;
+            };
+
+            let owner = owner.target.name.clone();
+            let repeated = path.contains(&owner);
+            path.push(owner);
+            if repeated {
+                return path;
+            }
+        }
+    }
+}
+
+// `has_owner` is written as `model.field`
+fn get_owner_target<'a>(
+    schema: &'a AnubisSchema,
+    model: &Models,
+) -> Option<(&'a Models, &'a ModelFields)> {
+    let (model_name, field_name) = model.has_owner.as_ref()?.split_once('.')?;
+    let target = schema.get_model(model_name)?;
+    let target_field = target
+        .fields
+        .iter()
+        .find(|field| field.name == field_name)?;
+
+    Some((target, target_field))
+}
+
+#[cfg(test)]
+mod relationships_tests {
+    use super::*;
+    use crate::cli::parse::parse_schema_str;
+    use std::path::PathBuf;
+
+    fn parse(yaml_content: &str) -> AnubisSchema {
+        parse_schema_str(yaml_content, PathBuf::from("/tmp")).unwrap()
+    }
+
+    #[test]
+    fn links_and_owners_become_edges() {
+        let schema = parse(include_str!("../examples/Anubis.yaml"));
+        let graph = RelationshipGraph::build(&schema);
+
+        let preferences = graph.get_field_relationship("user", "preferences").unwrap();
+        assert_eq!(preferences.kind, RelationshipKind::Link);
+        assert_eq!(preferences.target.name, "preferences");
+        assert_eq!(preferences.on_delete, ReferentialAction::Restrict);
+
+        let owner = graph
+            .get_field_relationship("transactions", "user_id")
+            .unwrap();
+        assert_eq!(owner.kind, RelationshipKind::Owner);
+        assert_eq!(owner.on_delete, ReferentialAction::Cascade);
+
+        assert_eq!(
+            graph
+                .get_owned("user")
+                .iter()
+                .map(|model| model.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["preferences", "transactions"]
+        );
+        assert!(graph.find_ownership_cycles().is_empty());
+    }
+
+    #[test]
+    fn finds_ownership_cycles() {
+        let schema = parse(
+            "
+project:
+  name: Test
+models:
+  a:
+    has_owner: b.id
+    fields:
+      - name: id
+  b:
+    has_owner: a.id
+    fields:
+      - name: id
+  c:
+    has_owner: a.id
+    fields:
+      - name: id
+",
+        );
+        let graph = RelationshipGraph::build(&schema);
+
+        assert_eq!(
+            graph.find_ownership_cycles(),
+            vec![vec![
+                String::from("a"),
+                String::from("b"),
+                String::from("a")
+            ]]
+        );
+        // Models in the cycle have no owner, anything they own still does
+        assert!(graph.get_owner("a").is_none());
+        assert_eq!(
+            graph.get_owner("c").map(|model| model.name.as_str()),
+            Some("a")
+        );
+    }
+}
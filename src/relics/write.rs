@@ -1,39 +1,50 @@
 // Copyright © 2024 Navarrotech
 
-use std::path::PathBuf;
+use std::path::Path;
 
-use crate::cli::common::get_comment_type;
+use crate::cli::common::comment_lines;
+use crate::cli::common::get_comment_syntax;
 use crate::cli::common::get_copyright_header;
-use crate::cli::common::get_file_type;
+use crate::error::AnubisError;
 use crate::schema::AnubisSchema;
+use crate::vfs::{
+    file_exists, get_file_name, skip_generated_file, write_generated_file, FileClass,
+};
+
+// Relics are only written once, an existing relic belongs to the user and is never overwritten
+pub fn write_relic(
+    schema: &AnubisSchema,
+    content: &String,
+    file_path: &Path,
+) -> Result<(), AnubisError> {
+    if file_exists(file_path) {
+        skip_generated_file(FileClass::Relic, file_path);
+        return Ok(());
+    }
+
+    create_relic(schema, content, file_path)
+}
 
-pub fn write_relic(schema: &AnubisSchema, content: &String, file_path: &PathBuf) {
+// Writes a relic even if it already exists, used to restore one to its defaults
+pub fn create_relic(
+    schema: &AnubisSchema,
+    content: &String,
+    file_path: &Path,
+) -> Result<(), AnubisError> {
     let mut super_content = content.clone();
 
     // Get file name
-    let file_name = file_path
-        .file_name()
-        .expect("Invalid file path")
-        .to_string_lossy()
-        .to_string();
-
-    let file_type = get_file_type(&file_name);
+    let file_name = get_file_name(file_path)?;
 
-    // Write all parent directories in the path if they don't exist
-    std::fs::create_dir_all(file_path.parent().unwrap()).expect(&format!(
-        "Unable to create parent directories for {file_path}",
-        file_path = file_path.display()
-    ));
-
-    let comment_type = get_comment_type(&file_name);
     let copyright = get_copyright_header(schema, &file_name);
-    let preamble = format!("{comment} This is a generated relic by Anubis. 
-{comment} Relics are files that are only auto-generated once and never touched again by Anubis.
-{comment} You may safely modify this file as much as you want, you are in full control of this file.",
-        comment = comment_type
+    let preamble = comment_lines(
+        &file_name,
+        "This is a generated relic by Anubis. 
+Relics are files that are only auto-generated once and never touched again by Anubis.
+You may safely modify this file as much as you want, you are in full control of this file.",
     );
 
-    if file_type != "md" && file_type != "html" && file_type != "json" && file_type != "txt" {
+    if get_comment_syntax(&file_name).is_some() {
         super_content = format!(
             "{copyright}\n{preamble}\n{content}",
             preamble = preamble,
@@ -43,29 +54,29 @@ pub fn write_relic(schema: &AnubisSchema, content: &String, file_path: &PathBuf)
     }
 
     // Write the content to the file
-    std::fs::write(file_path, super_content).expect(&format!(
-        "Unable to write {file_name} file",
-        file_name = file_name
-    ));
+    write_generated_file(FileClass::Relic, file_path, &super_content)
 }
 
 #[cfg(test)]
 mod relics {
     use super::*;
+    use std::path::PathBuf;
     use tempfile::tempdir;
 
     fn generate_temp_file(content: &String, file_path: &PathBuf) -> PathBuf {
         let temp_directory = tempdir().unwrap().into_path();
 
-        let mut test_schema = AnubisSchema::default();
-        test_schema.project_name = "Anubis Test".to_string();
-        test_schema.copyright_header = String::from("Copyright © {YYYY} Navarrotech");
-        test_schema.copyright_header_formatted = String::from("Copyright © 2024 Navarrotech");
-        test_schema.install_directory = temp_directory.clone();
+        let test_schema = AnubisSchema {
+            project_name: "Anubis Test".to_string(),
+            copyright_header: String::from("Copyright © {YYYY} Navarrotech"),
+            copyright_header_formatted: String::from("Copyright © 2024 Navarrotech"),
+            install_directory: temp_directory.clone(),
+            ..AnubisSchema::default()
+        };
 
         let path_upgraded = temp_directory.clone().join(file_path);
 
-        write_relic(&test_schema, &content, &path_upgraded);
+        write_relic(&test_schema, content, &path_upgraded).unwrap();
 
         path_upgraded
     }
@@ -141,4 +152,23 @@ mod relics {
         assert!(file_contents.starts_with("// Copyright © 2024 Navarrotech\n"));
         assert!(file_contents.contains("let foo = String::from(\"noop\");"));
     }
+
+    #[test]
+    fn existing_relics_are_never_overwritten() {
+        let file_path = generate_temp_file(&String::from("default"), &PathBuf::from("custom.rs"));
+        std::fs::write(&file_path, "user content").unwrap();
+
+        let test_schema = AnubisSchema {
+            install_directory: file_path.parent().unwrap().to_path_buf(),
+            ..AnubisSchema::default()
+        };
+
+        write_relic(&test_schema, &String::from("new default"), &file_path).unwrap();
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "user content");
+
+        create_relic(&test_schema, &String::from("new default"), &file_path).unwrap();
+        assert!(std::fs::read_to_string(&file_path)
+            .unwrap()
+            .ends_with("new default"));
+    }
 }
@@ -1,6 +1,8 @@
 // Copyright © 2024 Navarrotech
 
-use crate::models::Models;
+use crate::cli::install::{get_package_manager, PackageManager};
+use crate::models::{JoinTable, Models};
+use crate::settings::Settings;
 use std::path::PathBuf;
 
 pub struct AnubisSchema {
@@ -12,12 +14,14 @@ pub struct AnubisSchema {
     pub copyright_header: String,
     pub copyright_header_formatted: String,
 
+    pub settings: Settings,
     pub models: Vec<Models>,
 }
 
 impl Default for AnubisSchema {
     fn default() -> Self {
-        let default_install_directory = std::env::current_dir().unwrap();
+        // If the current directory can't be read, an empty path still resolves relative to it
+        let default_install_directory = std::env::current_dir().unwrap_or_default();
         AnubisSchema {
             project_name: String::from(""),
             description: String::from(""),
@@ -25,7 +29,66 @@ impl Default for AnubisSchema {
             install_directory: default_install_directory,
             copyright_header: String::from(""),
             copyright_header_formatted: String::from(""),
+            settings: Settings::default(),
             models: Vec::new(),
         }
     }
 }
+
+impl AnubisSchema {
+    // The package manager generated files run the frontend with, see `get_package_manager`
+    pub fn get_package_manager(&self) -> PackageManager {
+        get_package_manager(
+            &self.install_directory.join("frontend"),
+            self.settings.package_manager,
+        )
+    }
+
+    pub fn get_model(&self, name: &str) -> Option<&Models> {
+        self.models.iter().find(|model| model.name == name)
+    }
+
+    // Models named by a `through:` field only add columns to their join table,
+    // they don't get a table, routes or messages of their own
+    pub fn is_join_model(&self, name: &str) -> bool {
+        self.models.iter().any(|model| {
+            model
+                .fields
+                .iter()
+                .any(|field| field.through.as_deref() == Some(name))
+        })
+    }
+
+    // Links to a missing model, or between models without a primary key, are reported by validate
+    pub fn get_join_tables(&self) -> Vec<JoinTable<'_>> {
+        let mut join_tables = vec![];
+
+        for model in self.models.iter() {
+            for field in model.fields.iter() {
+                let (Some(through), Some(links)) = (&field.through, &field.links) else {
+                    continue;
+                };
+                let Some(linked) = self.get_model(links) else {
+                    continue;
+                };
+                let (Some(model_key), Some(linked_key)) =
+                    (model.primary_key(), linked.primary_key())
+                else {
+                    continue;
+                };
+
+                join_tables.push(JoinTable {
+                    name: through.clone(),
+                    model,
+                    field,
+                    linked,
+                    join_model: self.get_model(through),
+                    model_key,
+                    linked_key,
+                });
+            }
+        }
+
+        join_tables
+    }
+}
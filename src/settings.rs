@@ -0,0 +1,91 @@
+// Copyright © 2024 Navarrotech
+
+// The `settings:` block of Anubis.yaml
+// Choices made once at init (CI provider, package manager, ...) live here,
+// so every later generate makes the same choice without asking again
+
+use serde::Deserialize;
+
+use crate::cli::install::PackageManager;
+use crate::cli::relics::cicd::CICDProvider;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScalingMode {
+    // One api instance, which runs the migrations when it starts
+    #[default]
+    Vertical,
+    // Several api instances behind the same address
+    Horizontal,
+}
+
+impl ScalingMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScalingMode::Vertical => "vertical",
+            ScalingMode::Horizontal => "horizontal",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Styling {
+    #[default]
+    Bulma,
+    // Only the theme variables and fonts, bring your own CSS framework
+    Plain,
+}
+
+impl Styling {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Styling::Bulma => "bulma",
+            Styling::Plain => "plain",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub scaling: ScalingMode,
+    // The api runs on a serverless platform, which starts and stops instances on its own
+    pub serverless_api: bool,
+    // Generates the JSON CRUD routes under /api, turn off to only sync over protobufs
+    pub json_api: bool,
+    // None picks whichever lockfile the frontend already has, or yarn
+    pub package_manager: Option<PackageManager>,
+    pub ci: CICDProvider,
+    pub styling: Styling,
+    // The language the frontend starts in, i.e. `en` or `pt-BR`
+    pub default_locale: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            scaling: ScalingMode::default(),
+            serverless_api: false,
+            json_api: true,
+            package_manager: None,
+            ci: CICDProvider::GitHubActions,
+            styling: Styling::default(),
+            default_locale: String::from("en"),
+        }
+    }
+}
+
+// A language code with an optional region, i.e. `en`, `fil` or `pt-BR`
+pub fn is_locale(value: &str) -> bool {
+    let (language, region) = match value.split_once('-') {
+        Some((language, region)) => (language, Some(region)),
+        None => (value, None),
+    };
+
+    let is_language =
+        (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_lowercase());
+    let is_region = region
+        .is_none_or(|region| region.len() == 2 && region.chars().all(|c| c.is_ascii_uppercase()));
+
+    is_language && is_region
+}
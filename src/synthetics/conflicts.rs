@@ -0,0 +1,193 @@
+// Copyright © 2024 Navarrotech
+
+// When a synthetic file can't be merged cleanly, Anubis writes git-style conflict hunks into it
+// and records the file in .anubis/conflicts.json until the user resolves it
+
+use std::path::Path;
+
+use crate::error::AnubisError;
+use crate::synthetics::merge::MergeChunk;
+use crate::vfs::{read_file, remove_file, write_file};
+
+pub const CONFLICT_START: &str = "<<<<<<< anubis";
+pub const CONFLICT_SEPARATOR: &str = "=======";
+pub const CONFLICT_END: &str = ">>>>>>> yours";
+
+const CONFLICTS_FILE: &str = ".anubis/conflicts.json";
+
+// Joins merged chunks back into file content, writing conflict markers around unresolved hunks
+pub fn format_merge(chunks: &[MergeChunk]) -> String {
+    let mut lines: Vec<&str> = vec![];
+
+    for chunk in chunks {
+        match chunk {
+            MergeChunk::Resolved(resolved) => lines.extend(resolved),
+            MergeChunk::Conflict { generated, user } => {
+                lines.push(CONFLICT_START);
+                lines.extend(generated);
+                lines.push(CONFLICT_SEPARATOR);
+                lines.extend(user);
+                lines.push(CONFLICT_END);
+            }
+        }
+    }
+
+    lines.join("\n")
+}
+
+// The inverse of format_merge, used to read back a file that still contains conflict markers
+pub fn parse_conflict_markers(content: &str) -> Vec<MergeChunk<'_>> {
+    let mut chunks = vec![];
+    let mut resolved = vec![];
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        if line != CONFLICT_START {
+            resolved.push(line);
+            continue;
+        }
+
+        let mut generated = vec![];
+        let mut user = vec![];
+        let mut in_user_section = false;
+        let mut closed = false;
+
+        for line in lines.by_ref() {
+            match line {
+                CONFLICT_SEPARATOR if !in_user_section => in_user_section = true,
+                CONFLICT_END if in_user_section => {
+                    closed = true;
+                    break;
+                }
+                _ if in_user_section => user.push(line),
+                _ => generated.push(line),
+            }
+        }
+
+        // An unterminated hunk isn't a conflict, keep the lines as they are
+        if !closed {
+            resolved.push(CONFLICT_START);
+            resolved.extend(generated);
+            if in_user_section {
+                resolved.push(CONFLICT_SEPARATOR);
+                resolved.extend(user);
+            }
+            continue;
+        }
+
+        if !resolved.is_empty() {
+            chunks.push(MergeChunk::Resolved(resolved));
+            resolved = vec![];
+        }
+        chunks.push(MergeChunk::Conflict { generated, user });
+    }
+
+    if !resolved.is_empty() {
+        chunks.push(MergeChunk::Resolved(resolved));
+    }
+
+    chunks
+}
+
+// Returns the project relative paths of every file with unresolved conflicts
+pub fn get_conflicts(install_directory: &Path) -> Result<Vec<String>, AnubisError> {
+    let conflicts_path = install_directory.join(CONFLICTS_FILE);
+    let Some(content) = read_file(&conflicts_path) else {
+        return Ok(vec![]);
+    };
+
+    let parsed =
+        json::parse(&content).map_err(|error| AnubisError::invalid_data(&conflicts_path, error))?;
+
+    Ok(parsed
+        .members()
+        .filter_map(|member| member.as_str().map(|s| s.to_string()))
+        .collect())
+}
+
+pub fn record_conflict(install_directory: &Path, local_file_path: &str) -> Result<(), AnubisError> {
+    let mut conflicts = get_conflicts(install_directory)?;
+    if !conflicts.iter().any(|conflict| conflict == local_file_path) {
+        conflicts.push(local_file_path.to_string());
+        write_conflicts(install_directory, &conflicts)?;
+    }
+
+    Ok(())
+}
+
+pub fn clear_conflict(install_directory: &Path, local_file_path: &str) -> Result<(), AnubisError> {
+    let mut conflicts = get_conflicts(install_directory)?;
+    if conflicts.iter().any(|conflict| conflict == local_file_path) {
+        conflicts.retain(|conflict| conflict != local_file_path);
+        write_conflicts(install_directory, &conflicts)?;
+    }
+
+    Ok(())
+}
+
+fn write_conflicts(install_directory: &Path, conflicts: &[String]) -> Result<(), AnubisError> {
+    let conflicts_path = install_directory.join(CONFLICTS_FILE);
+
+    if conflicts.is_empty() {
+        return remove_file(&conflicts_path);
+    }
+
+    let content = json::stringify_pretty(conflicts.to_vec(), 2);
+    write_file(&conflicts_path, &content)
+}
+
+#[cfg(test)]
+mod conflicts_tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn conflict_markers_round_trip() {
+        let chunks = vec![
+            MergeChunk::Resolved(vec!["a"]),
+            MergeChunk::Conflict {
+                generated: vec!["anubis"],
+                user: vec!["user", "user2"],
+            },
+            MergeChunk::Resolved(vec!["c"]),
+        ];
+
+        let content = format_merge(&chunks);
+
+        assert_eq!(
+            content,
+            "a\n<<<<<<< anubis\nanubis\n=======\nuser\nuser2\n>>>>>>> yours\nc"
+        );
+        assert_eq!(parse_conflict_markers(&content), chunks);
+    }
+
+    #[test]
+    fn unterminated_conflict_markers_are_kept() {
+        let content = "a\n<<<<<<< anubis\nb";
+
+        assert_eq!(
+            parse_conflict_markers(content),
+            vec![MergeChunk::Resolved(vec!["a", "<<<<<<< anubis", "b"])]
+        );
+    }
+
+    #[test]
+    fn records_and_clears_conflicts() {
+        let temp_directory = tempdir().unwrap().into_path();
+
+        record_conflict(&temp_directory, "frontend/index.html").unwrap();
+        record_conflict(&temp_directory, "frontend/index.html").unwrap();
+        record_conflict(&temp_directory, "Dockerfile").unwrap();
+
+        assert_eq!(
+            get_conflicts(&temp_directory).unwrap(),
+            vec!["frontend/index.html", "Dockerfile"]
+        );
+
+        clear_conflict(&temp_directory, "frontend/index.html").unwrap();
+        clear_conflict(&temp_directory, "Dockerfile").unwrap();
+
+        assert!(get_conflicts(&temp_directory).unwrap().is_empty());
+        assert!(!temp_directory.join(CONFLICTS_FILE).exists());
+    }
+}
@@ -0,0 +1,309 @@
+// Copyright © 2024 Navarrotech
+
+// Three-way merging for synthetic files
+//
+// Anubis keeps the last generated output of every synthetic in .anubis/cache
+// That cached copy is the common ancestor (base) of two descendants:
+//  - The file on disk, which the user may have edited
+//  - The freshly generated content, which Anubis wants to write
+//
+// Each descendant is diffed against the base into a list of edits,
+// and edits that don't touch the same base lines are applied together.
+
+#[derive(Debug, PartialEq)]
+pub enum MergeChunk<'a> {
+    // Lines that merged cleanly
+    Resolved(Vec<&'a str>),
+    // Lines where the user and Anubis both changed the same region differently
+    Conflict {
+        generated: Vec<&'a str>,
+        user: Vec<&'a str>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    User,
+    Generated,
+}
+
+// Replaces base[start..end] with the given lines
+// When start == end, this is a pure insertion before base[start]
+struct Edit<'a> {
+    start: usize,
+    end: usize,
+    lines: Vec<&'a str>,
+    side: Side,
+}
+
+pub fn three_way_merge<'a>(
+    base: &'a str,
+    user: &'a str,
+    generated: &'a str,
+) -> Vec<MergeChunk<'a>> {
+    let base_lines: Vec<&str> = base.lines().collect();
+    let user_lines: Vec<&str> = user.lines().collect();
+    let generated_lines: Vec<&str> = generated.lines().collect();
+
+    let mut edits = diff_lines(&base_lines, &user_lines, Side::User);
+    edits.extend(diff_lines(&base_lines, &generated_lines, Side::Generated));
+    edits.sort_by_key(|edit| (edit.start, edit.end));
+
+    let mut chunks: Vec<MergeChunk> = vec![];
+    let mut cursor = 0;
+    let mut index = 0;
+
+    while index < edits.len() {
+        // Group every edit that touches the same region of the base
+        let cluster_start = edits[index].start;
+        let mut cluster_end = edits[index].end;
+        let mut cluster_length = 1;
+
+        while index + cluster_length < edits.len()
+            && overlaps(cluster_start, cluster_end, &edits[index + cluster_length])
+        {
+            cluster_end = cluster_end.max(edits[index + cluster_length].end);
+            cluster_length += 1;
+        }
+
+        let cluster = &edits[index..index + cluster_length];
+        index += cluster_length;
+
+        // Unchanged lines before this cluster
+        push_resolved(&mut chunks, &base_lines[cursor..cluster_start]);
+        cursor = cluster_end;
+
+        let user_version =
+            apply_edits(&base_lines, cluster_start, cluster_end, cluster, Side::User);
+        let generated_version = apply_edits(
+            &base_lines,
+            cluster_start,
+            cluster_end,
+            cluster,
+            Side::Generated,
+        );
+
+        let user_changed = cluster.iter().any(|edit| edit.side == Side::User);
+        let generated_changed = cluster.iter().any(|edit| edit.side == Side::Generated);
+
+        if !user_changed {
+            push_resolved(&mut chunks, &generated_version);
+        } else if !generated_changed || user_version == generated_version {
+            push_resolved(&mut chunks, &user_version);
+        } else {
+            chunks.push(MergeChunk::Conflict {
+                generated: generated_version,
+                user: user_version,
+            });
+        }
+    }
+
+    push_resolved(&mut chunks, &base_lines[cursor..]);
+
+    chunks
+}
+
+// Two edits are independent if they replace disjoint base lines
+// An insertion on the boundary of a replacement is independent of it,
+// but an insertion inside of a replacement or two insertions at the same spot are not
+fn overlaps(start: usize, end: usize, edit: &Edit) -> bool {
+    if start == end && edit.start == edit.end {
+        return start == edit.start;
+    }
+    if start == end {
+        return edit.start < start && start < edit.end;
+    }
+    if edit.start == edit.end {
+        return start < edit.start && edit.start < end;
+    }
+
+    edit.start < end && start < edit.end
+}
+
+// Rebuilds base[start..end] as seen from one side of the merge
+fn apply_edits<'a>(
+    base_lines: &[&'a str],
+    start: usize,
+    end: usize,
+    edits: &[Edit<'a>],
+    side: Side,
+) -> Vec<&'a str> {
+    let mut result = vec![];
+    let mut cursor = start;
+
+    for edit in edits.iter().filter(|edit| edit.side == side) {
+        result.extend_from_slice(&base_lines[cursor..edit.start]);
+        result.extend_from_slice(&edit.lines);
+        cursor = edit.end;
+    }
+    result.extend_from_slice(&base_lines[cursor..end]);
+
+    result
+}
+
+fn push_resolved<'a>(chunks: &mut Vec<MergeChunk<'a>>, lines: &[&'a str]) {
+    if lines.is_empty() {
+        return;
+    }
+
+    match chunks.last_mut() {
+        Some(MergeChunk::Resolved(resolved)) => resolved.extend_from_slice(lines),
+        _ => chunks.push(MergeChunk::Resolved(lines.to_vec())),
+    }
+}
+
+// The edits that turn `base` into `other`, as (start, end, lines) where base[start..end] is replaced by lines
+pub fn diff<'a>(base: &[&'a str], other: &[&'a str]) -> Vec<(usize, usize, Vec<&'a str>)> {
+    diff_lines(base, other, Side::Generated)
+        .into_iter()
+        .map(|edit| (edit.start, edit.end, edit.lines))
+        .collect()
+}
+
+// Produces the minimal list of edits that turn `base` into `other`,
+// based on the longest common subsequence of lines between them
+fn diff_lines<'a>(base: &[&'a str], other: &[&'a str], side: Side) -> Vec<Edit<'a>> {
+    // Trim the common prefix and suffix, synthetic files are usually mostly unchanged
+    let mut prefix = 0;
+    while prefix < base.len() && prefix < other.len() && base[prefix] == other[prefix] {
+        prefix += 1;
+    }
+
+    let mut suffix = 0;
+    while suffix < base.len() - prefix
+        && suffix < other.len() - prefix
+        && base[base.len() - 1 - suffix] == other[other.len() - 1 - suffix]
+    {
+        suffix += 1;
+    }
+
+    let base_middle = &base[prefix..base.len() - suffix];
+    let other_middle = &other[prefix..other.len() - suffix];
+
+    // lengths[i][j] is the LCS length of base_middle[i..] and other_middle[j..]
+    let columns = other_middle.len() + 1;
+    let mut lengths = vec![0u32; (base_middle.len() + 1) * columns];
+    for i in (0..base_middle.len()).rev() {
+        for j in (0..other_middle.len()).rev() {
+            lengths[i * columns + j] = if base_middle[i] == other_middle[j] {
+                lengths[(i + 1) * columns + j + 1] + 1
+            } else {
+                lengths[(i + 1) * columns + j].max(lengths[i * columns + j + 1])
+            };
+        }
+    }
+
+    let mut edits = vec![];
+    let mut pending: Option<Edit> = None;
+    let (mut i, mut j) = (0, 0);
+
+    while i < base_middle.len() || j < other_middle.len() {
+        if i < base_middle.len() && j < other_middle.len() && base_middle[i] == other_middle[j] {
+            edits.extend(pending.take());
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        let edit = pending.get_or_insert(Edit {
+            start: prefix + i,
+            end: prefix + i,
+            lines: vec![],
+            side,
+        });
+
+        let remove_from_base = j == other_middle.len()
+            || (i < base_middle.len()
+                && lengths[(i + 1) * columns + j] >= lengths[i * columns + j + 1]);
+
+        if remove_from_base {
+            edit.end += 1;
+            i += 1;
+        } else {
+            edit.lines.push(other_middle[j]);
+            j += 1;
+        }
+    }
+    edits.extend(pending);
+
+    edits
+}
+
+#[cfg(test)]
+mod merge_tests {
+    use super::*;
+
+    fn merge_to_string(base: &str, user: &str, generated: &str) -> String {
+        three_way_merge(base, user, generated)
+            .iter()
+            .flat_map(|chunk| match chunk {
+                MergeChunk::Resolved(lines) => lines.clone(),
+                MergeChunk::Conflict { .. } => panic!("Unexpected merge conflict"),
+            })
+            .collect::<Vec<&str>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn keeps_user_insertions() {
+        let result = merge_to_string("a\nb\nc", "a\nb\nb1\nc", "a\nb\nc\nd");
+
+        assert_eq!(result, "a\nb\nb1\nc\nd");
+    }
+
+    #[test]
+    fn keeps_user_modifications() {
+        let result = merge_to_string(
+            "apples\nbananas\ncats",
+            "apples\nbananas and bats\ncats",
+            "apples\nbananas\ncats\ndogs",
+        );
+
+        assert_eq!(result, "apples\nbananas and bats\ncats\ndogs");
+    }
+
+    #[test]
+    fn keeps_user_deletions() {
+        let result = merge_to_string("a\nb\nc\nd", "a\nc\nd", "a\nb\nc\nd\ne");
+
+        assert_eq!(result, "a\nc\nd\ne");
+    }
+
+    #[test]
+    fn applies_generated_changes_next_to_user_changes() {
+        let result = merge_to_string(
+            "apples\npears\nbananas\noats\ncats\ndogs\nairplanes",
+            "apples\npears\nbananas fabulouso\noats\ncats and bats\ndogs\nairplanes nippy",
+            "apples\npears\nbananas\ncrackers\noats\ncats\nyankees\ndogs\nmarxism?\nairplanes",
+        );
+
+        assert_eq!(
+            result,
+            "apples\npears\nbananas fabulouso\ncrackers\noats\ncats and bats\nyankees\ndogs\nmarxism?\nairplanes nippy"
+        );
+    }
+
+    #[test]
+    fn identical_changes_are_applied_once() {
+        let result = merge_to_string("a\nb", "a\nx\nb", "a\nx\nb");
+
+        assert_eq!(result, "a\nx\nb");
+    }
+
+    #[test]
+    fn reports_conflicting_changes() {
+        let chunks = three_way_merge("a\nb\nc", "a\nuser\nc", "a\nanubis\nc");
+
+        assert_eq!(
+            chunks,
+            vec![
+                MergeChunk::Resolved(vec!["a"]),
+                MergeChunk::Conflict {
+                    generated: vec!["anubis"],
+                    user: vec!["user"],
+                },
+                MergeChunk::Resolved(vec!["c"]),
+            ]
+        );
+    }
+}
@@ -1,3 +1,5 @@
 // Copyright © 2024 Navarrotech
 
+pub mod conflicts;
+pub mod merge;
 pub mod write;
@@ -1,37 +1,39 @@
 // Copyright © 2024 Navarrotech
 
-use std::path::PathBuf;
+use std::path::Path;
 
-use crate::cli::common::get_comment_type;
+use crate::cli::common::comment_lines;
+use crate::cli::common::get_comment_syntax;
 use crate::cli::common::get_copyright_header;
-use crate::cli::common::get_file_type;
+use crate::error::AnubisError;
+use crate::logger::warning;
 use crate::schema::AnubisSchema;
-
-pub fn write_synthetic(schema: &AnubisSchema, content: &String, file_path: &PathBuf) {
+use crate::synthetics::conflicts::clear_conflict;
+use crate::synthetics::conflicts::format_merge;
+use crate::synthetics::conflicts::record_conflict;
+use crate::synthetics::merge::three_way_merge;
+use crate::synthetics::merge::MergeChunk;
+use crate::vfs::{get_file_name, read_file, write_file, write_generated_file, FileClass};
+
+pub fn write_synthetic(
+    schema: &AnubisSchema,
+    content: &String,
+    file_path: &Path,
+) -> Result<(), AnubisError> {
     let mut super_content = content.clone();
 
     // Get file name
-    let file_name = file_path
-        .file_name()
-        .expect("Invalid file path")
-        .to_string_lossy()
-        .to_string();
-
-    let file_type = get_file_type(&file_name);
-
-    // Write all parent directories in the path if they don't exist
-    std::fs::create_dir_all(file_path.parent().unwrap()).expect(&format!(
-        "Unable to create parent directories for {file_path}",
-        file_path = file_path.display()
-    ));
+    let file_name = get_file_name(file_path)?;
 
-    let comment_type = get_comment_type(&file_name);
     let copyright = get_copyright_header(schema, &file_name);
-    let preamble = format!("{comment} This is a synthetic Anubis file. 
-{comment} Synthetic are files that Anubis writes and manages, but Anubis will always honor your changes.
-{comment} Take caution while editing this file, it may change in the future & you are in partial control of this file.", comment = comment_type);
-
-    if file_type != "md" && file_type != "html" && file_type != "json" {
+    let preamble = comment_lines(
+        &file_name,
+        "This is a synthetic Anubis file. 
+Synthetic are files that Anubis writes and manages, but Anubis will always honor your changes.
+Take caution while editing this file, it may change in the future & you are in partial control of this file.",
+    );
+
+    if get_comment_syntax(&file_name).is_some() {
         super_content = format!(
             "{copyright}\n{preamble}\n{content}",
             preamble = preamble,
@@ -40,74 +42,50 @@ pub fn write_synthetic(schema: &AnubisSchema, content: &String, file_path: &Path
         );
     }
 
-    let local_file_path = file_path
-        .to_string_lossy()
-        .to_string()
-        .replace(&schema.install_directory.to_string_lossy().to_string(), "");
+    if !super_content.ends_with('\n') {
+        super_content.push('\n');
+    }
 
     let anubis_analysis_file = schema
         .install_directory
-        .clone()
         .join(".anubis/cache")
-        .clone()
-        .join(&local_file_path.clone()[1..]);
+        .join(get_local_path(schema, file_path));
 
     // Past content is the last output from a synthetic file without user changes
-    let mut past_content = String::from("");
-    if anubis_analysis_file.exists() {
-        past_content = std::fs::read_to_string(&anubis_analysis_file).unwrap();
-    }
+    let past_content = read_file(&anubis_analysis_file).unwrap_or_default();
+    let current_content = read_file(file_path).unwrap_or_default();
 
-    let mut current_content = String::from("");
-    if file_path.exists() {
-        current_content = std::fs::read_to_string(file_path).unwrap();
-    }
+    let local_file_path = get_local_path(schema, file_path);
 
     let mut super_content_with_user_changes = super_content.clone();
+    let mut has_conflicts = false;
     if !past_content.is_empty() && !current_content.is_empty() {
-        // Compare each line of the past content with the super content
-        // If the line is the same, that means there has been no user edits.
-        // If the line is different, it could mean the following:
-        // 1. The user added a line
-        // 2. The user changed the line
-        // 3. The user deleted the line
-        // 4. This is old content and the the new content is supposed to add to it
-        // 5. This is old content and the new content is supposed to replace it
-
-        // TODO: Figure out how to handle respecting user deleted lines
-
-        // let past_lines: Vec<&str> = past_content.lines().collect();
-        // let current_lines: Vec<&str> = current_content.lines().collect();
-        // let super_lines: Vec<&str> = super_content.lines().collect();
-
-        // let mut merged_lines = vec![];
-        // let mut past_index = 0;
-        // let mut current_index = 0;
-        // let mut super_index = 0;
-
-        // while past_index < past_lines.len() && current_index < current_lines.len() {
-        //     if past_lines[past_index] == current_lines[current_index] {
-        //         merged_lines.push(super_lines[super_index].to_string());
-        //         past_index += 1;
-        //         current_index += 1;
-        //         super_index += 1;
-        //     } else if past_index < past_lines.len() {
-        //         merged_lines.push(current_lines[current_index].to_string());
-        //         current_index += 1;
-        //     }
-        // }
-
-        // while current_index < current_lines.len() {
-        //     merged_lines.push(current_lines[current_index].to_string());
-        //     current_index += 1;
-        // }
-
-        // while super_index < super_lines.len() {
-        //     merged_lines.push(super_lines[super_index].to_string());
-        //     super_index += 1;
-        // }
-
-        // super_content_with_user_changes = merged_lines.join("\n");
+        // The cached past content is the common ancestor of both the user's file and the new content
+        // Any line the user added, changed or deleted since then is carried over into the new content
+        // When the user and Anubis both changed the same lines, conflict markers are written instead
+        let chunks = three_way_merge(&past_content, &current_content, &super_content);
+
+        has_conflicts = chunks
+            .iter()
+            .any(|chunk| matches!(chunk, MergeChunk::Conflict { .. }));
+
+        super_content_with_user_changes = format_merge(&chunks);
+    }
+
+    if has_conflicts {
+        warning!(
+            "Merge conflict in {file_path}, run `anubis resolve` to resolve it",
+            file_path = local_file_path.display()
+        );
+        record_conflict(
+            &schema.install_directory,
+            &local_file_path.to_string_lossy(),
+        )?;
+    } else {
+        clear_conflict(
+            &schema.install_directory,
+            &local_file_path.to_string_lossy(),
+        )?;
     }
 
     if !super_content_with_user_changes.ends_with('\n') {
@@ -115,42 +93,45 @@ pub fn write_synthetic(schema: &AnubisSchema, content: &String, file_path: &Path
     }
 
     // Write the content to the file
-    std::fs::write(file_path, super_content_with_user_changes).expect(&format!(
-        "Unable to write {file_name} file",
-        file_name = file_name
-    ));
+    write_generated_file(
+        FileClass::Synthetic,
+        file_path,
+        &super_content_with_user_changes,
+    )?;
 
     // After the core file is written, we re-write the past_content .anubis file with the non-user edited content
+    write_file(&anubis_analysis_file, &super_content)
+}
 
-    // Write all parent directories in the path if they don't exist
-    // std::fs::create_dir_all(anubis_analysis_file.parent().unwrap()).expect(&format!(
-    //     "Unable to create parent directories for {file_path}",
-    //     file_path = anubis_analysis_file.display()
-    // ));
-
-    // std::fs::write(anubis_analysis_file, super_content).expect(&format!(
-    //     "Unable to write {file_name} file",
-    //     file_name = file_name
-    // ));
+// The path of the file relative to the project root
+// The cache mirrors the project's directory structure under .anubis/cache
+fn get_local_path<'a>(schema: &AnubisSchema, file_path: &'a Path) -> &'a Path {
+    file_path
+        .strip_prefix(&schema.install_directory)
+        .unwrap_or(file_path)
 }
 
 #[cfg(test)]
 mod synthetics {
     use super::*;
+    use crate::synthetics::conflicts::get_conflicts;
+    use std::path::PathBuf;
     use tempfile::tempdir;
 
     fn generate_temp_file(content: &String, file_path: &PathBuf) -> PathBuf {
         let temp_directory = tempdir().unwrap().into_path();
 
-        let mut test_schema = AnubisSchema::default();
-        test_schema.project_name = "Anubis Test".to_string();
-        test_schema.copyright_header = String::from("Copyright © {YYYY} Navarrotech");
-        test_schema.copyright_header_formatted = String::from("Copyright © 2024 Navarrotech");
-        test_schema.install_directory = temp_directory.clone();
+        let test_schema = AnubisSchema {
+            project_name: "Anubis Test".to_string(),
+            copyright_header: String::from("Copyright © {YYYY} Navarrotech"),
+            copyright_header_formatted: String::from("Copyright © 2024 Navarrotech"),
+            install_directory: temp_directory.clone(),
+            ..AnubisSchema::default()
+        };
 
         let path_upgraded = temp_directory.clone().join(file_path);
 
-        write_synthetic(&test_schema, &content, &path_upgraded);
+        write_synthetic(&test_schema, content, &path_upgraded).unwrap();
 
         path_upgraded
     }
@@ -231,11 +212,13 @@ mod synthetics {
     fn first_time_synthetic_write() {
         let temp_directory = tempdir().unwrap().into_path();
 
-        let mut test_schema = AnubisSchema::default();
-        test_schema.project_name = "Anubis Test".to_string();
-        test_schema.copyright_header = String::from("Copyright © {YYYY} Navarrotech");
-        test_schema.copyright_header_formatted = String::from("Copyright © 2024 Navarrotech");
-        test_schema.install_directory = temp_directory.clone();
+        let test_schema = AnubisSchema {
+            project_name: "Anubis Test".to_string(),
+            copyright_header: String::from("Copyright © {YYYY} Navarrotech"),
+            copyright_header_formatted: String::from("Copyright © 2024 Navarrotech"),
+            install_directory: temp_directory.clone(),
+            ..AnubisSchema::default()
+        };
 
         let file_path = temp_directory.clone().join("test.rs");
 
@@ -259,7 +242,7 @@ mod synthetics {
 </html>",
         );
 
-        write_synthetic(&test_schema, &content, &file_path);
+        write_synthetic(&test_schema, &content, &file_path).unwrap();
 
         assert!(file_path.exists());
 
@@ -269,189 +252,165 @@ mod synthetics {
         assert!(file_contents.contains(&content));
     }
 
-    //     #[test]
-    //     fn synthetic_write_respect_user_addins() {
-    //         let temp_directory = tempdir().unwrap().into_path();
-
-    //         let mut test_schema = AnubisSchema::default();
-    //         test_schema.project_name = "Anubis Test".to_string();
-    //         test_schema.copyright_header = String::from("Copyright © {YYYY} Navarrotech");
-    //         test_schema.copyright_header_formatted = String::from("Copyright © 2024 Navarrotech");
-    //         test_schema.install_directory = temp_directory.clone();
-
-    //         let file_path = temp_directory.clone().join("addon_test.md");
-
-    //         // Original content is missing content-type tag and seo tags
-    //         let original_content = String::from("
-    // a
-    // b
-    // c
-    // ");
-
-    //         // The user added a link tag and meta tags
-    //         let user_modified_content = String::from("
-    // a
-    // b
-    // b1
-    // c
-    // ");
-
-    //         let updated_content = String::from("
-    // a
-    // b
-    // c
-    // d
-    // ");
-
-    //         let expected_result = String::from("
-    // a
-    // b
-    // b1
-    // c
-    // d
-    // ");
-
-    //         // Write the original content
-    //         write_synthetic(&test_schema, &original_content, &file_path);
-
-    //         assert!(file_path.exists());
-
-    //         // Update with user content
-    //         std::fs::write(&file_path, &user_modified_content).expect(
-    //           "Unable to write addon_test.rs file in synthetic writing unit test"
-    //         );
-
-    //         // Update with new content
-    //         write_synthetic(&test_schema, &updated_content, &file_path);
-
-    //         let file_contents = std::fs::read_to_string(file_path).unwrap();
-
-    //         assert_eq!(file_contents, expected_result);
-    //     }
-
-    //     #[test]
-    //     fn synthetic_write_respect_user_addins_with_headers() {
-    //         let temp_directory = tempdir().unwrap().into_path();
-
-    //         let mut test_schema = AnubisSchema::default();
-    //         test_schema.project_name = "Anubis Test".to_string();
-    //         test_schema.copyright_header = String::from("Copyright © {YYYY} Navarrotech");
-    //         test_schema.copyright_header_formatted = String::from("Copyright © 2024 Navarrotech");
-    //         test_schema.install_directory = temp_directory.clone();
-
-    //         let file_path = temp_directory.clone().join("addon_test.rs");
-
-    //         // Original content is missing content-type tag and seo tags
-    //         let original_content = String::from("
-    // a
-    // b
-    // c
-    // ");
-
-    //         // The user added a link tag and meta tags
-    //         let user_modified_content = String::from("// Copyright © 2024 Navarrotech
-
-    // // This is a synthetic Anubis file.
-    // // Synthetic are files that Anubis writes and manages, but Anubis will always honor your changes.
-    // // Take caution while editing this file, it may change in the future & you are in partial control of this file.
-
-    // a
-    // b
-    // b1
-    // c
-    // ");
-
-    //         let updated_content = String::from("
-    // a
-    // b
-    // c
-    // d
-    // ");
-
-    //         let expected_result = String::from("// Copyright © 2024 Navarrotech
-
-    // // This is a synthetic Anubis file.
-    // // Synthetic are files that Anubis writes and manages, but Anubis will always honor your changes.
-    // // Take caution while editing this file, it may change in the future & you are in partial control of this file.
-
-    // a
-    // b
-    // b1
-    // c
-    // d
-    // ");
-
-    //         // Write the original content
-    //         write_synthetic(&test_schema, &original_content, &file_path);
-
-    //         assert!(file_path.exists());
-
-    //         // Update with user content
-    //         std::fs::write(&file_path, &user_modified_content).expect(
-    //           "Unable to write addon_test.rs file in synthetic writing unit test"
-    //         );
-
-    //         // Update with new content
-    //         write_synthetic(&test_schema, &updated_content, &file_path);
-
-    //         let file_contents = std::fs::read_to_string(file_path).unwrap();
-
-    //         assert_eq!(file_contents, expected_result);
-    //     }
-
-    //     #[test]
-    //     fn synthetic_write_respect_user_changes() {
-    //         let temp_directory = tempdir().unwrap().into_path();
-
-    //         let mut test_schema = AnubisSchema::default();
-    //         test_schema.project_name = "Anubis Test".to_string();
-    //         test_schema.copyright_header = String::from("Copyright © {YYYY} Navarrotech");
-    //         test_schema.copyright_header_formatted = String::from("Copyright © 2024 Navarrotech");
-    //         test_schema.install_directory = temp_directory.clone();
+    fn mock_schema() -> AnubisSchema {
+        AnubisSchema {
+            project_name: "Anubis Test".to_string(),
+            copyright_header: String::from("Copyright © {YYYY} Navarrotech"),
+            copyright_header_formatted: String::from("Copyright © 2024 Navarrotech"),
+            install_directory: tempdir().unwrap().into_path(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn synthetic_write_respect_user_addins() {
+        let test_schema = mock_schema();
+        let file_path = test_schema.install_directory.join("addon_test.txt");
+
+        let original_content = String::from("\na\nb\nc\n");
+
+        // The user added a line
+        let user_modified_content = String::from("\na\nb\nb1\nc\n");
+
+        let updated_content = String::from("\na\nb\nc\nd\n");
+
+        let expected_result = String::from("\na\nb\nb1\nc\nd\n");
+
+        // Write the original content
+        write_synthetic(&test_schema, &original_content, &file_path).unwrap();
+
+        assert!(file_path.exists());
+
+        // Update with user content
+        std::fs::write(&file_path, &user_modified_content)
+            .expect("Unable to write addon_test.txt file in synthetic writing unit test");
+
+        // Update with new content
+        write_synthetic(&test_schema, &updated_content, &file_path).unwrap();
+
+        let file_contents = std::fs::read_to_string(file_path).unwrap();
+
+        assert_eq!(file_contents, expected_result);
+    }
+
+    #[test]
+    fn synthetic_write_respect_user_addins_with_headers() {
+        let test_schema = mock_schema();
+        let file_path = test_schema.install_directory.join("addon_test.rs");
 
-    //         let file_path = temp_directory.clone().join("change_test.md");
+        let original_content = String::from("\na\nb\nc\n");
+        let updated_content = String::from("\na\nb\nc\nd\n");
 
-    //         // Original content is missing content-type tag and seo tags
-    //         let original_content = String::from("
-    // apples
-    // bananas
-    // cats
-    // ");
-
-    //         // The user added a link tag and meta tags
-    //         let user_modified_content = String::from("
-    // apples
-    // bats
-    // cats
-    // ");
-
-    //         let updated_content = String::from("
-    // apples
-    // bananas
-    // cats
-    // ");
-
-    //         let expected_result = String::from("
-    // apples
-    // bats
-    // cats
-    // ");
-
-    //         // Write the original content
-    //         write_synthetic(&test_schema, &original_content, &file_path);
-
-    //         assert!(file_path.exists());
-
-    //         // Update with user content
-    //         std::fs::write(&file_path, &user_modified_content).expect(
-    //           "Unable to write addon_test.rs file in synthetic writing unit test"
-    //         );
-
-    //         // Update with new content
-    //         write_synthetic(&test_schema, &updated_content, &file_path);
-
-    //         let file_contents = std::fs::read_to_string(file_path).unwrap();
-
-    //         assert_eq!(file_contents, expected_result);
-    //     }
+        // Write the original content
+        write_synthetic(&test_schema, &original_content, &file_path).unwrap();
+
+        assert!(file_path.exists());
+
+        // The user added a line below the generated headers
+        let written_content = std::fs::read_to_string(&file_path).unwrap();
+        let user_modified_content = written_content.replace("b\nc\n", "b\nb1\nc\n");
+        let expected_result = written_content.replace("b\nc\n", "b\nb1\nc\nd\n");
+
+        std::fs::write(&file_path, &user_modified_content)
+            .expect("Unable to write addon_test.rs file in synthetic writing unit test");
+
+        // Update with new content
+        write_synthetic(&test_schema, &updated_content, &file_path).unwrap();
+
+        let file_contents = std::fs::read_to_string(file_path).unwrap();
+
+        assert!(file_contents.starts_with("// Copyright © 2024 Navarrotech\n"));
+        assert_eq!(file_contents, expected_result);
+    }
+
+    #[test]
+    fn synthetic_write_respect_user_changes() {
+        let test_schema = mock_schema();
+        let file_path = test_schema.install_directory.join("change_test.txt");
+
+        let original_content = String::from("\napples\nbananas\ncats\n");
+
+        // The user changed a line
+        let user_modified_content = String::from("\napples\nbats\ncats\n");
+
+        let updated_content = String::from("\napples\nbananas\ncats\n");
+
+        let expected_result = String::from("\napples\nbats\ncats\n");
+
+        // Write the original content
+        write_synthetic(&test_schema, &original_content, &file_path).unwrap();
+
+        assert!(file_path.exists());
+
+        // Update with user content
+        std::fs::write(&file_path, &user_modified_content)
+            .expect("Unable to write change_test.txt file in synthetic writing unit test");
+
+        // Update with new content
+        write_synthetic(&test_schema, &updated_content, &file_path).unwrap();
+
+        let file_contents = std::fs::read_to_string(file_path).unwrap();
+
+        assert_eq!(file_contents, expected_result);
+    }
+
+    #[test]
+    fn synthetic_write_respect_user_deletions() {
+        let test_schema = mock_schema();
+        let file_path = test_schema.install_directory.join("deletion_test.txt");
+
+        write_synthetic(&test_schema, &String::from("a\nb\nc\n"), &file_path).unwrap();
+
+        // The user deleted a line
+        std::fs::write(&file_path, "a\nc\n")
+            .expect("Unable to write deletion_test.txt file in synthetic writing unit test");
+
+        write_synthetic(&test_schema, &String::from("a\nb\nc\nd\n"), &file_path).unwrap();
+
+        let file_contents = std::fs::read_to_string(&file_path).unwrap();
+
+        assert_eq!(file_contents, "a\nc\nd\n");
+    }
+
+    #[test]
+    fn synthetic_write_persists_the_baseline() {
+        let test_schema = mock_schema();
+        let file_path = test_schema
+            .install_directory
+            .join("frontend/cache_test.txt");
+
+        write_synthetic(&test_schema, &String::from("a\nb\n"), &file_path).unwrap();
+
+        let cache_path = test_schema
+            .install_directory
+            .join(".anubis/cache/frontend/cache_test.txt");
+
+        assert!(cache_path.exists());
+        assert_eq!(std::fs::read_to_string(cache_path).unwrap(), "a\nb\n");
+    }
+
+    #[test]
+    fn synthetic_write_marks_conflicts() {
+        let test_schema = mock_schema();
+        let file_path = test_schema.install_directory.join("conflict_test.txt");
+
+        write_synthetic(&test_schema, &String::from("a\nb\nc\n"), &file_path).unwrap();
+
+        // The user and Anubis both change the same line
+        std::fs::write(&file_path, "a\nuser\nc\n")
+            .expect("Unable to write conflict_test.txt file in synthetic writing unit test");
+
+        write_synthetic(&test_schema, &String::from("a\nanubis\nc\n"), &file_path).unwrap();
+
+        let file_contents = std::fs::read_to_string(&file_path).unwrap();
+
+        assert_eq!(
+            file_contents,
+            "a\n<<<<<<< anubis\nanubis\n=======\nuser\n>>>>>>> yours\nc\n"
+        );
+        assert_eq!(
+            get_conflicts(&test_schema.install_directory).unwrap(),
+            vec!["conflict_test.txt"]
+        );
+    }
 }
@@ -0,0 +1,145 @@
+// Copyright © 2024 Navarrotech
+
+// Unified diffs (like `git diff`) between what's on disk and what Anubis would write
+
+use crate::synthetics::merge::diff;
+
+// Lines of unchanged context shown around each change
+const CONTEXT: usize = 3;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+pub fn create_unified_diff(path: &str, before: &str, after: &str, color: bool) -> String {
+    let before_lines: Vec<&str> = before.lines().collect();
+    let after_lines: Vec<&str> = after.lines().collect();
+    let edits = diff(&before_lines, &after_lines);
+
+    let paint = |code: &str, line: String| match color {
+        true => format!("{code}{line}{RESET}"),
+        false => line,
+    };
+
+    let mut output = vec![
+        paint(BOLD, format!("--- a/{path}")),
+        paint(BOLD, format!("+++ b/{path}")),
+    ];
+
+    // How many lines the new file has gained (or lost) before the current hunk
+    let mut offset: isize = 0;
+    let mut index = 0;
+
+    while index < edits.len() {
+        // Edits close enough to share their context are shown in one hunk
+        let mut last = index;
+        while last + 1 < edits.len() && edits[last + 1].0 - edits[last].1 <= CONTEXT * 2 {
+            last += 1;
+        }
+
+        let old_start = edits[index].0.saturating_sub(CONTEXT);
+        let old_end = (edits[last].1 + CONTEXT).min(before_lines.len());
+        let new_start = (old_start as isize + offset) as usize;
+
+        let mut lines = vec![];
+        let mut cursor = old_start;
+        for (start, end, replacement) in edits[index..=last].iter() {
+            for line in before_lines[cursor..*start].iter() {
+                lines.push(format!(" {line}"));
+            }
+            for line in before_lines[*start..*end].iter() {
+                lines.push(paint(RED, format!("-{line}")));
+            }
+            for line in replacement.iter() {
+                lines.push(paint(GREEN, format!("+{line}")));
+            }
+
+            offset += replacement.len() as isize - (end - start) as isize;
+            cursor = *end;
+        }
+        for line in before_lines[cursor..old_end].iter() {
+            lines.push(format!(" {line}"));
+        }
+
+        let old_length = old_end - old_start;
+        let new_length = (old_end as isize + offset) as usize - new_start;
+
+        output.push(paint(
+            CYAN,
+            format!(
+                "@@ -{} +{} @@",
+                format_range(old_start, old_length),
+                format_range(new_start, new_length)
+            ),
+        ));
+        output.extend(lines);
+
+        index = last + 1;
+    }
+
+    output.join("\n")
+}
+
+// Hunk ranges are 1-based, and an empty range points at the line before it
+fn format_range(start: usize, length: usize) -> String {
+    match length {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{length}", start + 1),
+    }
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use super::*;
+
+    #[test]
+    fn shows_changes_with_context() {
+        let before = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let after = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n10\n11\n";
+
+        assert_eq!(
+            create_unified_diff("file.txt", before, after, false),
+            "--- a/file.txt
++++ b/file.txt
+@@ -2,9 +2,10 @@
+ 2
+ 3
+ 4
+-5
++five
+ 6
+ 7
+ 8
+ 9
+ 10
++11"
+        );
+    }
+
+    #[test]
+    fn new_files_are_all_additions() {
+        assert_eq!(
+            create_unified_diff("new.txt", "", "a\nb\n", false),
+            "--- a/new.txt\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+a\n+b"
+        );
+    }
+
+    #[test]
+    fn distant_changes_get_separate_hunks() {
+        let before: String = (1..=20).map(|line| format!("{line}\n")).collect();
+        let after: String = (1..=20)
+            .map(|line| match line {
+                2 => String::from("two\n"),
+                19 => String::from("nineteen\n"),
+                _ => format!("{line}\n"),
+            })
+            .collect();
+
+        let diff = create_unified_diff("file.txt", &before, &after, false);
+        assert!(diff.contains("@@ -1,5 +1,5 @@"));
+        assert!(diff.contains("@@ -16,5 +16,5 @@"));
+    }
+}
@@ -0,0 +1,383 @@
+// Copyright © 2024 Navarrotech
+
+// Every file Anubis writes goes through here instead of std::fs directly
+// Normally writes go straight to disk, but while recording, every write is collected so
+// `generate --dry-run` and `generate --diff` can show what would change before anything is touched
+//
+// Recording is thread local, so tests running in parallel never see each other's writes
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::error::AnubisError;
+use crate::logger::{self, Level};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileClass {
+    Automatron,
+    Relic,
+    Synthetic,
+}
+
+impl FileClass {
+    pub fn from_name(name: &str) -> Option<FileClass> {
+        match name {
+            "automatron" => Some(FileClass::Automatron),
+            "relic" => Some(FileClass::Relic),
+            "synthetic" => Some(FileClass::Synthetic),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FileClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileClass::Automatron => f.pad("automatron"),
+            FileClass::Relic => f.pad("relic"),
+            FileClass::Synthetic => f.pad("synthetic"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileAction {
+    Create,
+    Update,
+    Unchanged,
+    // Relics that already exist are left alone
+    Skip,
+    // Automatrons that are no longer generated
+    Delete,
+}
+
+impl fmt::Display for FileAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileAction::Create => f.pad("create"),
+            FileAction::Update => f.pad("update"),
+            FileAction::Unchanged => f.pad("unchanged"),
+            FileAction::Skip => f.pad("skip"),
+            FileAction::Delete => f.pad("delete"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub class: FileClass,
+    pub action: FileAction,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Default)]
+struct VirtualFilesystem {
+    recording: bool,
+    // When true, nothing is written to disk and writes are kept in `pending` instead
+    dry_run: bool,
+    // Path -> content, None when the file was removed
+    pending: BTreeMap<PathBuf, Option<String>>,
+    changes: Vec<FileChange>,
+}
+
+thread_local! {
+    static FILESYSTEM: RefCell<VirtualFilesystem> = RefCell::new(VirtualFilesystem::default());
+}
+
+// Starts collecting every generated file written on this thread
+pub fn start_recording(dry_run: bool) {
+    FILESYSTEM.with(|filesystem| {
+        *filesystem.borrow_mut() = VirtualFilesystem {
+            recording: true,
+            dry_run,
+            ..Default::default()
+        }
+    });
+}
+
+// Stops recording, returning every generated file in the order it was written
+pub fn stop_recording() -> Vec<FileChange> {
+    FILESYSTEM.with(|filesystem| std::mem::take(&mut *filesystem.borrow_mut()).changes)
+}
+
+// Every generated file recorded so far, without stopping the recording
+pub fn get_recorded_changes() -> Vec<FileChange> {
+    FILESYSTEM.with(|filesystem| filesystem.borrow().changes.clone())
+}
+
+// Reads a file as it would be on disk, including writes held back by a dry run
+pub fn read_file(file_path: &Path) -> Option<String> {
+    let pending = FILESYSTEM.with(|filesystem| filesystem.borrow().pending.get(file_path).cloned());
+
+    match pending {
+        Some(content) => content,
+        None => std::fs::read_to_string(file_path).ok(),
+    }
+}
+
+pub fn file_exists(file_path: &Path) -> bool {
+    let pending = FILESYSTEM.with(|filesystem| filesystem.borrow().pending.get(file_path).cloned());
+
+    match pending {
+        Some(content) => content.is_some(),
+        None => file_path.exists(),
+    }
+}
+
+// Names of the files directly in a directory as it would be on disk, including a dry run's
+// pending writes and removals, sorted
+pub fn read_dir(directory: &Path) -> Vec<String> {
+    let mut names: BTreeSet<String> = std::fs::read_dir(directory)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_file())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    FILESYSTEM.with(|filesystem| {
+        for (path, content) in filesystem.borrow().pending.iter() {
+            if path.parent() != Some(directory) {
+                continue;
+            }
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+
+            let name = name.to_string_lossy().to_string();
+            match content {
+                Some(_) => names.insert(name),
+                None => names.remove(&name),
+            };
+        }
+    });
+
+    names.into_iter().collect()
+}
+
+// Records a generated file that was deliberately not written
+pub fn skip_generated_file(class: FileClass, file_path: &Path) {
+    let content = read_file(file_path).unwrap_or_default();
+    log_file_event(FileAction::Skip, class, file_path);
+
+    FILESYSTEM.with(|filesystem| {
+        let mut filesystem = filesystem.borrow_mut();
+        if filesystem.recording {
+            filesystem.changes.push(FileChange {
+                path: file_path.to_path_buf(),
+                class,
+                action: FileAction::Skip,
+                before: content.clone(),
+                after: content,
+            });
+        }
+    });
+}
+
+// Writes one of the files Anubis generates for the project, and records it while recording
+pub fn write_generated_file(
+    class: FileClass,
+    file_path: &Path,
+    content: &str,
+) -> Result<(), AnubisError> {
+    let recording = FILESYSTEM.with(|filesystem| filesystem.borrow().recording);
+
+    if recording || logger::is_enabled(Level::Debug) {
+        let before = read_file(file_path);
+        let action = match &before {
+            None => FileAction::Create,
+            Some(before) if before == content => FileAction::Unchanged,
+            Some(_) => FileAction::Update,
+        };
+        log_file_event(action, class, file_path);
+
+        if recording {
+            FILESYSTEM.with(|filesystem| {
+                filesystem.borrow_mut().changes.push(FileChange {
+                    path: file_path.to_path_buf(),
+                    class,
+                    action,
+                    before: before.unwrap_or_default(),
+                    after: content.to_string(),
+                })
+            });
+        }
+    }
+
+    write_file(file_path, content)
+}
+
+// Per file events show with `-v`, files that didn't change only with `-vv`
+fn log_file_event(action: FileAction, class: FileClass, file_path: &Path) {
+    let level = match action {
+        FileAction::Unchanged => Level::Trace,
+        _ => Level::Debug,
+    };
+
+    // Relative to where Anubis was run from, like the paths users type
+    let path = std::env::current_dir()
+        .ok()
+        .and_then(|current_directory| file_path.strip_prefix(current_directory).ok())
+        .unwrap_or(file_path)
+        .display()
+        .to_string();
+
+    logger::log_with(
+        level,
+        &format!("{action} {class} {path}"),
+        json::object! {
+            action: action.to_string(),
+            class: class.to_string(),
+            path: path.as_str(),
+        },
+    );
+}
+
+// Writes Anubis' own bookkeeping files (caches, locks, snapshots), these are never reported
+pub fn write_file(file_path: &Path, content: &str) -> Result<(), AnubisError> {
+    logger::log(Level::Trace, &format!("write {}", file_path.display()));
+    let dry_run = FILESYSTEM.with(|filesystem| filesystem.borrow().dry_run);
+
+    if dry_run {
+        FILESYSTEM.with(|filesystem| {
+            filesystem
+                .borrow_mut()
+                .pending
+                .insert(file_path.to_path_buf(), Some(content.to_string()))
+        });
+        return Ok(());
+    }
+
+    // Write all parent directories in the path if they don't exist
+    if let Some(parent) = file_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|error| AnubisError::io(parent, error))?;
+    }
+
+    std::fs::write(file_path, content).map_err(|error| AnubisError::io(file_path, error))
+}
+
+// Removes a file Anubis generated, and records it while recording
+pub fn remove_generated_file(class: FileClass, file_path: &Path) -> Result<(), AnubisError> {
+    let before = read_file(file_path).unwrap_or_default();
+    log_file_event(FileAction::Delete, class, file_path);
+
+    FILESYSTEM.with(|filesystem| {
+        let mut filesystem = filesystem.borrow_mut();
+        if filesystem.recording {
+            filesystem.changes.push(FileChange {
+                path: file_path.to_path_buf(),
+                class,
+                action: FileAction::Delete,
+                before,
+                after: String::new(),
+            });
+        }
+    });
+
+    remove_file(file_path)
+}
+
+pub fn remove_file(file_path: &Path) -> Result<(), AnubisError> {
+    let dry_run = FILESYSTEM.with(|filesystem| filesystem.borrow().dry_run);
+
+    if dry_run {
+        FILESYSTEM.with(|filesystem| {
+            filesystem
+                .borrow_mut()
+                .pending
+                .insert(file_path.to_path_buf(), None)
+        });
+        return Ok(());
+    }
+
+    if file_path.exists() {
+        std::fs::remove_file(file_path).map_err(|error| AnubisError::io(file_path, error))?;
+    }
+
+    Ok(())
+}
+
+// The file name a writer uses to pick the comment syntax, i.e. `main.rs`
+pub fn get_file_name(file_path: &Path) -> Result<String, AnubisError> {
+    match file_path.file_name() {
+        Some(file_name) => Ok(file_name.to_string_lossy().to_string()),
+        None => Err(AnubisError::invalid_data(
+            file_path,
+            "is not a path to a file",
+        )),
+    }
+}
+
+pub mod diff;
+
+#[cfg(test)]
+mod vfs_tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn dry_runs_never_touch_the_disk() {
+        let temp_directory = tempdir().unwrap().into_path();
+        let file_path = temp_directory.join("nested/file.txt");
+
+        start_recording(true);
+        write_generated_file(FileClass::Relic, &file_path, "hello\n").unwrap();
+
+        // Reads see the pending write, but the disk doesn't
+        assert_eq!(read_file(&file_path), Some(String::from("hello\n")));
+        assert!(!file_path.exists());
+
+        let changes = stop_recording();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].action, FileAction::Create);
+        assert_eq!(changes[0].class, FileClass::Relic);
+
+        // Once stopped, reads go back to the disk
+        assert_eq!(read_file(&file_path), None);
+    }
+
+    #[test]
+    fn recorded_actions_compare_against_the_disk() {
+        let temp_directory = tempdir().unwrap().into_path();
+        let same = temp_directory.join("same.txt");
+        let changed = temp_directory.join("changed.txt");
+        std::fs::write(&same, "a\n").unwrap();
+        std::fs::write(&changed, "a\n").unwrap();
+
+        start_recording(false);
+        write_generated_file(FileClass::Automatron, &same, "a\n").unwrap();
+        write_generated_file(FileClass::Synthetic, &changed, "b\n").unwrap();
+        let changes = stop_recording();
+
+        assert_eq!(changes[0].action, FileAction::Unchanged);
+        assert_eq!(changes[1].action, FileAction::Update);
+        assert_eq!(changes[1].before, "a\n");
+
+        // Without a dry run, the writes still happen
+        assert_eq!(std::fs::read_to_string(&changed).unwrap(), "b\n");
+    }
+
+    #[test]
+    fn directories_include_pending_writes_and_removals() {
+        let temp_directory = tempdir().unwrap().into_path();
+        std::fs::write(temp_directory.join("kept.txt"), "a\n").unwrap();
+        std::fs::write(temp_directory.join("removed.txt"), "a\n").unwrap();
+        std::fs::create_dir(temp_directory.join("nested")).unwrap();
+
+        start_recording(true);
+        write_file(&temp_directory.join("added.txt"), "b\n").unwrap();
+        write_file(&temp_directory.join("nested/deeper.txt"), "b\n").unwrap();
+        remove_file(&temp_directory.join("removed.txt")).unwrap();
+
+        assert_eq!(read_dir(&temp_directory), vec!["added.txt", "kept.txt"]);
+
+        stop_recording();
+        assert_eq!(read_dir(&temp_directory), vec!["kept.txt", "removed.txt"]);
+    }
+}